@@ -155,6 +155,14 @@ impl GigaChatClient {
         self.conversation_history.clear();
     }
 
+    /// Заменяет историю разговора (например, после восстановления из корзины)
+    pub fn set_history(&mut self, history: Vec<Message>) {
+        self.conversation_history = history.into_iter().collect();
+        while self.conversation_history.len() > 10 {
+            self.conversation_history.pop_front();
+        }
+    }
+
     /// Возвращает текущую историю разговора
     pub fn get_history(&self) -> Vec<Message> {
         self.conversation_history.iter().cloned().collect()
@@ -1,7 +1,18 @@
 use std::env;
+use std::path::PathBuf;
+
+use crate::profile::Profile;
+use crate::services::crypto_prices::{find_coin, CRYPTO_VS_CURRENCIES, DEFAULT_CRYPTO_API_URL};
+use crate::services::currency::RateSource;
+use crate::services::stocks::DEFAULT_MOEX_API_URL;
+use crate::services::iso4217;
+use crate::services::weather::{PressureUnit, TemperatureUnit, WeatherUnits, WindUnit};
+use crate::services::weather_alerts::AlertSettings;
 
 #[derive(Debug, Clone)]
 pub struct Config {
+    /// Активный профиль пользователя
+    pub profile: Profile,
     pub gigachat_api_key: Option<String>,
     pub openai_api_key: Option<String>,
     pub use_openai: bool,
@@ -15,11 +26,81 @@ pub struct Config {
     pub gigachat_model: String,
     pub gigachat_temperature: f32,
     pub gigachat_max_tokens: i32,
+    /// Путь к файлу БД (None — ~/.config/clippy/clippy.db; у профилей — в каталоге профиля)
+    pub db_path: Option<PathBuf>,
+    /// Хранить историю только в памяти, не открывая БД
+    pub in_memory_storage: bool,
+    /// Пароль для шифрования содержимого БД (None — БД не шифруется)
+    pub db_passphrase: Option<String>,
+    /// Хранить сообщения не дольше N дней
+    pub retention_max_age_days: Option<i64>,
+    /// Хранить не больше N сообщений
+    pub retention_max_messages: Option<usize>,
+    /// Ограничение размера БД в мегабайтах
+    pub retention_max_db_mb: Option<u64>,
+    /// Через сколько дней очищенные сообщения удаляются из корзины
+    pub trash_retention_days: Option<i64>,
+    /// Период фонового обслуживания БД (минуты)
+    pub maintenance_interval_mins: u64,
+    /// Каталог резервных копий (по умолчанию ~/.config/clippy/backups)
+    pub backup_dir: Option<PathBuf>,
+    /// Период автоматических резервных копий (часы, 0 — выключено)
+    pub backup_interval_hours: u64,
+    /// Сколько последних копий хранить
+    pub backup_keep: usize,
+    /// Город для виджета погоды (None — по часовому поясу)
+    pub weather_city: Option<String>,
+    /// Явные координаты для погоды (CLIPPY_WEATHER_LAT / CLIPPY_WEATHER_LON)
+    pub weather_coordinates: Option<(f32, f32)>,
+    /// Период обновления погоды (минуты)
+    pub weather_refresh_mins: u64,
+    /// Период обновления курсов валют (минуты)
+    pub rates_refresh_mins: u64,
+    /// Основной источник курсов валют (остальные — запасные)
+    pub rates_source: RateSource,
+    /// Базовая валюта курсов по умолчанию (потом меняется командой /currency base)
+    pub base_currency: String,
+    /// Отслеживаемые валюты по умолчанию (потом меняются командой /currency)
+    pub currency_watchlist: Vec<String>,
+    /// CoinGecko-совместимый API цен криптовалют
+    pub crypto_api_url: String,
+    /// Монеты на виджетах (пусто — виджетов криптовалют нет)
+    pub crypto_coins: Vec<String>,
+    /// Валюта цен криптовалют: RUB или USD
+    pub crypto_vs: String,
+    /// Период обновления цен криптовалют (минуты)
+    pub crypto_refresh_mins: u64,
+    /// ISS Московской биржи
+    pub moex_api_url: String,
+    /// Тикеры акций на виджетах (пусто — виджетов акций нет)
+    pub stock_tickers: Vec<String>,
+    /// Период обновления котировок акций (минуты)
+    pub stocks_refresh_mins: u64,
+    /// Озвучивать уведомления о курсах валют и акций
+    pub rate_alerts_speak: bool,
+    /// Единицы температуры, ветра и давления
+    pub weather_units: WeatherUnits,
+    /// Пороги, тихие часы и озвучка предупреждений о погоде
+    pub weather_alerts: AlertSettings,
 }
 
 impl Default for Config {
     fn default() -> Self {
-        dotenv::dotenv().ok();
+        Self::for_profile(Profile::default())
+    }
+}
+
+impl Config {
+    /// Загружает настройки профиля. dotenv не перезаписывает уже заданные
+    /// переменные, поэтому окружение процесса важнее любого `.env`. Профиль
+    /// `default` читает `.env` рабочего каталога (или ближайшего родительского),
+    /// именованный — только `.env` из своего каталога
+    pub fn for_profile(profile: Profile) -> Self {
+        if profile.is_default() {
+            dotenv::dotenv().ok();
+        } else {
+            dotenv::from_path(profile.env_file()).ok();
+        }
 
         let gigachat_api_key = env::var("GIGACHAT_API_KEY").ok();
         let openai_api_key = env::var("OPENAI_API_KEY").ok();
@@ -43,6 +124,222 @@ impl Default for Config {
             .and_then(|v| v.parse::<i32>().ok())
             .unwrap_or(500);
 
+        let db_path = env::var("CLIPPY_DB_PATH")
+            .ok()
+            .filter(|p| !p.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| (!profile.is_default()).then(|| profile.db_path()));
+
+        let in_memory_storage = env::var("CLIPPY_STORAGE")
+            .map(|v| v.to_lowercase() == "memory")
+            .unwrap_or(false);
+
+        let db_passphrase = env::var("CLIPPY_DB_PASSPHRASE")
+            .ok()
+            .filter(|p| !p.is_empty());
+
+        let retention_max_age_days = env::var("CLIPPY_RETENTION_DAYS")
+            .ok()
+            .and_then(|v| v.parse::<i64>().ok());
+
+        let retention_max_messages = env::var("CLIPPY_RETENTION_MAX_MESSAGES")
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok());
+
+        let retention_max_db_mb = env::var("CLIPPY_RETENTION_MAX_DB_MB")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok());
+
+        // 0 — хранить корзину бессрочно
+        let trash_retention_days = match env::var("CLIPPY_TRASH_DAYS").ok().and_then(|v| v.parse::<i64>().ok()) {
+            Some(0) => None,
+            Some(days) => Some(days),
+            None => Some(30),
+        };
+
+        let maintenance_interval_mins = env::var("CLIPPY_MAINTENANCE_INTERVAL_MINS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(60)
+            .max(1);
+
+        let backup_dir = env::var("CLIPPY_BACKUP_DIR")
+            .ok()
+            .filter(|v| !v.trim().is_empty())
+            .map(PathBuf::from);
+
+        let backup_interval_hours = env::var("CLIPPY_BACKUP_INTERVAL_HOURS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(24);
+
+        let backup_keep = env::var("CLIPPY_BACKUP_KEEP")
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+            .unwrap_or(7)
+            .max(1);
+
+        let weather_city = env::var("CLIPPY_WEATHER_CITY")
+            .ok()
+            .filter(|v| !v.trim().is_empty());
+
+        let weather_coordinates = env::var("CLIPPY_WEATHER_LAT")
+            .ok()
+            .and_then(|lat| lat.trim().parse::<f32>().ok())
+            .zip(
+                env::var("CLIPPY_WEATHER_LON")
+                    .ok()
+                    .and_then(|lon| lon.trim().parse::<f32>().ok()),
+            )
+            .filter(|(lat, lon)| lat.abs() <= 90.0 && lon.abs() <= 180.0);
+
+        let weather_refresh_mins = env::var("CLIPPY_WEATHER_REFRESH_MINS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(15)
+            .max(1);
+
+        let rates_refresh_mins = env::var("CLIPPY_RATES_REFRESH_MINS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(60)
+            .max(1);
+
+        let rates_source = env::var("CLIPPY_RATES_SOURCE")
+            .ok()
+            .and_then(|v| RateSource::parse(&v))
+            .unwrap_or_default();
+
+        let base_currency = env::var("CLIPPY_BASE_CURRENCY")
+            .ok()
+            .and_then(|v| iso4217::lookup(v.trim()))
+            .map_or("RUB", |c| c.code)
+            .to_string();
+
+        let mut currency_watchlist: Vec<String> = Vec::new();
+        for code in env::var("CLIPPY_CURRENCIES").unwrap_or_default().split(',') {
+            match iso4217::lookup(code.trim()) {
+                Some(c) if c.code != base_currency && !currency_watchlist.iter().any(|w| w == c.code) => {
+                    currency_watchlist.push(c.code.to_string())
+                }
+                Some(_) => {}
+                None if code.trim().is_empty() => {}
+                None => log::warn!("⚠️ CLIPPY_CURRENCIES: неизвестный код валюты «{}»", code.trim()),
+            }
+        }
+        if currency_watchlist.is_empty() {
+            currency_watchlist = ["USD", "EUR"]
+                .iter()
+                .filter(|code| **code != base_currency)
+                .map(|code| code.to_string())
+                .collect();
+        }
+
+        let crypto_api_url = env::var("CLIPPY_CRYPTO_API_URL")
+            .ok()
+            .filter(|v| !v.trim().is_empty())
+            .unwrap_or_else(|| DEFAULT_CRYPTO_API_URL.to_string());
+
+        let crypto_coins = match env::var("CLIPPY_CRYPTO_COINS") {
+            Ok(v) if v.trim().eq_ignore_ascii_case("off") => Vec::new(),
+            Ok(v) => v
+                .split(',')
+                .map(str::trim)
+                .filter(|symbol| !symbol.is_empty())
+                .filter_map(|symbol| {
+                    let coin = find_coin(symbol);
+                    if coin.is_none() {
+                        log::warn!("⚠️ CLIPPY_CRYPTO_COINS: неизвестная монета «{}»", symbol);
+                    }
+                    coin.map(|c| c.symbol.to_string())
+                })
+                .collect(),
+            Err(_) => vec!["BTC".to_string(), "ETH".to_string()],
+        };
+
+        let crypto_vs = env::var("CLIPPY_CRYPTO_VS")
+            .ok()
+            .map(|v| v.trim().to_uppercase())
+            .filter(|v| CRYPTO_VS_CURRENCIES.contains(&v.as_str()))
+            .unwrap_or_else(|| "RUB".to_string());
+
+        let crypto_refresh_mins = env::var("CLIPPY_CRYPTO_REFRESH_MINS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(10)
+            .max(1);
+
+        let moex_api_url = env::var("CLIPPY_MOEX_API_URL")
+            .ok()
+            .filter(|v| !v.trim().is_empty())
+            .unwrap_or_else(|| DEFAULT_MOEX_API_URL.to_string());
+
+        let stock_tickers = match env::var("CLIPPY_STOCKS") {
+            Ok(v) if v.trim().eq_ignore_ascii_case("off") => Vec::new(),
+            Ok(v) => v
+                .split(',')
+                .map(|ticker| ticker.trim().to_uppercase())
+                .filter(|ticker| !ticker.is_empty() && ticker.chars().all(|c| c.is_ascii_alphanumeric()))
+                .collect(),
+            Err(_) => vec!["SBER".to_string(), "GAZP".to_string()],
+        };
+
+        let stocks_refresh_mins = env::var("CLIPPY_STOCKS_REFRESH_MINS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(5)
+            .max(1);
+
+        let rate_alerts_speak = env::var("CLIPPY_RATE_ALERTS_SPEAK")
+            .map(|v| !matches!(v.trim().to_lowercase().as_str(), "false" | "0" | "off"))
+            .unwrap_or(true);
+
+        let weather_units = WeatherUnits {
+            temperature: env::var("CLIPPY_TEMPERATURE_UNIT")
+                .ok()
+                .and_then(|v| TemperatureUnit::parse(&v))
+                .unwrap_or_default(),
+            wind: env::var("CLIPPY_WIND_UNIT")
+                .ok()
+                .and_then(|v| WindUnit::parse(&v))
+                .unwrap_or_default(),
+            pressure: env::var("CLIPPY_PRESSURE_UNIT")
+                .ok()
+                .and_then(|v| PressureUnit::parse(&v))
+                .unwrap_or_default(),
+        };
+
+        let alert_defaults = AlertSettings::default();
+        let weather_alerts = AlertSettings {
+            enabled: env::var("CLIPPY_WEATHER_ALERTS")
+                .map(|v| !matches!(v.trim().to_lowercase().as_str(), "false" | "0" | "off"))
+                .unwrap_or(alert_defaults.enabled),
+            frost_c: env::var("CLIPPY_ALERT_FROST_C")
+                .ok()
+                .and_then(|v| v.trim().parse::<f32>().ok())
+                .unwrap_or(alert_defaults.frost_c),
+            heat_c: env::var("CLIPPY_ALERT_HEAT_C")
+                .ok()
+                .and_then(|v| v.trim().parse::<f32>().ok())
+                .unwrap_or(alert_defaults.heat_c),
+            precipitation_probability: env::var("CLIPPY_ALERT_PRECIPITATION_PROBABILITY")
+                .ok()
+                .and_then(|v| v.trim().parse::<i32>().ok())
+                .map(|p| p.clamp(0, 100))
+                .unwrap_or(alert_defaults.precipitation_probability),
+            wind_ms: env::var("CLIPPY_ALERT_WIND_MS")
+                .ok()
+                .and_then(|v| v.trim().parse::<f32>().ok())
+                .unwrap_or(alert_defaults.wind_ms),
+            quiet_hours: env::var("CLIPPY_ALERT_QUIET_HOURS")
+                .ok()
+                .and_then(|v| AlertSettings::parse_quiet_hours(&v))
+                .unwrap_or(alert_defaults.quiet_hours),
+            speak: env::var("CLIPPY_ALERT_SPEAK")
+                .map(|v| v.trim().to_lowercase() == "true")
+                .unwrap_or(alert_defaults.speak),
+        };
+
         let clippy_name = "Скрепыш".to_string();
         let system_prompt = format!(
             "Ты {}, дружелюбный персональный помощник.\n\
@@ -60,6 +357,7 @@ impl Default for Config {
         let use_openai_final = use_openai && openai_api_key.is_some();
 
         Self {
+            profile,
             gigachat_api_key,
             openai_api_key,
             use_openai: use_openai_final,
@@ -76,7 +374,41 @@ impl Default for Config {
             gigachat_model,
             gigachat_temperature,
             gigachat_max_tokens,
+            db_path,
+            in_memory_storage,
+            db_passphrase,
+            retention_max_age_days,
+            retention_max_messages,
+            retention_max_db_mb,
+            trash_retention_days,
+            maintenance_interval_mins,
+            backup_dir,
+            backup_interval_hours,
+            backup_keep,
+            weather_city,
+            weather_coordinates,
+            weather_refresh_mins,
+            rates_refresh_mins,
+            rates_source,
+            base_currency,
+            currency_watchlist,
+            crypto_api_url,
+            crypto_coins,
+            crypto_vs,
+            crypto_refresh_mins,
+            moex_api_url,
+            stock_tickers,
+            stocks_refresh_mins,
+            rate_alerts_speak,
+            weather_units,
+            weather_alerts,
         }
     }
+
+    /// Каталог файлов профиля: сохранённые места, список валют, кэш.
+    /// БД и её вложения могут лежать отдельно (CLIPPY_DB_PATH)
+    pub fn data_dir(&self) -> PathBuf {
+        self.profile.dir()
+    }
 }
 
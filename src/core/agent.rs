@@ -1,9 +1,18 @@
 use crate::config::Config;
+use crate::profile::Profile;
 use crate::ai::GigaChatClient;
 use crate::ai::local::LocalAI;
-use crate::services::{SQLiteStorage, WeatherService, CurrencyService};
+use crate::core::{AlertCommand, ChatCommand, CurrencyCommand, PlaceCommand};
+use crate::core::intents::{ConversionQuestion, CryptoQuestion, StockQuestion, WeatherQuestion};
+use crate::services::iso4217;
+use crate::services::rate_alerts::{AlertTarget, RateRule};
+use crate::services::attachment::NewAttachment;
+use crate::services::{BackupManager, FeedbackAction};
+use crate::services::{ConversationStore, InMemoryStore, SQLiteStorage, StorageHandle, WeatherService, CurrencyService, CryptoService, StocksService};
+use crate::services::{AirQualityService, LocationService, Place};
 use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
+use std::sync::Arc;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct Message {
@@ -15,14 +24,45 @@ pub struct ClippyAgent {
     config: Config,
     conversation_history: VecDeque<Message>,
     gigachat_client: Option<GigaChatClient>,
-    storage: Option<SQLiteStorage>,
+    storage: Option<StorageHandle>,
     current_model: String,
+    /// uid последнего сохранённого ответа (для оценок из команд чата)
+    last_reply_uid: Option<String>,
+    /// uid последнего сохранённого вопроса (для вложений)
+    last_prompt_uid: Option<String>,
+    /// Инкогнито: ничего не пишется в хранилище, история только в памяти
+    incognito: bool,
+    /// Контекст разговора до входа в инкогнито, возвращается при выходе
+    pre_incognito: Option<ConversationSnapshot>,
     weather_service: WeatherService,
+    location_service: LocationService,
+    air_quality_service: AirQualityService,
+    /// Варианты последнего поиска `/place <город>`
+    place_candidates: Vec<Place>,
+    /// Уведомляется при смене основного места или списка валют
+    widgets_listener: Option<Arc<tokio::sync::watch::Sender<()>>>,
     currency_service: CurrencyService,
+    crypto_service: CryptoService,
+    stocks_service: StocksService,
+}
+
+/// Контекст разговора, отложенный на время инкогнито
+#[derive(Default)]
+struct ConversationSnapshot {
+    history: VecDeque<Message>,
+    client_history: Vec<crate::ai::gigachat::Message>,
+    last_reply_uid: Option<String>,
+    last_prompt_uid: Option<String>,
 }
 
 impl ClippyAgent {
     pub fn new(config: Config) -> Self {
+        let store = Self::open_store(&config);
+        Self::with_store(config, store)
+    }
+
+    /// Создаёт агента с заданным хранилищем (тесты, скрипты, сессии без записи на диск)
+    pub fn with_store(config: Config, store: Option<Box<dyn ConversationStore>>) -> Self {
         // Пытаемся создать GigaChat клиент если доступен API ключ
         let gigachat_client = config.gigachat_api_key.as_ref().and_then(|key| {
             if key.is_empty() {
@@ -37,26 +77,76 @@ impl ClippyAgent {
             }
         });
 
-        // Инициализируем хранилище
-        let storage = match SQLiteStorage::new(None) {
+        let weather_service = WeatherService::from_config(&config);
+        let location_service = LocationService::from_config(&config);
+        let currency_service = CurrencyService::from_config(&config);
+        let crypto_service = CryptoService::from_config(&config);
+        let stocks_service = StocksService::from_config(&config);
+        let air_quality_service = AirQualityService::from_config(&config);
+
+        Self {
+            config,
+            conversation_history: VecDeque::new(),
+            gigachat_client,
+            storage: store.map(StorageHandle::spawn),
+            current_model: "Local".to_string(),
+            last_reply_uid: None,
+            last_prompt_uid: None,
+            incognito: false,
+            pre_incognito: None,
+            weather_service,
+            location_service,
+            air_quality_service,
+            place_candidates: Vec::new(),
+            widgets_listener: None,
+            currency_service,
+            crypto_service,
+            stocks_service,
+        }
+    }
+
+    /// Открывает хранилище согласно конфигурации: в памяти или SQLite по `db_path`
+    fn open_store(config: &Config) -> Option<Box<dyn ConversationStore>> {
+        if config.in_memory_storage {
+            return Some(Box::new(InMemoryStore::new()));
+        }
+
+        match SQLiteStorage::new(config.db_path.clone()) {
             Ok(s) => {
                 log::info!("✓ SQLiteStorage инициализирован");
-                Some(s)
+                Self::unlock_storage(s, config.db_passphrase.as_deref())
+                    .map(|s| Box::new(s) as Box<dyn ConversationStore>)
             }
             Err(e) => {
                 log::warn!("⚠️ Ошибка инициализации SQLiteStorage: {}", e);
                 None
             }
-        };
+        }
+    }
 
-        Self {
-            config,
-            conversation_history: VecDeque::new(),
-            gigachat_client,
-            storage,
-            current_model: "Local".to_string(),
-            weather_service: WeatherService::new(),
-            currency_service: CurrencyService::new(),
+    /// Разблокирует зашифрованное хранилище паролем из конфигурации.
+    /// Без верного пароля зашифрованная БД не используется, чтобы не смешивать
+    /// открытые и зашифрованные записи
+    fn unlock_storage(mut storage: SQLiteStorage, passphrase: Option<&str>) -> Option<SQLiteStorage> {
+        match passphrase {
+            Some(passphrase) => match storage.unlock(passphrase) {
+                Ok(()) => Some(storage),
+                Err(e) => {
+                    log::error!("🔐 Не удалось разблокировать БД: {}", e);
+                    None
+                }
+            },
+            None => match storage.is_encrypted() {
+                Ok(false) => Some(storage),
+                Ok(true) => {
+                    log::warn!("🔐 БД зашифрована, но CLIPPY_DB_PASSPHRASE не задан — история отключена");
+                    None
+                }
+                Err(e) => {
+                    log::error!("Ошибка проверки шифрования БД: {}", e);
+                    None
+                }
+            },
         }
     }
 
@@ -65,7 +155,26 @@ impl ClippyAgent {
             return "Чем могу помочь?".to_string();
         }
 
-        let response = self.get_ai_response(user_input).await;
+        // Локальные команды не уходят в AI и не попадают в историю
+        if let Some(command) = ChatCommand::parse(user_input) {
+            return self.handle_command(command).await;
+        }
+
+        // Вопросы о погоде ("будет ли дождь завтра") отвечаются по прогнозу,
+        // пересчёт валют ("сколько будет 250 евро в долларах") — по курсам
+        let response = match self.answer_weather_question(user_input).await {
+            Some(answer) => answer,
+            None => match self.answer_conversion_question(user_input).await {
+                Some(answer) => answer,
+                None => match self.answer_crypto_question(user_input).await {
+                    Some(answer) => answer,
+                    None => match self.answer_stock_question(user_input).await {
+                        Some(answer) => answer,
+                        None => self.get_ai_response(user_input).await,
+                    },
+                },
+            },
+        };
 
         // Сохраняем в историю памяти
         self.conversation_history.push_back(Message {
@@ -82,17 +191,650 @@ impl ClippyAgent {
             self.conversation_history.pop_front();
         }
 
-        // Сохраняем в БД (асинхронно, не блокируем ответ)
-        if let Some(ref storage) = self.storage {
-            if let Err(e) = storage.save_message("user", user_input, &self.current_model) {
-                log::error!("Ошибка сохранения user message в БД: {}", e);
+        // Сохраняем в БД (в потоке хранилища, не блокируем ответ); в инкогнито — никогда
+        if let Some(storage) = self.storage.as_ref().filter(|_| !self.incognito) {
+            self.last_prompt_uid = Some(storage.save_message("user", user_input, &self.current_model));
+            self.last_reply_uid = Some(storage.save_message("assistant", &response, &self.current_model));
+        }
+
+        response
+    }
+
+    /// Выполняет локальную команду чата
+    async fn handle_command(&mut self, command: ChatCommand) -> String {
+        // Инкогнито не оставляет следов: ни записей в хранилище, ни выгрузок из него
+        if self.incognito && command.writes_data() {
+            return "🕶 В инкогнито эта команда недоступна: она сохраняет данные. Выйди из инкогнито и повтори".to_string();
+        }
+
+        // Handle клонируется, чтобы команды могли менять состояние агента
+        let Some(storage) = self.storage.clone() else {
+            return match command {
+                // Места для погоды и валюты хранятся в файлах профиля и не зависят от хранилища истории
+                ChatCommand::Place(action) => self.handle_place_command(action).await,
+                ChatCommand::Currency(action) => self.handle_currency_command(action),
+                ChatCommand::Profile(name) => self.switch_profile(name),
+                ChatCommand::Help => ChatCommand::help_text(),
+                ChatCommand::Invalid(message) => message,
+                _ => "Хранилище недоступно".to_string(),
+            };
+        };
+
+        match command {
+            ChatCommand::Import { source, path } => {
+                let file = path.clone();
+                let result = storage
+                    .call(move |s| crate::services::import::import_file(s, source, &file))
+                    .await;
+                match result {
+                    Ok(report) => report.to_string(),
+                    Err(e) => {
+                        log::warn!("⚠️ Ошибка импорта {}: {}", path.display(), e);
+                        format!("Не удалось импортировать '{}': {}", path.display(), e)
+                    }
+                }
+            }
+            ChatCommand::Search(query) => {
+                let needle = query.clone();
+                match storage.read(move |s| s.search_messages(&needle, 5)).await {
+                    Ok(found) if found.is_empty() => format!("🔍 По запросу «{}» ничего не найдено", query),
+                    Ok(found) => {
+                        let mut result = format!("🔍 Найдено по запросу «{}»:\n", query);
+                        for msg in found {
+                            let date = msg.timestamp.get(..10).unwrap_or(&msg.timestamp);
+                            let preview: String = msg.content.chars().take(80).collect();
+                            result.push_str(&format!("• [{}] {}: {}\n", date, msg.role, preview));
+                        }
+                        result
+                    }
+                    Err(e) => format!("Ошибка поиска: {}", e),
+                }
+            }
+            ChatCommand::Pin(pinned) => match storage.call(move |s| s.set_session_pinned(pinned)).await {
+                Ok(()) if pinned => "📌 Сессия закреплена и не будет удалена автоматически".to_string(),
+                Ok(()) => "Сессия откреплена".to_string(),
+                Err(e) => format!("Ошибка: {}", e),
+            },
+            ChatCommand::Trash => match storage.read(|s| s.list_trash(10)).await {
+                Ok(entries) if entries.is_empty() => "🗑️ Корзина пуста".to_string(),
+                Ok(entries) => {
+                    let mut result = "🗑️ Корзина:\n".to_string();
+                    for (i, entry) in entries.iter().enumerate() {
+                        let date = entry.batch.get(..16).unwrap_or(&entry.batch).replace('T', " ");
+                        result.push_str(&format!(
+                            "{}. [{}] {} сообщ. — {}\n",
+                            i + 1,
+                            date,
+                            entry.count,
+                            entry.preview
+                        ));
+                    }
+                    result.push_str("Восстановить: /restore <номер>");
+                    result
+                }
+                Err(e) => format!("Ошибка чтения корзины: {}", e),
+            },
+            ChatCommand::Restore(index) => {
+                let entry = match storage.read(|s| s.list_trash(10)).await {
+                    Ok(entries) => entries.into_iter().nth(index.saturating_sub(1)),
+                    Err(e) => return format!("Ошибка чтения корзины: {}", e),
+                };
+                let Some(entry) = entry else {
+                    return format!("В корзине нет записи №{}", index);
+                };
+                match self.restore_history(&entry.batch, Some(&entry.session_id)).await {
+                    Ok(count) => format!("♻️ Восстановлено {} сообщений", count),
+                    Err(e) => e,
+                }
+            }
+            ChatCommand::Comment(text) => self.annotate_last_reply(FeedbackAction::Comment(text)).await,
+            ChatCommand::Tag(tags) => self.annotate_last_reply(FeedbackAction::AddTags(tags)).await,
+            ChatCommand::Bookmarks => match storage.read(|s| s.bookmarks(10)).await {
+                Ok(found) if found.is_empty() => "🔖 Закладок пока нет".to_string(),
+                Ok(found) => {
+                    let mut result = "🔖 Закладки:\n".to_string();
+                    for (msg, feedback) in found {
+                        let date = msg.timestamp.get(..10).unwrap_or(&msg.timestamp);
+                        let preview: String = msg.content.chars().take(80).collect();
+                        let tags = if feedback.tags.is_empty() {
+                            String::new()
+                        } else {
+                            format!(" #{}", feedback.tags.join(" #"))
+                        };
+                        result.push_str(&format!("• [{}] {}{}\n", date, preview, tags));
+                    }
+                    result
+                }
+                Err(e) => format!("Ошибка чтения закладок: {}", e),
+            },
+            ChatCommand::ExportFeedback(path) => {
+                let file = path.clone();
+                let result = storage
+                    .read(move |s| {
+                        let pairs = s.rated_pairs()?;
+                        crate::services::feedback::write_jsonl(&pairs, &file)?;
+                        let files = crate::services::feedback::export_attachments(s, &pairs, &file)?;
+                        Ok((pairs.len(), files))
+                    })
+                    .await;
+                match result {
+                    Ok((count, 0)) => format!("📤 Выгружено {} оценённых ответов в {}", count, path.display()),
+                    Ok((count, files)) => format!(
+                        "📤 Выгружено {} оценённых ответов и {} вложений в {}",
+                        count,
+                        files,
+                        path.display()
+                    ),
+                    Err(e) => format!("Не удалось выгрузить оценки: {}", e),
+                }
+            }
+            ChatCommand::Attach(path) => {
+                let Some(uid) = self.last_prompt_uid.clone() else {
+                    return "Вложение крепится к последнему вопросу — сначала задай вопрос".to_string();
+                };
+                let file = match NewAttachment::from_file(&path, None, None) {
+                    Ok(file) => file,
+                    Err(e) => return format!("Не удалось прочитать '{}': {}", path.display(), e),
+                };
+                match storage.call(move |s| s.attach(&uid, &file)).await {
+                    Ok(a) => format!("📎 {} ({}, {} КБ) прикреплён, id {}", a.filename, a.mime_type, a.size.div_ceil(1024), a.id),
+                    Err(e) => format!("Не удалось прикрепить файл: {}", e),
+                }
+            }
+            ChatCommand::Attachments => {
+                let uids: Vec<String> = [self.last_prompt_uid.clone(), self.last_reply_uid.clone()]
+                    .into_iter()
+                    .flatten()
+                    .collect();
+                let result = storage
+                    .read(move |s| {
+                        let mut found = Vec::new();
+                        for uid in &uids {
+                            found.extend(s.list_attachments(uid)?);
+                        }
+                        Ok(found)
+                    })
+                    .await;
+                match result {
+                    Ok(found) if found.is_empty() => "📎 Вложений нет".to_string(),
+                    Ok(found) => {
+                        let mut result = "📎 Вложения:\n".to_string();
+                        for a in found {
+                            result.push_str(&format!("• [{}] {} — {}, {} КБ\n", a.id, a.filename, a.mime_type, a.size.div_ceil(1024)));
+                        }
+                        result.push_str("Сохранить: /save-attachment <id> <путь>");
+                        result
+                    }
+                    Err(e) => format!("Ошибка чтения вложений: {}", e),
+                }
             }
-            if let Err(e) = storage.save_message("assistant", &response, &self.current_model) {
-                log::error!("Ошибка сохранения assistant message в БД: {}", e);
+            ChatCommand::SaveAttachment { id, path } => {
+                match storage.read(move |s| s.fetch_attachment(id)).await {
+                    Ok(Some((attachment, data))) => {
+                        // Каталог — сохраняем под исходным именем
+                        let target = if path.is_dir() { path.join(&attachment.filename) } else { path };
+                        match std::fs::write(&target, data) {
+                            Ok(()) => format!("💾 {} сохранён в {}", attachment.filename, target.display()),
+                            Err(e) => format!("Не удалось записать {}: {}", target.display(), e),
+                        }
+                    }
+                    Ok(None) => format!("Вложение {} не найдено", id),
+                    Err(e) => format!("Ошибка чтения вложения: {}", e),
+                }
             }
+            ChatCommand::Backup if self.config.in_memory_storage => {
+                "Хранилище в памяти не поддерживает резервные копии".to_string()
+            }
+            ChatCommand::Backup => match BackupManager::from_config(&self.config).create().await {
+                Ok(backup) => format!("💾 Резервная копия {} ({} КБ)", backup.name, backup.size_bytes / 1024),
+                Err(e) => format!("Не удалось создать резервную копию: {}", e),
+            },
+            ChatCommand::Backups => match BackupManager::from_config(&self.config).list() {
+                Ok(backups) if backups.is_empty() => "💾 Резервных копий пока нет".to_string(),
+                Ok(backups) => {
+                    let mut result = "💾 Резервные копии:\n".to_string();
+                    for (i, backup) in backups.iter().enumerate() {
+                        result.push_str(&format!("{}. {} — {} КБ\n", i + 1, backup.name, backup.size_bytes / 1024));
+                    }
+                    result.push_str("Восстановить: /backup restore <номер>");
+                    result
+                }
+                Err(e) => format!("Ошибка чтения каталога копий: {}", e),
+            },
+            ChatCommand::RestoreBackup(key) => {
+                match BackupManager::from_config(&self.config).restore(&storage, &key).await {
+                    Ok(report) => report.to_string(),
+                    Err(e) => {
+                        log::warn!("⚠️ Ошибка восстановления из копии {}: {:#}", key, e);
+                        format!("Не удалось восстановить данные: {:#}", e)
+                    }
+                }
+            }
+            ChatCommand::Place(action) => self.handle_place_command(action).await,
+            ChatCommand::Currency(action) => self.handle_currency_command(action),
+            ChatCommand::Alert(action) => self.handle_alert_command(&storage, action).await,
+            ChatCommand::Profile(name) => self.switch_profile(name),
+            ChatCommand::Help => ChatCommand::help_text(),
+            ChatCommand::Invalid(message) => message,
         }
+    }
 
-        response
+    /// Без имени — список профилей; с именем — профиль для следующего запуска
+    fn switch_profile(&self, name: Option<String>) -> String {
+        let current = &self.config.profile;
+        let Some(name) = name else {
+            let mut result = "👤 Профили:\n".to_string();
+            for profile in Profile::list() {
+                let marker = if profile == current.name { " ← текущий" } else { "" };
+                result.push_str(&format!("• {}{}\n", profile, marker));
+            }
+            result.push_str("Выбрать: /profile <имя> (или --profile <имя> при запуске)");
+            return result;
+        };
+
+        match Profile::new(&name).and_then(|profile| profile.remember().map(|_| profile)) {
+            Ok(profile) if profile == *current => format!("👤 Профиль {} уже активен", profile.name),
+            Ok(profile) => format!(
+                "👤 Профиль {} будет выбран при следующем запуске.\nЕго настройки и ключи API: {}",
+                profile.name,
+                profile.env_file().display()
+            ),
+            Err(e) => format!("Не удалось выбрать профиль: {}", e),
+        }
+    }
+
+    /// Применяет комментарий или теги к последнему ответу
+    async fn annotate_last_reply(&self, action: FeedbackAction) -> String {
+        let (Some(storage), Some(uid)) = (self.storage.clone(), self.last_reply_uid.clone()) else {
+            return "Пока нечего отмечать — сначала задай вопрос".to_string();
+        };
+
+        match storage.call(move |s| s.update_feedback(&uid, &action)).await {
+            Ok(feedback) => {
+                let mut result = "✍️ Отметка сохранена".to_string();
+                if let Some(comment) = feedback.comment {
+                    result.push_str(&format!("\nКомментарий: {}", comment));
+                }
+                if !feedback.tags.is_empty() {
+                    result.push_str(&format!("\nТеги: #{}", feedback.tags.join(" #")));
+                }
+                result
+            }
+            Err(e) => format!("Ошибка сохранения отметки: {}", e),
+        }
+    }
+
+    /// uid последнего ответа, сохранённого в историю
+    pub fn last_reply_uid(&self) -> Option<String> {
+        self.last_reply_uid.clone()
+    }
+
+    /// Поиск, сохранение и выбор мест для погоды
+    async fn handle_place_command(&mut self, action: PlaceCommand) -> String {
+        let mut saved = self.location_service.saved();
+
+        let result = match action {
+            PlaceCommand::Search(query) => {
+                return match self.location_service.search(&query).await {
+                    Ok(found) if found.is_empty() => format!("🗺️ Место «{}» не найдено", query),
+                    Ok(found) => {
+                        let mut text = format!("🗺️ Найдено по запросу «{}»:", query);
+                        for (i, place) in found.iter().enumerate() {
+                            text.push_str(&format!("\n{}. {}", i + 1, place.display_name()));
+                        }
+                        text.push_str("\nСохрани нужное: /place save <номер>");
+                        self.place_candidates = found;
+                        text
+                    }
+                    Err(e) => {
+                        log::warn!("⚠️ Ошибка геокодинга '{}': {}", query, e);
+                        format!("Не удалось найти место «{}»: {}", query, e)
+                    }
+                };
+            }
+            PlaceCommand::Save(index) => match self.place_candidates.get(index) {
+                Some(place) => {
+                    let number = saved.add(place.clone()) + 1;
+                    Ok(format!("⭐ Место {} сохранено под номером {}", place.display_name(), number))
+                }
+                None => Err(anyhow::anyhow!("Варианта {} нет — сначала найди место: /place <город>", index + 1)),
+            },
+            PlaceCommand::Default(index) => saved
+                .set_default(index)
+                .map(|place| format!("📍 Погода теперь для места {}", place.display_name())),
+            PlaceCommand::Remove(index) => saved
+                .remove(index)
+                .map(|place| format!("🗑️ Место {} удалено", place.display_name())),
+            PlaceCommand::List => {
+                return if saved.places.is_empty() {
+                    "🗺️ Сохранённых мест нет. Найди место: /place <город>".to_string()
+                } else {
+                    let mut text = "🗺️ Сохранённые места:".to_string();
+                    for (i, place) in saved.places.iter().enumerate() {
+                        let mark = if saved.default == Some(i) { " 📍" } else { "" };
+                        text.push_str(&format!("\n{}. {}{}", i + 1, place.display_name(), mark));
+                    }
+                    text
+                };
+            }
+        };
+
+        match result.and_then(|message| self.location_service.store(&saved).map(|_| message)) {
+            Ok(message) => {
+                self.notify_widgets();
+                message
+            }
+            Err(e) => e.to_string(),
+        }
+    }
+
+    /// Список валют на виджетах и базовая валюта
+    fn handle_currency_command(&mut self, action: CurrencyCommand) -> String {
+        let mut watchlist = self.currency_service.watchlist();
+
+        let result = match action {
+            CurrencyCommand::List => {
+                let mut text = format!(
+                    "💱 Курсы считаются в {} ({})",
+                    watchlist.base,
+                    iso4217::lookup(&watchlist.base).map_or("", |c| c.name)
+                );
+                if watchlist.codes.is_empty() {
+                    text.push_str("\nСписок валют пуст. Добавь: /currency add USD EUR");
+                }
+                for code in &watchlist.codes {
+                    let name = iso4217::lookup(code).map_or("", |c| c.name);
+                    text.push_str(&format!("\n• {} {} — {}", iso4217::symbol(code), code, name));
+                }
+                return text;
+            }
+            CurrencyCommand::Add(codes) => {
+                let mut added = Vec::new();
+                let mut errors = Vec::new();
+                for code in &codes {
+                    if *code == watchlist.base {
+                        errors.push(format!("{} — базовая валюта", code));
+                        continue;
+                    }
+                    match watchlist.add(code) {
+                        Ok(true) => added.push(code.clone()),
+                        Ok(false) => errors.push(format!("{} уже в списке", code)),
+                        Err(e) => errors.push(e.to_string()),
+                    }
+                }
+                let mut text = if added.is_empty() {
+                    "💱 Ничего не добавлено".to_string()
+                } else {
+                    format!("💱 Добавлено: {}", added.join(", "))
+                };
+                for error in errors {
+                    text.push_str(&format!("\n• {}", error));
+                }
+                Ok(text)
+            }
+            CurrencyCommand::Remove(codes) => {
+                let (removed, missing): (Vec<String>, Vec<String>) =
+                    codes.into_iter().partition(|code| watchlist.remove(code));
+                let mut text = if removed.is_empty() {
+                    "💱 Ничего не убрано".to_string()
+                } else {
+                    format!("💱 Убрано: {}", removed.join(", "))
+                };
+                if !missing.is_empty() {
+                    text.push_str(&format!("\nНет в списке: {}", missing.join(", ")));
+                }
+                Ok(text)
+            }
+            CurrencyCommand::Base(code) => watchlist
+                .set_base(&code)
+                .map(|_| format!("💱 Курсы теперь считаются в {}", watchlist.base)),
+        };
+
+        match result.and_then(|message| self.currency_service.store_watchlist(&watchlist).map(|_| message)) {
+            Ok(message) => {
+                self.notify_widgets();
+                message
+            }
+            Err(e) => e.to_string(),
+        }
+    }
+
+    /// Правила уведомлений о курсах валют и акций
+    async fn handle_alert_command(&mut self, storage: &StorageHandle, action: AlertCommand) -> String {
+        match action {
+            AlertCommand::List => match storage.read(|s| s.rates().rate_rules()).await {
+                Ok(rules) if rules.is_empty() => {
+                    "🔔 Уведомлений о курсах нет. Добавь: /alert USD > 100, /alert EUR 2%, /alert SBER < 250".to_string()
+                }
+                Ok(rules) => {
+                    let mut text = "🔔 Уведомления о курсах:".to_string();
+                    for (i, saved) in rules.iter().enumerate() {
+                        text.push_str(&format!("\n{}. {}", i + 1, saved.rule.describe()));
+                        if let Some(date) = saved.fired_on {
+                            text.push_str(&format!(" (сработало {})", date.format("%d.%m")));
+                        }
+                    }
+                    text.push_str("\nУдалить: /alert remove <номер>");
+                    text
+                }
+                Err(e) => format!("Ошибка чтения уведомлений: {}", e),
+            },
+            AlertCommand::Add(input) => {
+                let watchlist = self.currency_service.watchlist();
+                let rule = match RateRule::parse(&input, &watchlist.base) {
+                    Ok(rule) => rule,
+                    Err(e) => return e.to_string(),
+                };
+                let saved = rule.clone();
+                if let Err(e) = storage.call(move |s| s.rates_mut().add_rate_rule(&saved)).await {
+                    return format!("Ошибка сохранения уведомления: {}", e);
+                }
+
+                let mut text = format!("🔔 Сообщу, когда {}", rule.describe());
+                // Курсы считаются только для валют из списка виджетов и базовой валюты
+                if rule.target == AlertTarget::Currency {
+                    let missing: Vec<&str> = [rule.symbol.as_str(), rule.base.as_str()]
+                        .into_iter()
+                        .filter(|code| *code != watchlist.base && !watchlist.codes.iter().any(|c| c == code))
+                        .collect();
+                    if !missing.is_empty() {
+                        text.push_str(&format!(
+                            "\n⚠️ Курсы проверяются только для валют из списка: /currency add {}",
+                            missing.join(" ")
+                        ));
+                    }
+                }
+                // Внеочередное обновление курсов сразу проверит новое правило
+                self.notify_widgets();
+                text
+            }
+            AlertCommand::Remove(index) => {
+                let saved = match storage.read(|s| s.rates().rate_rules()).await {
+                    Ok(rules) => rules.into_iter().nth(index - 1),
+                    Err(e) => return format!("Ошибка чтения уведомлений: {}", e),
+                };
+                let Some(saved) = saved else {
+                    return format!("Нет уведомления №{}. Список: /alerts", index);
+                };
+                let id = saved.id;
+                match storage.call(move |s| s.rates_mut().remove_rate_rule(id)).await {
+                    Ok(_) => format!("🔕 Уведомление удалено: {}", saved.rule.describe()),
+                    Err(e) => format!("Ошибка удаления уведомления: {}", e),
+                }
+            }
+        }
+    }
+
+    /// Подписка на смену места и списка валют (виджеты обновляются сразу)
+    pub fn set_widgets_listener(&mut self, listener: Arc<tokio::sync::watch::Sender<()>>) {
+        self.widgets_listener = Some(listener);
+    }
+
+    fn notify_widgets(&self) {
+        if let Some(listener) = &self.widgets_listener {
+            listener.send_replace(());
+        }
+    }
+
+    /// Отвечает на вопрос о погоде по прогнозу Open-Meteo; `None` — вопрос не о погоде
+    async fn answer_weather_question(&mut self, user_input: &str) -> Option<String> {
+        let question = WeatherQuestion::parse(user_input)?;
+        self.current_model = "Open-Meteo".to_string();
+
+        let place = match self.location_service.current_place().await {
+            Ok(place) => place,
+            Err(e) => {
+                log::warn!("⚠️ Не удалось определить место для погоды: {}", e);
+                return Some(format!("Не смог определить место для погоды: {}. Выбери его командой /place <город>", e));
+            }
+        };
+
+        if question == WeatherQuestion::Current {
+            return Some(self.get_weather_info(&place).await);
+        }
+
+        log::info!("📡 Запрос прогноза для места: {} ({:?})", place.display_name(), question);
+
+        let air = if question.needs_air_quality() {
+            match self.air_quality_service.get_air_quality(&place).await {
+                Ok(air) => Some(air),
+                Err(e) => {
+                    log::warn!("⚠️ Ошибка получения качества воздуха: {}", e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        Some(match self.weather_service.get_forecast(&place).await {
+            Ok(forecast) => {
+                let mut answer = question.answer(&forecast.value, air.as_ref().map(|a| &a.value));
+                let notes = [forecast.stale_note(), air.as_ref().and_then(|a| a.stale_note())];
+                if let Some(note) = notes.into_iter().flatten().next() {
+                    answer.push_str(&format!("\n{}", note));
+                }
+                answer
+            }
+            Err(e) => {
+                log::warn!("⚠️ Ошибка получения прогноза: {}", e);
+                format!("Извини, не смог получить прогноз погоды для города '{}'. Ошибка: {}", place.name, e)
+            }
+        })
+    }
+
+    /// Пересчитывает сумму между валютами; `None` — вопрос не о пересчёте
+    async fn answer_conversion_question(&mut self, user_input: &str) -> Option<String> {
+        let question = ConversionQuestion::parse(user_input)?;
+        let to = question.to.unwrap_or_else(|| self.currency_service.watchlist().base);
+        if to == question.from {
+            return None;
+        }
+
+        Some(match self.currency_service.convert(question.amount, &question.from, &to).await {
+            Ok(conversion) => {
+                self.current_model = conversion.value.source.label().to_string();
+                let mut answer = format!("💱 {}", conversion.value.format());
+                if let Some(note) = conversion.stale_note() {
+                    answer.push_str(&format!("\n{}", note));
+                }
+                answer
+            }
+            Err(e) => {
+                log::warn!("⚠️ Ошибка пересчёта {} {} в {}: {}", question.amount, question.from, to, e);
+                format!("Не смог пересчитать {} в {}: {}", question.from, to, e)
+            }
+        })
+    }
+
+    /// Цены криптовалют; `None` — вопрос не о них
+    async fn answer_crypto_question(&mut self, user_input: &str) -> Option<String> {
+        let question = CryptoQuestion::parse(user_input)?;
+        let coins = if question.coins.is_empty() {
+            self.crypto_service.coins().to_vec()
+        } else {
+            question.coins
+        };
+        if coins.is_empty() {
+            return None;
+        }
+        let vs = question.vs.unwrap_or_else(|| self.crypto_service.vs().to_string());
+        self.current_model = "CoinGecko".to_string();
+
+        Some(match self.crypto_service.get_prices(&coins, &vs).await {
+            Ok(quotes) if quotes.value.is_empty() => format!("Нет цены для {}", coins.join(", ")),
+            Ok(quotes) => {
+                let mut answer = match (question.amount, quotes.value.as_slice()) {
+                    (Some(amount), [quote]) => format!(
+                        "🪙 {} {} = {} (1 {} = {})",
+                        amount,
+                        quote.symbol,
+                        iso4217::format_amount(amount * quote.price, &quote.vs),
+                        quote.symbol,
+                        quote.format_price()
+                    ),
+                    _ => {
+                        let mut text = "🪙 Криптовалюты:".to_string();
+                        for quote in &quotes.value {
+                            text.push_str(&format!("\n• {}", quote.format()));
+                        }
+                        text
+                    }
+                };
+                if let Some(note) = quotes.stale_note() {
+                    answer.push_str(&format!("\n{}", note));
+                }
+                answer
+            }
+            Err(e) => {
+                log::warn!("⚠️ Ошибка получения цен криптовалют: {}", e);
+                format!("Не смог узнать цены криптовалют: {}", e)
+            }
+        })
+    }
+
+    /// Котировки акций Мосбиржи; `None` — вопрос не о них
+    async fn answer_stock_question(&mut self, user_input: &str) -> Option<String> {
+        let question = StockQuestion::parse(user_input, self.stocks_service.tickers())?;
+        let tickers = if question.tickers.is_empty() {
+            self.stocks_service.tickers().to_vec()
+        } else {
+            question.tickers
+        };
+        if tickers.is_empty() {
+            return None;
+        }
+        self.current_model = "Мосбиржа".to_string();
+
+        Some(match self.stocks_service.get_quotes_for(&tickers).await {
+            Ok(quotes) => {
+                let mut answer = match (question.amount, quotes.value.as_slice()) {
+                    (Some(amount), [quote]) => format!(
+                        "📊 {} акций {} = {} ({} за акцию, {})",
+                        amount,
+                        quote.name,
+                        iso4217::format_amount(amount * quote.price, "RUB"),
+                        quote.format_price(),
+                        quote.trading_label()
+                    ),
+                    _ => {
+                        let mut text = "📊 Акции на Мосбирже:".to_string();
+                        for quote in &quotes.value {
+                            text.push_str(&format!("\n• {}", quote.format()));
+                        }
+                        text
+                    }
+                };
+                if let Some(note) = quotes.stale_note() {
+                    answer.push_str(&format!("\n{}", note));
+                }
+                answer
+            }
+            Err(e) => {
+                log::warn!("⚠️ Ошибка получения котировок {}: {}", tickers.join(", "), e);
+                format!("Не смог узнать котировки {}: {}", tickers.join(", "), e)
+            }
+        })
     }
 
     async fn get_ai_response(&mut self, user_input: &str) -> String {
@@ -129,19 +871,104 @@ impl ClippyAgent {
         "OpenAI ещё не интегрирован в эту версию.".to_string()
     }
 
-    pub fn clear_history(&mut self) {
+    /// Включает или выключает инкогнито. Разговор в инкогнито начинается
+    /// с чистого контекста; при выходе он стирается из памяти, а прежний
+    /// контекст возвращается
+    pub fn set_incognito(&mut self, enabled: bool) {
+        if enabled == self.incognito {
+            return;
+        }
+
+        if enabled {
+            self.pre_incognito = Some(ConversationSnapshot {
+                history: std::mem::take(&mut self.conversation_history),
+                client_history: self.gigachat_client.as_ref().map(GigaChatClient::get_history).unwrap_or_default(),
+                last_reply_uid: self.last_reply_uid.take(),
+                last_prompt_uid: self.last_prompt_uid.take(),
+            });
+            if let Some(client) = &mut self.gigachat_client {
+                client.clear_history();
+            }
+            log::info!("🕶 Инкогнито включено: сообщения не сохраняются");
+        } else {
+            let snapshot = self.pre_incognito.take().unwrap_or_default();
+            self.conversation_history = snapshot.history;
+            if let Some(client) = &mut self.gigachat_client {
+                client.set_history(snapshot.client_history);
+            }
+            self.last_reply_uid = snapshot.last_reply_uid;
+            self.last_prompt_uid = snapshot.last_prompt_uid;
+            log::info!("🕶 Инкогнито выключено, разговор в инкогнито стёрт из памяти");
+        }
+        self.incognito = enabled;
+    }
+
+    /// Очищает историю разговора. Сообщения в БД переносятся в корзину;
+    /// возвращается метка очистки для `restore_history`
+    pub async fn clear_history(&mut self) -> Option<String> {
         self.conversation_history.clear();
         if let Some(client) = &mut self.gigachat_client {
             client.clear_history();
         }
 
+        // В инкогнито сохранённую историю не трогаем: стирается только память
+        if self.incognito {
+            return None;
+        }
+
+        let mut batch = None;
         if let Some(ref storage) = self.storage {
-            if let Err(e) = storage.clear_session_history() {
-                log::error!("Ошибка при очистке истории в БД: {}", e);
+            match storage.call(|s| s.clear_session_history()).await {
+                Ok(b) => batch = Some(b),
+                Err(e) => log::error!("Ошибка при очистке истории в БД: {}", e),
             }
         }
 
         log::info!("🗑️  История разговора очищена");
+        batch
+    }
+
+    /// Отменяет очистку: возвращает сообщения из корзины и восстанавливает контекст разговора
+    pub async fn restore_history(&mut self, batch: &str, session_id: Option<&str>) -> Result<usize, String> {
+        let Some(ref storage) = self.storage else {
+            return Err("Хранилище недоступно".to_string());
+        };
+
+        let batch = batch.to_string();
+        let session_id = session_id.map(str::to_string);
+        let restored = storage
+            .call(move |s| s.restore_deleted(&batch, session_id.as_deref()))
+            .await
+            .map_err(|e| format!("Ошибка восстановления: {}", e))?;
+
+        // Контекст разговора — последние 10 сообщений текущей сессии
+        if let Ok(history) = storage.read(|s| s.load_session_history()).await {
+            self.conversation_history = history
+                .iter()
+                .rev()
+                .take(10)
+                .rev()
+                .map(|m| Message {
+                    role: m.role.clone(),
+                    content: m.content.clone(),
+                })
+                .collect();
+
+            if let Some(client) = &mut self.gigachat_client {
+                client.set_history(
+                    self.conversation_history
+                        .iter()
+                        .map(|m| crate::ai::gigachat::Message {
+                            role: m.role.clone(),
+                            content: m.content.clone(),
+                        })
+                        .collect(),
+                );
+            }
+        }
+
+        log::info!("♻️  История восстановлена ({} сообщений)", restored);
+        Ok(restored)
     }
 
     pub fn get_history(&self) -> Vec<(String, String)> {
@@ -155,35 +982,23 @@ impl ClippyAgent {
         &self.current_model
     }
 
-    pub fn get_storage_stats(&self) -> String {
-        if let Some(ref storage) = self.storage {
-            match storage.get_stats() {
-                Ok(stats) => stats,
-                Err(e) => format!("Ошибка получения статистики: {}", e),
-            }
-        } else {
-            "Хранилище недоступно".to_string()
-        }
+    /// Handle потока хранилища (для фоновых задач, которым не нужен мьютекс агента)
+    pub fn storage_handle(&self) -> Option<StorageHandle> {
+        self.storage.clone()
     }
 
-    /// Получает информацию о погоде для города
-    pub async fn get_weather_info(&self, city: &str) -> String {
-        let city_name = if city.trim().is_empty() {
-            "Москва".to_string()
-        } else {
-            city.to_string()
-        };
-
-        log::info!("📡 Запрос погоды для города: {}", city_name);
+    /// Получает информацию о погоде для места
+    pub async fn get_weather_info(&self, place: &Place) -> String {
+        log::info!("📡 Запрос погоды для места: {}", place.display_name());
 
-        match self.weather_service.format_weather_info(&city_name).await {
+        match self.weather_service.format_weather_info(place).await {
             Ok(weather_info) => {
-                log::info!("✓ Погода получена для города: {}", city_name);
+                log::info!("✓ Погода получена для места: {}", place.name);
                 weather_info
             }
             Err(e) => {
                 log::warn!("⚠️ Ошибка получения погоды: {}", e);
-                format!("Извини, не смог получить информацию о погоде в городе '{}'. Ошибка: {}", city_name, e)
+                format!("Извини, не смог получить информацию о погоде в городе '{}'. Ошибка: {}", place.name, e)
             }
         }
     }
@@ -203,18 +1018,63 @@ impl ClippyAgent {
             }
         }
     }
+}
 
-    /// Получает структурированную информацию о погоде для виджета
-    pub async fn get_weather_data(&self, city: &str) -> Result<crate::services::WeatherInfo, String> {
-        self.weather_service.get_weather(city)
-            .await
-            .map_err(|e| format!("Ошибка получения погоды: {}", e))
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(role: &str, content: &str) -> Message {
+        Message {
+            role: role.to_string(),
+            content: content.to_string(),
+        }
     }
 
-    /// Получает структурированную информацию о курсах валют для виджета
-    pub async fn get_currency_data(&self) -> Result<Vec<crate::services::ExchangeRate>, String> {
-        self.currency_service.get_rates()
-            .await
-            .map_err(|e| format!("Ошибка получения курсов: {}", e))
+    #[test]
+    fn incognito_starts_clean_and_gives_back_the_earlier_context() {
+        let mut agent = ClippyAgent::with_store(Config::default(), Some(Box::new(InMemoryStore::new())));
+        agent.conversation_history.push_back(message("user", "обычный вопрос"));
+        agent.last_reply_uid = Some("reply".to_string());
+
+        agent.set_incognito(true);
+        assert!(agent.conversation_history.is_empty());
+        assert!(agent.last_reply_uid.is_none());
+        agent.conversation_history.push_back(message("user", "тайный вопрос"));
+
+        agent.set_incognito(false);
+        let contents: Vec<&str> = agent.conversation_history.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, ["обычный вопрос"]);
+        assert_eq!(agent.last_reply_uid.as_deref(), Some("reply"));
+    }
+
+    #[tokio::test]
+    async fn incognito_refuses_commands_that_write_storage() {
+        let mut store = InMemoryStore::new();
+        store
+            .save_messages(&[crate::services::storage::PendingMessage {
+                uid: "old".to_string(),
+                role: "user".to_string(),
+                content: "старый вопрос".to_string(),
+                model: "test".to_string(),
+                timestamp: (chrono::Local::now() - chrono::Duration::days(100)).to_rfc3339(),
+            }])
+            .unwrap();
+        let mut agent = ClippyAgent::with_store(Config::default(), Some(Box::new(store)));
+        agent.set_incognito(true);
+
+        assert!(agent.get_response("/pin").await.contains("инкогнито"));
+        assert!(agent.get_response("/alert USD > 100").await.contains("инкогнито"));
+        assert!(agent.get_response("/alerts").await.contains("нет"));
+
+        let storage = agent.storage_handle().unwrap();
+        assert!(storage.read(|s| s.rates().rate_rules()).await.unwrap().is_empty());
+        // Сессия не закреплена: старое сообщение удаляется по сроку хранения
+        let policy = crate::services::storage::RetentionPolicy {
+            max_age_days: Some(30),
+            ..Default::default()
+        };
+        let report = storage.call(move |s| s.run_maintenance(&policy)).await.unwrap();
+        assert_eq!(report.deleted, 1);
     }
 }
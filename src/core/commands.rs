@@ -0,0 +1,373 @@
+/// Локальные команды чата: начинаются с "/" и обрабатываются агентом без обращения к AI
+use crate::services::{Feedback, ImportSource};
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChatCommand {
+    /// `/import <chatgpt|gigachat> <путь>` — импорт истории из другого ассистента
+    Import { source: ImportSource, path: PathBuf },
+    /// `/search <текст>` — поиск по сохранённой истории
+    Search(String),
+    /// `/pin`, `/unpin` — закрепить текущую сессию (не удаляется политиками хранения)
+    Pin(bool),
+    /// `/trash` — содержимое корзины
+    Trash,
+    /// `/restore <номер>` — восстановить запись корзины
+    Restore(usize),
+    /// `/comment <текст>` — комментарий к последнему ответу
+    Comment(String),
+    /// `/tag <теги>` — теги к последнему ответу
+    Tag(Vec<String>),
+    /// `/bookmarks` — ответы с закладкой
+    Bookmarks,
+    /// `/export-feedback <путь>` — выгрузка оценённых ответов в JSONL
+    ExportFeedback(PathBuf),
+    /// `/attach <путь>` — прикрепить файл к последнему вопросу
+    Attach(PathBuf),
+    /// `/attachments` — вложения последнего вопроса и ответа
+    Attachments,
+    /// `/save-attachment <id> <путь>` — сохранить вложение в файл
+    SaveAttachment { id: i64, path: PathBuf },
+    /// `/backup` — резервная копия данных сейчас
+    Backup,
+    /// `/backup list` — список резервных копий
+    Backups,
+    /// `/backup restore <имя|номер>` — восстановление из копии
+    RestoreBackup(String),
+    /// `/place …`, `/places` — места для погоды
+    Place(PlaceCommand),
+    /// `/currency …` — список валют и базовая валюта
+    Currency(CurrencyCommand),
+    /// `/alert …`, `/alerts` — уведомления о курсах валют и акций
+    Alert(AlertCommand),
+    /// `/profile [имя]` — профили; с именем — выбрать профиль для следующего запуска
+    Profile(Option<String>),
+    /// `/help` — список команд
+    Help,
+    /// Неизвестная или некорректная команда (текст ошибки для пользователя)
+    Invalid(String),
+}
+
+/// Действия с местами для погоды
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlaceCommand {
+    /// `/place <город>` — варианты геокодинга для выбора
+    Search(String),
+    /// `/place save <номер>` — сохранить вариант из последнего поиска (индекс с нуля)
+    Save(usize),
+    /// `/place use <номер>` — сделать сохранённое место основным
+    Default(usize),
+    /// `/place remove <номер>` — удалить сохранённое место
+    Remove(usize),
+    /// `/places` — сохранённые места
+    List,
+}
+
+/// Действия со списком валют
+#[derive(Debug, Clone, PartialEq)]
+pub enum CurrencyCommand {
+    /// `/currency` — отслеживаемые валюты и база
+    List,
+    /// `/currency add <коды>` — добавить валюты
+    Add(Vec<String>),
+    /// `/currency remove <коды>` — убрать валюты
+    Remove(Vec<String>),
+    /// `/currency base <код>` — сменить базовую валюту
+    Base(String),
+}
+
+/// Действия с уведомлениями о курсах
+#[derive(Debug, Clone, PartialEq)]
+pub enum AlertCommand {
+    /// `/alerts` — список правил
+    List,
+    /// `/alert <правило>` — добавить правило ("USD > 100", "EUR 2%", "SBER < 250")
+    Add(String),
+    /// `/alert remove <номер>` — удалить правило (номер из списка, с единицы)
+    Remove(usize),
+}
+
+impl ChatCommand {
+    /// Разбирает ввод пользователя; `None` если это обычное сообщение
+    pub fn parse(input: &str) -> Option<Self> {
+        let input = input.trim();
+        let rest = input.strip_prefix('/')?;
+        let (name, args) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+        let args = args.trim();
+
+        let command = match name.to_lowercase().as_str() {
+            "import" => {
+                let (source, path) = args.split_once(char::is_whitespace).unwrap_or((args, ""));
+                match ImportSource::parse(source) {
+                    Some(source) if !path.trim().is_empty() => Self::Import {
+                        source,
+                        path: expand_home(path.trim()),
+                    },
+                    _ => Self::Invalid("Использование: /import <chatgpt|gigachat> <путь к файлу>".to_string()),
+                }
+            }
+            "search" | "find" => {
+                if args.is_empty() {
+                    Self::Invalid("Использование: /search <текст>".to_string())
+                } else {
+                    Self::Search(args.to_string())
+                }
+            }
+            "pin" => Self::Pin(true),
+            "unpin" => Self::Pin(false),
+            "trash" => Self::Trash,
+            "restore" => match args.parse::<usize>() {
+                Ok(index) if index > 0 => Self::Restore(index),
+                _ => Self::Invalid("Использование: /restore <номер из /trash>".to_string()),
+            },
+            "comment" => Self::Comment(args.to_string()),
+            "tag" | "tags" => {
+                let tags = Feedback::parse_tags(args);
+                if tags.is_empty() {
+                    Self::Invalid("Использование: /tag <тег1, тег2>".to_string())
+                } else {
+                    Self::Tag(tags)
+                }
+            }
+            "bookmarks" => Self::Bookmarks,
+            "export-feedback" => {
+                if args.is_empty() {
+                    Self::Invalid("Использование: /export-feedback <путь.jsonl>".to_string())
+                } else {
+                    Self::ExportFeedback(expand_home(args))
+                }
+            }
+            "attach" => {
+                if args.is_empty() {
+                    Self::Invalid("Использование: /attach <путь к файлу>".to_string())
+                } else {
+                    Self::Attach(expand_home(args))
+                }
+            }
+            "attachments" => Self::Attachments,
+            "save-attachment" => {
+                let (id, path) = args.split_once(char::is_whitespace).unwrap_or((args, ""));
+                match id.parse::<i64>() {
+                    Ok(id) if !path.trim().is_empty() => Self::SaveAttachment {
+                        id,
+                        path: expand_home(path.trim()),
+                    },
+                    _ => Self::Invalid("Использование: /save-attachment <id> <путь>".to_string()),
+                }
+            }
+            "backup" | "backups" => {
+                let (action, key) = args.split_once(char::is_whitespace).unwrap_or((args, ""));
+                match action {
+                    "" if name.eq_ignore_ascii_case("backup") => Self::Backup,
+                    "" | "list" => Self::Backups,
+                    "restore" if !key.trim().is_empty() => Self::RestoreBackup(key.trim().to_string()),
+                    _ => Self::Invalid("Использование: /backup [list | restore <имя или номер>]".to_string()),
+                }
+            }
+            "places" => Self::Place(PlaceCommand::List),
+            "place" => {
+                let (action, number) = args.split_once(char::is_whitespace).unwrap_or((args, ""));
+                let index = number.trim().parse::<usize>().ok().filter(|n| *n > 0).map(|n| n - 1);
+                match (action, index) {
+                    ("" | "list", _) => Self::Place(PlaceCommand::List),
+                    ("save", Some(i)) => Self::Place(PlaceCommand::Save(i)),
+                    ("use" | "default", Some(i)) => Self::Place(PlaceCommand::Default(i)),
+                    ("remove" | "delete", Some(i)) => Self::Place(PlaceCommand::Remove(i)),
+                    ("save" | "use" | "default" | "remove" | "delete", None) => Self::Invalid(
+                        "Использование: /place <город> | save <номер> | use <номер> | remove <номер>".to_string(),
+                    ),
+                    _ => Self::Place(PlaceCommand::Search(args.to_string())),
+                }
+            }
+            "currency" | "currencies" => {
+                let (action, codes) = args.split_once(char::is_whitespace).unwrap_or((args, ""));
+                let codes: Vec<String> = codes
+                    .split(|c: char| c == ',' || c.is_whitespace())
+                    .filter(|code| !code.is_empty())
+                    .map(|code| code.to_uppercase())
+                    .collect();
+                match (action.to_lowercase().as_str(), codes.as_slice()) {
+                    ("" | "list", _) => Self::Currency(CurrencyCommand::List),
+                    ("add", [_, ..]) => Self::Currency(CurrencyCommand::Add(codes)),
+                    ("remove" | "delete", [_, ..]) => Self::Currency(CurrencyCommand::Remove(codes)),
+                    ("base", [code]) => Self::Currency(CurrencyCommand::Base(code.clone())),
+                    _ => Self::Invalid(
+                        "Использование: /currency [add <коды> | remove <коды> | base <код>]".to_string(),
+                    ),
+                }
+            }
+            "alerts" => Self::Alert(AlertCommand::List),
+            "alert" => {
+                let (action, rest) = args.split_once(char::is_whitespace).unwrap_or((args, ""));
+                match action.to_lowercase().as_str() {
+                    "" | "list" => Self::Alert(AlertCommand::List),
+                    "remove" | "delete" => match rest.trim().parse::<usize>() {
+                        Ok(index) if index > 0 => Self::Alert(AlertCommand::Remove(index)),
+                        _ => Self::Invalid("Использование: /alert remove <номер из /alerts>".to_string()),
+                    },
+                    "add" => Self::Alert(AlertCommand::Add(rest.trim().to_string())),
+                    _ => Self::Alert(AlertCommand::Add(args.to_string())),
+                }
+            }
+            "profile" | "profiles" => Self::Profile((!args.is_empty()).then(|| args.to_string())),
+            "help" => Self::Help,
+            other => Self::Invalid(format!("Неизвестная команда /{}. Напиши /help", other)),
+        };
+
+        Some(command)
+    }
+
+    /// Команда записывает в хранилище или выгружает из него данные на диск —
+    /// в инкогнито такие команды не выполняются
+    pub fn writes_data(&self) -> bool {
+        matches!(
+            self,
+            Self::Import { .. }
+                | Self::Pin(_)
+                | Self::Restore(_)
+                | Self::Comment(_)
+                | Self::Tag(_)
+                | Self::ExportFeedback(_)
+                | Self::Attach(_)
+                | Self::SaveAttachment { .. }
+                | Self::Backup
+                | Self::RestoreBackup(_)
+                | Self::Alert(AlertCommand::Add(_) | AlertCommand::Remove(_))
+        )
+    }
+
+    /// Справка по доступным командам
+    pub fn help_text() -> String {
+        "Команды:\n\
+        • /import <chatgpt|gigachat> <файл> — импорт истории\n\
+        • /search <текст> — поиск по истории\n\
+        • /pin, /unpin — закрепить текущую сессию навсегда\n\
+        • /trash — корзина, /restore <номер> — восстановить\n\
+        • /comment <текст>, /tag <теги> — к последнему ответу\n\
+        • /bookmarks — ответы с закладкой\n\
+        • /export-feedback <файл> — оценки в JSONL\n\
+        • /attach <файл>, /attachments, /save-attachment <id> <файл> — вложения\n\
+        • /backup, /backup list, /backup restore <номер> — резервные копии\n\
+        • /place <город> — найти место для погоды, /place save|use|remove <номер>, /places — список\n\
+        • /currency — валюты на виджетах, /currency add|remove <коды>, /currency base <код>\n\
+        • /alert USD > 100, /alert EUR 2%, /alert SBER < 250 — уведомления о курсах, /alerts — список, /alert remove <номер>\n\
+        • /profile [имя] — профили пользователей (новый профиль применяется после перезапуска)\n\
+        • /help — эта справка"
+            .to_string()
+    }
+}
+
+/// Раскрывает `~/` в начале пути
+fn expand_home(path: &str) -> PathBuf {
+    match path.strip_prefix("~/") {
+        Some(rest) => dirs::home_dir().unwrap_or_else(|| PathBuf::from(".")).join(rest),
+        None => PathBuf::from(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str) -> ChatCommand {
+        ChatCommand::parse(input).expect("команда")
+    }
+
+    /// Текст ошибки для некорректной команды
+    fn invalid(input: &str) -> String {
+        match parse(input) {
+            ChatCommand::Invalid(message) => message,
+            other => panic!("{} разобрана как {:?}", input, other),
+        }
+    }
+
+    #[test]
+    fn plain_text_is_not_a_command() {
+        assert_eq!(ChatCommand::parse("привет, Скрепыш"), None);
+        assert_eq!(ChatCommand::parse("курс 1/2 доллара"), None);
+        assert_eq!(parse("  /HELP  "), ChatCommand::Help);
+        assert!(invalid("/foo bar").contains("/foo"));
+    }
+
+    #[test]
+    fn commands_with_paths_require_arguments() {
+        assert_eq!(
+            parse("/import ChatGPT  export/conversations.json "),
+            ChatCommand::Import {
+                source: ImportSource::ChatGpt,
+                path: PathBuf::from("export/conversations.json"),
+            }
+        );
+        assert!(invalid("/import chatgpt").starts_with("Использование: /import"));
+        assert!(invalid("/import claude chats.json").starts_with("Использование: /import"));
+        assert!(invalid("/export-feedback").starts_with("Использование: /export-feedback"));
+        assert!(invalid("/attach").starts_with("Использование: /attach"));
+
+        assert_eq!(
+            parse("/save-attachment 5 out/файл.bin"),
+            ChatCommand::SaveAttachment {
+                id: 5,
+                path: PathBuf::from("out/файл.bin"),
+            }
+        );
+        assert!(invalid("/save-attachment 5").starts_with("Использование: /save-attachment"));
+        assert!(invalid("/save-attachment пять out.bin").starts_with("Использование: /save-attachment"));
+    }
+
+    #[test]
+    fn numbers_from_lists_start_at_one() {
+        assert_eq!(parse("/restore 2"), ChatCommand::Restore(2));
+        assert!(invalid("/restore 0").starts_with("Использование: /restore"));
+        assert!(invalid("/restore первый").starts_with("Использование: /restore"));
+
+        // В местах номер из списка переводится в индекс с нуля
+        assert_eq!(parse("/place save 1"), ChatCommand::Place(PlaceCommand::Save(0)));
+        assert_eq!(parse("/place use 3"), ChatCommand::Place(PlaceCommand::Default(2)));
+        assert!(invalid("/place remove 0").starts_with("Использование: /place"));
+        assert_eq!(parse("/place Нижний Новгород"), ChatCommand::Place(PlaceCommand::Search("Нижний Новгород".to_string())));
+
+        assert_eq!(parse("/alert remove 3"), ChatCommand::Alert(AlertCommand::Remove(3)));
+        assert!(invalid("/alert remove 0").starts_with("Использование: /alert remove"));
+    }
+
+    #[test]
+    fn tags_backups_currencies_and_alerts() {
+        assert_eq!(
+            parse("/tag #Погода, работа"),
+            ChatCommand::Tag(vec!["погода".to_string(), "работа".to_string()])
+        );
+        assert!(invalid("/tag , #").starts_with("Использование: /tag"));
+        assert_eq!(parse("/comment"), ChatCommand::Comment(String::new()));
+
+        assert_eq!(parse("/backup"), ChatCommand::Backup);
+        assert_eq!(parse("/backups"), ChatCommand::Backups);
+        assert_eq!(parse("/backup list"), ChatCommand::Backups);
+        assert_eq!(parse("/backup restore 2"), ChatCommand::RestoreBackup("2".to_string()));
+        assert!(invalid("/backup restore").starts_with("Использование: /backup"));
+        assert!(invalid("/backup now").starts_with("Использование: /backup"));
+
+        assert_eq!(
+            parse("/currency add usd, eur"),
+            ChatCommand::Currency(CurrencyCommand::Add(vec!["USD".to_string(), "EUR".to_string()]))
+        );
+        assert_eq!(parse("/currency base cny"), ChatCommand::Currency(CurrencyCommand::Base("CNY".to_string())));
+        assert!(invalid("/currency base usd eur").starts_with("Использование: /currency"));
+        assert!(invalid("/currency add").starts_with("Использование: /currency"));
+
+        assert_eq!(parse("/alerts"), ChatCommand::Alert(AlertCommand::List));
+        assert_eq!(parse("/alert USD > 100"), ChatCommand::Alert(AlertCommand::Add("USD > 100".to_string())));
+        assert_eq!(parse("/alert add EUR 2%"), ChatCommand::Alert(AlertCommand::Add("EUR 2%".to_string())));
+        assert_eq!(parse("/profile"), ChatCommand::Profile(None));
+        assert_eq!(parse("/profile работа"), ChatCommand::Profile(Some("работа".to_string())));
+    }
+
+    #[test]
+    fn only_writing_commands_are_marked() {
+        assert!(parse("/pin").writes_data());
+        assert!(parse("/alert USD > 100").writes_data());
+        assert!(parse("/backup").writes_data());
+        assert!(!parse("/alerts").writes_data());
+        assert!(!parse("/search погода").writes_data());
+        assert!(!parse("/backup list").writes_data());
+    }
+}
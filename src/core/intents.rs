@@ -0,0 +1,1132 @@
+/// Вопросы на естественном языке, на которые агент отвечает сам, не обращаясь к AI:
+/// о погоде по прогнозу Open-Meteo ("будет ли дождь завтра", "прогноз на неделю",
+/// "можно ли сегодня бегать на улице"), пересчёт валют ("сколько будет 250 евро в долларах")
+/// цены криптовалют ("сколько стоит биткоин") и котировки акций ("как там Сбербанк")
+use crate::services::air_quality::{AirQuality, AqiLevel};
+use crate::services::crypto_prices::{find_coin, CRYPTO_VS_CURRENCIES};
+use crate::services::iso4217;
+use crate::services::stocks::stock_name;
+use crate::services::weather::{DailyForecast, Forecast, WeatherService, WeatherUnits};
+use crate::services::wmo;
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WeatherQuestion {
+    /// Погода сейчас
+    Current,
+    /// Будут ли осадки в этот день
+    Rain { day: AskedDay },
+    /// Прогноз на день
+    Day { day: AskedDay },
+    /// Прогноз на неделю
+    Week,
+    /// Качество воздуха и пыльца сейчас
+    AirQuality,
+    /// Стоит ли в этот день заниматься спортом или гулять на улице
+    Outdoor { day: AskedDay },
+}
+
+/// День из вопроса. Отсчитывается от сегодняшней даты в городе прогноза,
+/// которая может отличаться от даты на компьютере
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AskedDay {
+    /// Через столько дней от сегодня ("завтра" — 1)
+    Offset(i64),
+    /// Ближайший такой день недели (сегодняшний — через 0 дней)
+    Weekday(Weekday),
+}
+
+impl AskedDay {
+    pub fn days_from(self, today: NaiveDate) -> i64 {
+        match self {
+            Self::Offset(days) => days,
+            Self::Weekday(weekday) => {
+                let from = today.weekday().num_days_from_monday() as i64;
+                let to = weekday.num_days_from_monday() as i64;
+                (to - from).rem_euclid(7)
+            }
+        }
+    }
+}
+
+/// Основа слова и окончания её форм: слово совпадает, только если оно целиком
+/// равно основе с одним из окончаний ("среда", но не "среди")
+type WordForms = (&'static str, &'static [&'static str]);
+
+/// Окончания существительных мужского рода в единственном числе
+const MASCULINE: &[&str] = &["", "а", "у", "ом", "е"];
+/// Окончания существительных женского рода на -а
+const FEMININE: &[&str] = &["а", "ы", "е", "у", "ой", "", "ам", "ами", "ах"];
+
+/// Слова, по которым вопрос считается вопросом об осадках
+const RAIN_WORDS: &[WordForms] = &[
+    ("дожд", &["ь", "я", "ю", "ём", "ем", "е", "и", "ей", "ям", "ями", "ях", "ик", "ичек", "ливо", "ливый", "ливая", "ливое", "ливые"]),
+    ("ливень", &[""]),
+    ("ливн", &["я", "ю", "ем", "е", "и", "ей", "ями"]),
+    ("осадк", &["и", "ов", "ам", "ами", "ах"]),
+    ("снег", &["", "а", "у", "ом", "е", "опад", "опада", "опады", "опадов"]),
+    ("гроз", FEMININE),
+];
+/// Зонт — вопрос об осадках, только если о нём спрашивают ("нужен ли зонт") или называют день
+const UMBRELLA_WORDS: &[WordForms] = &[("зонт", &["", "а", "ом", "ик", "ика", "иком"])];
+/// Слова о погоде
+const WEATHER_WORDS: &[WordForms] = &[("погод", &["а", "ы", "е", "у", "ой", "ка", "ку", "ки", "кой"])];
+/// "Прогноз" без слова "погода" — вопрос о погоде, только если рядом нет ничего,
+/// кроме дней и служебных слов ("прогноз на неделю", но не "прогноз продаж")
+const FORECAST_WORDS: &[WordForms] = &[("прогноз", &["", "а", "ом"])];
+const FORECAST_FILLERS: &[&str] = &[
+    "а", "на", "в", "во", "и", "мне", "какой", "покажи", "дай", "скажи", "пожалуйста", "дней", "дня", "ближайшие",
+];
+const WEEK_WORDS: &[WordForms] = &[("недел", &["я", "и", "е", "ю", "ей"])];
+/// Занятия на улице — вопрос о погоде, только если в нём есть день, "сейчас" или улица
+/// ("можно ли сегодня бегать", но не "люблю гулять с собакой")
+const OUTDOOR_WORDS: &[WordForms] = &[
+    ("бега", &["ть"]),
+    ("побега", &["ть"]),
+    ("пробежа", &["ться"]),
+    ("пробежк", FEMININE),
+    ("гуля", &["ть"]),
+    ("погуля", &["ть"]),
+    ("прогулк", FEMININE),
+    ("велосипед", &["", "е", "ом", "ах"]),
+];
+/// Где и когда: без этих слов занятия на улице и воздух — не вопрос о погоде
+const OUTSIDE_WORDS: &[WordForms] = &[("улиц", &["а", "е", "у"]), ("сейчас", &[""])];
+/// Воздух — вопрос о качестве воздуха, только вместе с днём, "сейчас", улицей
+/// или словами о качестве ("какой сегодня воздух", но не "воздушный шар" или "нужен воздух в шинах")
+const AIR_WORDS: &[WordForms] = &[("воздух", &["", "а", "е", "ом"])];
+const AIR_QUALITY_WORDS: &[WordForms] = &[
+    ("качеств", &["о", "а"]),
+    ("загрязн", &["ение", "ения", "ён", "ен", "ена", "ено"]),
+    ("чист", &["ый", "ым", "ая"]),
+    ("грязн", &["ый", "ым", "ая"]),
+    ("дыша", &["ть"]),
+];
+/// Пыльца и смог — вопрос о воздухе сами по себе ("смог" без окончания — глагол, поэтому не считается)
+const POLLEN_WORDS: &[WordForms] = &[
+    ("пыльц", &["а", "ы", "е", "у", "ой"]),
+    ("смог", &["а", "ом", "е"]),
+    ("aqi", &[""]),
+];
+/// Аллергия — вопрос о пыльце, только вместе с днём или "сейчас" ("аллергия на кошек" — нет)
+const ALLERGY_WORDS: &[WordForms] = &[("аллерги", &["я", "и", "ю", "ей"]), ("аллергик", &["", "ам", "у", "ов"])];
+
+/// Пороги для прогулок и пробежек
+const OUTDOOR_MIN_CELSIUS: f32 = -15.0;
+const OUTDOOR_MAX_CELSIUS: f32 = 30.0;
+const OUTDOOR_MAX_WIND_MS: f32 = 12.0;
+/// Вероятность осадков в ближайшие 3 часа, при которой стоит взять дождевик
+const OUTDOOR_RAIN_PROBABILITY: i32 = 60;
+
+const WEEKDAYS: &[(WordForms, Weekday)] = &[
+    (("понедельник", MASCULINE), Weekday::Mon),
+    (("вторник", MASCULINE), Weekday::Tue),
+    (("сред", &["а", "у", "ы", "е"]), Weekday::Wed),
+    (("четверг", MASCULINE), Weekday::Thu),
+    (("пятниц", &["а", "у", "ы", "е"]), Weekday::Fri),
+    (("суббот", &["а", "у", "ы", "е"]), Weekday::Sat),
+    (("воскресень", &["е", "я", "ю"]), Weekday::Sun),
+];
+
+/// Пересчёт суммы между валютами
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConversionQuestion {
+    pub amount: f64,
+    pub from: String,
+    /// `None` — в базовую валюту ("сколько будет 100 долларов")
+    pub to: Option<String>,
+}
+
+/// Окончания существительных мужского рода в обоих числах ("доллар", "долларов", "долларах")
+const MASCULINE_BOTH: &[&str] = &["", "а", "у", "ом", "е", "ы", "ов", "ам", "ами", "ах"];
+
+/// Названия валют во всех падежах. Слово должно совпасть с формой целиком ("лира", но не
+/// "лирика"; "франк", но не "Франкфурт"); формы драма, совпадающие с "драма", пропущены
+const CURRENCY_WORDS: &[(WordForms, &str)] = &[
+    (("доллар", MASCULINE_BOTH), "USD"),
+    (("бакс", MASCULINE_BOTH), "USD"),
+    (("евро", &[""]), "EUR"),
+    (("рубл", &["ь", "я", "ю", "ём", "ем", "е", "и", "ей", "ям", "ями", "ях"]), "RUB"),
+    (("руб", &[""]), "RUB"),
+    (("фунт", MASCULINE_BOTH), "GBP"),
+    (("юан", &["ь", "я", "ю", "ем", "е", "и", "ей", "ям", "ями", "ях"]), "CNY"),
+    (("иен", FEMININE), "JPY"),
+    (("йен", FEMININE), "JPY"),
+    (("франк", MASCULINE_BOTH), "CHF"),
+    (("тенге", &[""]), "KZT"),
+    (("лир", FEMININE), "TRY"),
+    (("злот", &["ый", "ого", "ому", "ым", "ом", "ые", "ых", "ыми"]), "PLN"),
+    (("гривн", &["а", "ы", "е", "у", "ой", "ам", "ами", "ах"]), "UAH"),
+    (("гривен", &[""]), "UAH"),
+    (("драм", &["", "у", "ом", "ов", "ам", "ами", "ах"]), "AMD"),
+    (("лари", &[""]), "GEL"),
+    (("манат", MASCULINE_BOTH), "AZN"),
+    (("дирхам", MASCULINE_BOTH), "AED"),
+    (("рупи", &["я", "и", "ю", "ей", "й", "ям", "ями", "ях"]), "INR"),
+];
+/// Знаки валют
+const CURRENCY_SIGNS: &[(char, &str)] = &[('$', "USD"), ('€', "EUR"), ('£', "GBP"), ('¥', "JPY"), ('₽', "RUB")];
+/// Предлоги перед валютой, в которую пересчитывать: "в долларах", "to EUR"
+const TARGET_WORDS: &[&str] = &["в", "во", "to", "in", "into"];
+/// Просьбы о пересчёте, после которых валюту можно не называть — пересчёт в базовую:
+/// "переведи 100 долларов", "сколько будет 100 долларов"
+const CONVERSION_WORDS: &[WordForms] = &[
+    ("перевед", &["и", "ите"]),
+    ("переве", &["сти"]),
+    ("конверт", &["ируй", "ируйте", "ировать"]),
+    ("пересчита", &["й", "йте", "ть"]),
+    ("convert", &[""]),
+];
+
+/// Вопрос о цене криптовалют: "сколько стоит биткоин", "курс эфира в долларах",
+/// "0.5 BTC в рублях", "как там крипта"
+#[derive(Debug, Clone, PartialEq)]
+pub struct CryptoQuestion {
+    /// Тикеры; пусто — монеты с виджетов
+    pub coins: Vec<String>,
+    /// `None` — валюта цен по умолчанию (CLIPPY_CRYPTO_VS)
+    pub vs: Option<String>,
+    pub amount: Option<f64>,
+}
+
+/// Названия монет во всех падежах
+const COIN_WORDS: &[(WordForms, &str)] = &[
+    (("биткоин", MASCULINE_BOTH), "BTC"),
+    (("биткойн", MASCULINE_BOTH), "BTC"),
+    (("битк", &["а", "и", "е", "у", "ой"]), "BTC"),
+    // "в прямом эфире" — не монета
+    (("эфир", &["", "а", "у", "ом"]), "ETH"),
+    (("эфириум", MASCULINE_BOTH), "ETH"),
+    (("этериум", MASCULINE_BOTH), "ETH"),
+    (("тезер", MASCULINE_BOTH), "USDT"),
+    (("тетер", MASCULINE_BOTH), "USDT"),
+    (("солан", &["а", "ы", "е", "у", "ой"]), "SOL"),
+    (("рипл", &["", "а", "у", "ом", "е"]), "XRP"),
+    (("тонкоин", MASCULINE_BOTH), "TON"),
+    (("догекоин", MASCULINE_BOTH), "DOGE"),
+    (("догикоин", MASCULINE_BOTH), "DOGE"),
+    (("лайткоин", MASCULINE_BOTH), "LTC"),
+    (("кардано", &[""]), "ADA"),
+];
+/// Криптовалюты вообще — монеты с виджетов ("как там крипта", но не "скрипт")
+const CRYPTO_WORDS: &[WordForms] = &[("крипт", FEMININE), ("криптовалют", FEMININE)];
+/// Без этих слов (или суммы) упоминание монеты или акции — не вопрос о цене
+const PRICE_WORDS: &[WordForms] = &[
+    ("сколько", &[""]),
+    ("курс", &["", "а", "е", "ом", "ы"]),
+    ("цен", &["а", "у", "ы", "е", "ой"]),
+    ("сто", &["ит", "ят"]),
+    ("почём", &[""]),
+    ("почем", &[""]),
+];
+/// "Как там ..."/"что там с ..." — вопрос о цене, только если сразу за ними названа монета или акция
+const HOW_IS_WORDS: &[[&str; 2]] = &[["как", "там"], ["что", "там"]];
+
+/// Вопрос о котировках акций Мосбиржи: "как там Сбербанк", "сколько стоит газпром",
+/// "что там с акциями"
+#[derive(Debug, Clone, PartialEq)]
+pub struct StockQuestion {
+    /// Тикеры; пусто — акции с виджетов
+    pub tickers: Vec<String>,
+    /// Сколько акций ("10 акций сбера")
+    pub amount: Option<f64>,
+}
+
+/// Окончания слов на мягкий знак женского рода: "нефть", "нефти", "нефтью"
+const SOFT_FEMININE: &[&str] = &["ь", "и", "ью"];
+/// Названия компаний во всех падежах; названия из двух слов ("Газпром нефть",
+/// "Интер РАО") ищутся и слитно
+const STOCK_WORDS: &[(WordForms, &str)] = &[
+    (("сбер", MASCULINE), "SBER"),
+    (("сбербанк", MASCULINE), "SBER"),
+    (("газпромнефт", SOFT_FEMININE), "SIBN"),
+    (("газпром", MASCULINE), "GAZP"),
+    (("лукойл", MASCULINE), "LKOH"),
+    (("роснефт", SOFT_FEMININE), "ROSN"),
+    (("норникел", &["ь", "я", "ю", "ем", "е"]), "GMKN"),
+    (("яндекс", MASCULINE), "YDEX"),
+    (("втб", &[""]), "VTBR"),
+    (("новатэк", MASCULINE), "NVTK"),
+    (("магнит", MASCULINE), "MGNT"),
+    (("полюс", MASCULINE), "PLZL"),
+    (("татнефт", SOFT_FEMININE), "TATN"),
+    (("мтс", &[""]), "MTSS"),
+    (("аэрофлот", MASCULINE), "AFLT"),
+    (("алрос", FEMININE), "ALRS"),
+    (("северстал", SOFT_FEMININE), "CHMF"),
+    (("нлмк", &[""]), "NLMK"),
+    (("мосбирж", &["а", "и", "е", "у", "ей"]), "MOEX"),
+    (("сургутнефтегаз", MASCULINE), "SNGS"),
+    (("озон", MASCULINE), "OZON"),
+    (("фосагро", &[""]), "PHOR"),
+    (("русал", MASCULINE), "RUAL"),
+    (("интеррао", &[""]), "IRAO"),
+    (("русгидро", &[""]), "HYDR"),
+];
+/// Акции вообще — акции с виджетов. Только множественное число: "акция в магазине" — не о бирже
+const ALL_STOCKS_WORDS: &[WordForms] = &[("акци", &["и", "й", "ям", "ями", "ях"])];
+/// После числа — количество акций: "10 акций сбера", "1 акция", "2 акции"
+const SHARES_WORDS: &[WordForms] = &[("акци", &["я", "и", "й"])];
+/// Без этих слов (или числа акций) название компании — не вопрос о котировке
+const QUOTE_WORDS: &[WordForms] = &[
+    ("котировк", &["а", "и", "е", "у", "ой", "ок", "ам", "ами", "ах"]),
+    ("торг", &["и", "ов", "ам", "ах"]),
+];
+
+/// Слово или знак в тексте вопроса
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Number(String),
+    Sign(char),
+}
+
+impl ConversionQuestion {
+    /// Распознаёт "сколько будет 250 евро в долларах", "100 usd в EUR", "$1.5 тыс в рублях":
+    /// сумма стоит рядом с валютой, а валюта, в которую пересчитывать, идёт после "в"/"to".
+    /// Без неё — только просьба о пересчёте в базовую валюту ("переведи 100 долларов").
+    /// `None` — вопрос не о пересчёте
+    pub fn parse(input: &str) -> Option<Self> {
+        let tokens = tokenize(input);
+        let lower: Vec<String> = words(&tokens).iter().map(|w| w.to_lowercase()).collect();
+        let lower: Vec<&str> = lower.iter().map(String::as_str).collect();
+
+        // "сколько стоит 1 биткоин в рублях" — вопрос о цене монеты
+        if words(&tokens).iter().any(|word| coin_word(word).is_some()) {
+            return None;
+        }
+
+        let (amount, from, rest) = (0..tokens.len()).find_map(|i| amount_with_currency(&tokens, i))?;
+        let to = (rest..tokens.len()).find_map(|i| match &tokens[i] {
+            Token::Word(word) if TARGET_WORDS.contains(&word.to_lowercase().as_str()) => currency_at(&tokens, i + 1, false),
+            _ => None,
+        });
+        let asks_conversion = has_form(&lower, CONVERSION_WORDS)
+            || lower.windows(2).any(|pair| pair == ["сколько", "будет"]);
+        if to.is_none() && !asks_conversion {
+            return None;
+        }
+
+        Some(Self {
+            amount,
+            from: from.to_string(),
+            to: to.map(str::to_string),
+        })
+    }
+}
+
+/// Сумма в позиции `i` с валютой вплотную к ней: "250 евро", "10 тыс рублей", "$1.5", "100 USD".
+/// Возвращает сумму, валюту и позицию, с которой искать валюту пересчёта
+fn amount_with_currency(tokens: &[Token], i: usize) -> Option<(f64, &'static str, usize)> {
+    let Token::Number(number) = &tokens[i] else {
+        return None;
+    };
+    let mut amount = number.parse::<f64>().ok()?;
+    let mut next = i + 1;
+    if let Some(Token::Word(word)) = tokens.get(next) {
+        let word = word.to_lowercase();
+        let multiplier = if word.starts_with("тыс") || word == "k" {
+            1e3
+        } else if word.starts_with("млн") || word.starts_with("миллион") {
+            1e6
+        } else {
+            1.0
+        };
+        if multiplier > 1.0 {
+            amount *= multiplier;
+            next += 1;
+        }
+    }
+
+    if let Some(code) = currency_at(tokens, next, true) {
+        let adjective = matches!(&tokens[next], Token::Word(w) if w.to_lowercase().starts_with("белорус"));
+        return Some((amount, code, next + if adjective { 2 } else { 1 }));
+    }
+    // Знак или код перед суммой: "$100", "USD 100"
+    let before = i.checked_sub(1).and_then(|p| match &tokens[p] {
+        Token::Sign(sign) => sign_currency(*sign),
+        Token::Word(word) => ascii_code(word, false),
+        Token::Number(_) => None,
+    })?;
+    Some((amount, before, next))
+}
+
+/// Валюта в позиции `i` (можно с "белорусских" перед ней); `near_amount` — сразу
+/// рядом с суммой, где латинский код можно писать строчными
+fn currency_at(tokens: &[Token], i: usize, near_amount: bool) -> Option<&'static str> {
+    match tokens.get(i)? {
+        Token::Sign(sign) => sign_currency(*sign),
+        Token::Word(word) if word.to_lowercase().starts_with("белорус") => match tokens.get(i + 1)? {
+            Token::Word(next) => currency_word(next, Some(word), near_amount),
+            _ => None,
+        },
+        Token::Word(word) => currency_word(word, None, near_amount),
+        Token::Number(_) => None,
+    }
+}
+
+fn sign_currency(sign: char) -> Option<&'static str> {
+    CURRENCY_SIGNS.iter().find(|(s, _)| *s == sign).map(|(_, code)| *code)
+}
+
+fn previous_word(tokens: &[Token], i: usize) -> Option<&str> {
+    match i.checked_sub(1).map(|p| &tokens[p]) {
+        Some(Token::Word(word)) => Some(word),
+        _ => None,
+    }
+}
+
+/// Латинский код валюты: заглавными ("TRY") или строчными рядом с суммой ("100 usd"),
+/// иначе это обычное английское слово ("try", "all", "top")
+fn ascii_code(word: &str, near_amount: bool) -> Option<&'static str> {
+    let uppercase = word.bytes().all(|b| b.is_ascii_uppercase());
+    if word.len() != 3 || !word.is_ascii() || !(uppercase || near_amount) {
+        return None;
+    }
+    iso4217::lookup(word).map(|c| c.code)
+}
+
+impl CryptoQuestion {
+    /// Распознаёт вопрос о цене монет; `None` — обычное сообщение. Тикеры латиницей
+    /// принимаются только заглавными ("TON", но не "ton"), сумма — только перед монетой
+    pub fn parse(input: &str) -> Option<Self> {
+        let tokens = tokenize(input);
+        let lower: Vec<String> = words(&tokens).iter().map(|w| w.to_lowercase()).collect();
+        let lower: Vec<&str> = lower.iter().map(String::as_str).collect();
+
+        let mut coins: Vec<String> = Vec::new();
+        let mut vs = None;
+        let mut amount = None;
+        for (i, token) in tokens.iter().enumerate() {
+            match token {
+                Token::Word(word) => {
+                    if let Some(coin) = coin_word(word) {
+                        if !coins.iter().any(|c| c == coin) {
+                            coins.push(coin.to_string());
+                        }
+                        if let Some(Token::Number(number)) = i.checked_sub(1).map(|p| &tokens[p]) {
+                            amount = amount.or(number.parse::<f64>().ok());
+                        }
+                    } else if let Some(code) = currency_word(word, previous_word(&tokens, i), false) {
+                        vs = vs.or(Some(code));
+                    }
+                }
+                Token::Sign(sign) => vs = vs.or(sign_currency(*sign)),
+                Token::Number(_) => {}
+            }
+        }
+
+        let all_coins = coins.is_empty() && has_form(&lower, CRYPTO_WORDS);
+        if coins.is_empty() && !all_coins {
+            return None;
+        }
+        let asks_price = has_form(&lower, PRICE_WORDS)
+            || asks_how_is(&lower, |word| coin_word(word).is_some() || is_form_of(word, CRYPTO_WORDS));
+        if amount.is_none() && !asks_price {
+            return None;
+        }
+
+        Some(Self {
+            coins,
+            vs: vs.filter(|code| CRYPTO_VS_CURRENCIES.contains(code)).map(str::to_string),
+            amount,
+        })
+    }
+}
+
+/// "Как там X", "что там с X" в любом регистре: `names` проверяет, что X — название
+/// монеты или акции (слово передаётся как написано)
+fn asks_how_is(words: &[&str], names: impl Fn(&str) -> bool) -> bool {
+    words.windows(2).enumerate().any(|(i, pair)| {
+        let pair = [pair[0].to_lowercase(), pair[1].to_lowercase()];
+        if !HOW_IS_WORDS.iter().any(|how| how == &pair) {
+            return false;
+        }
+        let mut next = i + 2;
+        if words.get(next).is_some_and(|w| matches!(w.to_lowercase().as_str(), "с" | "со")) {
+            next += 1;
+        }
+        words.get(next).is_some_and(|w| names(w))
+    })
+}
+
+impl StockQuestion {
+    /// Распознаёт вопрос о котировках; `watchlist` — тикеры с виджетов, их можно
+    /// называть латиницей заглавными ("как там PIKK"; "sber" — не тикер). Число в вопросе допускается только как
+    /// количество акций ("10 акций сбера"): "как там сбер в 2024" — не о сегодняшней цене.
+    /// `None` — обычное сообщение
+    pub fn parse(input: &str, watchlist: &[String]) -> Option<Self> {
+        let tokens = tokenize(input);
+        let words = words(&tokens);
+        let lower: Vec<String> = words.iter().map(|w| w.to_lowercase()).collect();
+        let lower: Vec<&str> = lower.iter().map(String::as_str).collect();
+
+        let mut tickers: Vec<String> = Vec::new();
+        for (i, word) in words.iter().enumerate() {
+            let ticker = match words.get(i + 1).and_then(|next| stock_word(&format!("{}{}", word, next), watchlist)) {
+                Some(ticker) => Some(ticker),
+                None => stock_word(word, watchlist),
+            };
+            if let Some(ticker) = ticker.filter(|t| !tickers.contains(t)) {
+                tickers.push(ticker);
+            }
+        }
+
+        let all_stocks = tickers.is_empty() && has_form(&lower, ALL_STOCKS_WORDS);
+        if tickers.is_empty() && !all_stocks {
+            return None;
+        }
+
+        let mut amount = None;
+        for (i, token) in tokens.iter().enumerate() {
+            let Token::Number(number) = token else {
+                continue;
+            };
+            match tokens.get(i + 1) {
+                Some(Token::Word(next)) if is_form_of(&next.to_lowercase(), SHARES_WORDS) && amount.is_none() => {
+                    amount = number.parse::<f64>().ok();
+                }
+                _ => return None,
+            }
+        }
+
+        let asks_quote = has_form(&lower, PRICE_WORDS)
+            || has_form(&lower, QUOTE_WORDS)
+            || asks_how_is(&words, |word| {
+                stock_word(word, watchlist).is_some() || is_form_of(&word.to_lowercase(), ALL_STOCKS_WORDS)
+            });
+        if amount.is_none() && !asks_quote {
+            return None;
+        }
+
+        Some(Self { tickers, amount })
+    }
+}
+
+/// Тикер по названию компании в любом падеже или по тикеру латиницей — только
+/// заглавными, как в `coin_word` (известному или с виджетов)
+fn stock_word(word: &str, watchlist: &[String]) -> Option<String> {
+    if word.is_ascii() {
+        let uppercase = word.bytes().all(|b| b.is_ascii_uppercase() || b.is_ascii_digit());
+        let known = stock_name(word).is_some() || watchlist.iter().any(|t| t == word);
+        return (uppercase && known).then(|| word.to_string());
+    }
+    let word = word.to_lowercase();
+    STOCK_WORDS
+        .iter()
+        .find(|(forms, _)| is_form_of(&word, std::slice::from_ref(forms)))
+        .map(|(_, ticker)| ticker.to_string())
+}
+
+/// Тикер монеты по слову: "биткоина", "эфир", "BTC" (латиницей — только заглавными:
+/// "ton", "sol" и "ada" — обычные слова)
+fn coin_word(word: &str) -> Option<&'static str> {
+    if word.is_ascii() {
+        let uppercase = word.bytes().all(|b| b.is_ascii_uppercase());
+        return find_coin(word).filter(|_| uppercase).map(|c| c.symbol);
+    }
+    let word = word.to_lowercase();
+    COIN_WORDS
+        .iter()
+        .find(|(forms, _)| is_form_of(&word, std::slice::from_ref(forms)))
+        .map(|(_, symbol)| *symbol)
+}
+
+/// Слова, числа ("1,5", "2.75", "10 000") и знаки валют
+fn tokenize(text: &str) -> Vec<Token> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let start = i;
+        if chars[i].is_ascii_digit() {
+            let mut number = String::new();
+            while i < chars.len() {
+                let c = chars[i];
+                if c.is_ascii_digit() {
+                    number.push(c);
+                } else if (c == '.' || c == ',') && !number.contains('.') && chars.get(i + 1).is_some_and(char::is_ascii_digit) {
+                    number.push('.');
+                } else if c != ' ' || !is_digit_group(&chars[i + 1..]) || number.contains('.') {
+                    break;
+                }
+                i += 1;
+            }
+            tokens.push(Token::Number(number));
+        } else if chars[i].is_alphabetic() {
+            while i < chars.len() && chars[i].is_alphabetic() {
+                i += 1;
+            }
+            tokens.push(Token::Word(chars[start..i].iter().collect()));
+        } else {
+            if CURRENCY_SIGNS.iter().any(|(sign, _)| *sign == chars[i]) {
+                tokens.push(Token::Sign(chars[i]));
+            }
+            i += 1;
+        }
+    }
+    tokens
+}
+
+/// Ровно три цифры — продолжение числа после пробела-разделителя разрядов
+fn is_digit_group(rest: &[char]) -> bool {
+    rest.len() >= 3 && rest[..3].iter().all(char::is_ascii_digit) && !rest.get(3).is_some_and(char::is_ascii_digit)
+}
+
+/// Код валюты по слову: "евро", "долларах", "USD"; "белорусских рублей" — BYN
+fn currency_word(word: &str, previous: Option<&str>, near_amount: bool) -> Option<&'static str> {
+    if word.is_ascii() {
+        return ascii_code(word, near_amount);
+    }
+    let word = word.to_lowercase();
+    let (_, code) = CURRENCY_WORDS.iter().find(|(forms, _)| is_form_of(&word, std::slice::from_ref(forms)))?;
+    let belarusian = previous.is_some_and(|w| w.to_lowercase().starts_with("белорус"));
+    Some(if *code == "RUB" && belarusian { "BYN" } else { code })
+}
+
+impl WeatherQuestion {
+    /// Распознаёт вопрос о погоде; `None` — обычное сообщение для AI
+    pub fn parse(input: &str) -> Option<Self> {
+        let text = input.to_lowercase();
+        let tokens = tokenize(&text);
+        let words = words(&tokens);
+
+        let day = asked_day(&words);
+        let fresh_air = words.windows(2).any(|pair| matches!(pair, ["свежем", "воздухе"]));
+        let about_now = day.is_some() || has_form(&words, OUTSIDE_WORDS) || has_form(&words, WEATHER_WORDS);
+
+        // "можно ли побегать на свежем воздухе" — вопрос о пробежке, а не о воздухе
+        if has_form(&words, OUTDOOR_WORDS) && (about_now || fresh_air) {
+            return Some(Self::Outdoor {
+                day: day.unwrap_or(AskedDay::Offset(0)),
+            });
+        }
+        let asks_air = has_form(&words, AIR_WORDS) && !fresh_air && (about_now || has_form(&words, AIR_QUALITY_WORDS));
+        if asks_air || has_form(&words, POLLEN_WORDS) || (has_form(&words, ALLERGY_WORDS) && about_now) {
+            return Some(Self::AirQuality);
+        }
+
+        let asks_rain = has_form(&words, RAIN_WORDS)
+            || (has_form(&words, UMBRELLA_WORDS) && (words.contains(&"ли") || day.is_some()));
+        let bare_forecast = has_form(&words, FORECAST_WORDS)
+            && words.iter().all(|w| {
+                FORECAST_FILLERS.contains(w)
+                    || is_form_of(w, FORECAST_WORDS)
+                    || is_form_of(w, WEEK_WORDS)
+                    || asked_day(&[w]).is_some()
+            });
+        let asks_weather = has_form(&words, WEATHER_WORDS) || bare_forecast;
+
+        if !asks_rain && !asks_weather {
+            return None;
+        }
+
+        let seven_days = tokens
+            .windows(2)
+            .any(|pair| matches!(pair, [Token::Number(n), Token::Word(w)] if n == "7" && w == "дней"));
+        if asks_weather && (has_form(&words, WEEK_WORDS) || seven_days) {
+            return Some(Self::Week);
+        }
+
+        if asks_rain {
+            Some(Self::Rain {
+                day: day.unwrap_or(AskedDay::Offset(0)),
+            })
+        } else {
+            Some(day.map_or(Self::Current, |day| Self::Day { day }))
+        }
+    }
+
+    /// Для ответа нужны данные о качестве воздуха
+    pub fn needs_air_quality(&self) -> bool {
+        matches!(self, Self::AirQuality | Self::Outdoor { .. })
+    }
+
+    /// Ответ по прогнозу (и качеству воздуха, если оно нужно и доступно).
+    /// Дни отсчитываются от сегодняшней даты в городе прогноза
+    pub fn answer(&self, forecast: &Forecast, air: Option<&AirQuality>) -> String {
+        let city = &forecast.current.city;
+        let today = forecast.today();
+        match self {
+            Self::AirQuality => match air {
+                Some(air) => air.format(),
+                None => "Данные о качестве воздуха сейчас недоступны.".to_string(),
+            },
+            Self::Outdoor { day } => {
+                let days = day.days_from(today);
+                if days == 0 {
+                    outdoor_advice(forecast, air)
+                } else {
+                    let date = today + Duration::days(days);
+                    match forecast.day(date) {
+                        Some(day) => outdoor_day_advice(day, days, forecast),
+                        None => out_of_range(),
+                    }
+                }
+            }
+            Self::Current => WeatherService::format_weather(&forecast.current),
+            Self::Rain { day } => {
+                let days = day.days_from(today);
+                let date = today + Duration::days(days);
+                match forecast.day(date) {
+                    Some(day) if day.is_wet() => format!(
+                        "☔ {} в городе {} ожидаются осадки: {}, вероятность {}%, {:.1} мм. Лучше взять зонт!",
+                        capitalize(&day_name(days, date)),
+                        city,
+                        wmo::description(day.weather_code).to_lowercase(),
+                        day.precipitation_probability,
+                        day.precipitation_sum
+                    ),
+                    Some(day) => format!(
+                        "🌤️ {} в городе {} дождя не ожидается (вероятность {}%). {}",
+                        capitalize(&day_name(days, date)),
+                        city,
+                        day.precipitation_probability,
+                        format_day(day, &forecast.units)
+                    ),
+                    None => out_of_range(),
+                }
+            }
+            Self::Day { day } => {
+                let days = day.days_from(today);
+                let date = today + Duration::days(days);
+                match forecast.day(date) {
+                    Some(day) => format!(
+                        "🌍 Прогноз на {} в городе {}:\n{}",
+                        day_name(days, date),
+                        city,
+                        format_day(day, &forecast.units)
+                    ),
+                    None => out_of_range(),
+                }
+            }
+            Self::Week => {
+                let mut text = format!("📅 Прогноз на неделю в городе {}:", city);
+                for day in &forecast.daily {
+                    text.push_str(&format!(
+                        "\n• {} {} {}: {}…{}, {}, осадки {}%",
+                        weekday_short(day.date.weekday()),
+                        day.date.format("%d.%m"),
+                        wmo::icon(day.weather_code, true),
+                        forecast.units.temperature(day.temperature_min),
+                        forecast.units.temperature(day.temperature_max),
+                        wmo::description(day.weather_code).to_lowercase(),
+                        day.precipitation_probability
+                    ));
+                }
+                text
+            }
+        }
+    }
+}
+
+/// Совет о пробежке или прогулке: воздух, пыльца, осадки в ближайшие часы, температура, ветер
+fn outdoor_advice(forecast: &Forecast, air: Option<&AirQuality>) -> String {
+    let units = &forecast.units;
+    let current = &forecast.current;
+    // Серьёзные причины остаться дома и мелкие неудобства
+    let mut blockers = Vec::new();
+    let mut cautions = Vec::new();
+
+    match air.and_then(|a| a.level().map(|level| (a, level))) {
+        Some((air, level)) if level >= AqiLevel::Poor => blockers.push(format!(
+            "{} воздух {} (EAQI {})",
+            level.icon(),
+            level.label(),
+            air.european_aqi.unwrap_or_default()
+        )),
+        Some((_, AqiLevel::Moderate)) => {
+            cautions.push("🟡 воздух умеренно загрязнён — лучше короткая тренировка без рекордов".to_string())
+        }
+        Some(_) => {}
+        None => cautions.push("качество воздуха неизвестно".to_string()),
+    }
+
+    if let Some(air) = air {
+        let pollen: Vec<&str> = air.notable_pollen().iter().map(|p| p.name.as_str()).collect();
+        if air.has_high_pollen() {
+            cautions.push(format!("🌼 много пыльцы ({}) — аллергикам лучше остаться дома", pollen.join(", ")));
+        } else if !pollen.is_empty() {
+            cautions.push(format!("🌼 есть пыльца ({})", pollen.join(", ")));
+        }
+    }
+
+    if matches!(current.weather_code, 95..=99) {
+        blockers.push("⛈ гроза".to_string());
+    } else if let Some(hour) = forecast
+        .hourly
+        .iter()
+        .take(3)
+        .find(|h| h.precipitation_probability >= OUTDOOR_RAIN_PROBABILITY)
+    {
+        cautions.push(format!(
+            "☔ около {} возможны осадки ({}%)",
+            hour.time.format("%H:%M"),
+            hour.precipitation_probability
+        ));
+    }
+
+    let celsius = units.celsius(current.apparent_temperature);
+    if !(OUTDOOR_MIN_CELSIUS..=OUTDOOR_MAX_CELSIUS).contains(&celsius) {
+        blockers.push(format!("🌡️ ощущается как {}", units.temperature(current.apparent_temperature)));
+    }
+    if units.meters_per_second(current.wind_speed) > OUTDOOR_MAX_WIND_MS {
+        cautions.push(format!("💨 сильный ветер, {}", units.wind(current.wind_speed)));
+    }
+
+    let mut text = format!(
+        "🏃 Выйти на улицу в городе {} сейчас — {}\n• {} {}, {}",
+        current.city,
+        outdoor_verdict(&blockers, &cautions),
+        current.icon(),
+        units.temperature(current.temperature),
+        current.description.to_lowercase()
+    );
+    if let Some(level) = air.and_then(AirQuality::level) {
+        text.push_str(&format!(", воздух {}", level.label()));
+    }
+    push_reasons(&mut text, &blockers, &cautions);
+    text
+}
+
+/// Совет о пробежке или прогулке в другой день — по дневному прогнозу.
+/// Качество воздуха известно только на сейчас, поэтому в совет оно не входит
+fn outdoor_day_advice(day: &DailyForecast, days: i64, forecast: &Forecast) -> String {
+    let units = &forecast.units;
+    let mut blockers = Vec::new();
+    let mut cautions = Vec::new();
+
+    if matches!(day.weather_code, 95..=99) {
+        blockers.push("⛈ гроза".to_string());
+    } else if day.precipitation_probability >= OUTDOOR_RAIN_PROBABILITY {
+        cautions.push(format!("☔ возможны осадки ({}%)", day.precipitation_probability));
+    }
+    if units.celsius(day.temperature_max) > OUTDOOR_MAX_CELSIUS {
+        blockers.push(format!("🌡️ днём до {}", units.temperature(day.temperature_max)));
+    } else if units.celsius(day.temperature_max) < OUTDOOR_MIN_CELSIUS {
+        blockers.push(format!("🌡️ даже днём {}", units.temperature(day.temperature_max)));
+    }
+    if units.meters_per_second(day.wind_speed_max) > OUTDOOR_MAX_WIND_MS {
+        cautions.push(format!("💨 сильный ветер, до {}", units.wind(day.wind_speed_max)));
+    }
+
+    let mut text = format!(
+        "🏃 Выйти на улицу в городе {} {} — {}\n• {}",
+        forecast.current.city,
+        day_name(days, day.date),
+        outdoor_verdict(&blockers, &cautions),
+        format_day(day, units)
+    );
+    push_reasons(&mut text, &blockers, &cautions);
+    text.push_str("\n• качество воздуха известно только на сейчас");
+    text
+}
+
+fn outdoor_verdict(blockers: &[String], cautions: &[String]) -> &'static str {
+    if !blockers.is_empty() {
+        "лучше не стоит 🙅"
+    } else if !cautions.is_empty() {
+        "можно, но учти пару моментов 👌"
+    } else {
+        "отличная идея! 🏃"
+    }
+}
+
+fn push_reasons(text: &mut String, blockers: &[String], cautions: &[String]) {
+    for reason in blockers.iter().chain(cautions) {
+        text.push_str(&format!("\n• {}", reason));
+    }
+}
+
+/// День из вопроса: "сегодня", "завтра", "послезавтра" или день недели
+fn asked_day(words: &[&str]) -> Option<AskedDay> {
+    for (word, days) in [("послезавтра", 2), ("завтра", 1), ("сегодня", 0)] {
+        if words.contains(&word) {
+            return Some(AskedDay::Offset(days));
+        }
+    }
+    WEEKDAYS
+        .iter()
+        .find(|(forms, _)| words.iter().any(|w| is_form_of(w, std::slice::from_ref(forms))))
+        .map(|(_, weekday)| AskedDay::Weekday(*weekday))
+}
+
+/// Слова вопроса без чисел и знаков
+fn words(tokens: &[Token]) -> Vec<&str> {
+    tokens
+        .iter()
+        .filter_map(|t| match t {
+            Token::Word(word) => Some(word.as_str()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Слово — одна из форм: основа целиком плюс одно из её окончаний
+fn is_form_of(word: &str, forms: &[WordForms]) -> bool {
+    forms
+        .iter()
+        .any(|(stem, endings)| word.strip_prefix(stem).is_some_and(|ending| endings.contains(&ending)))
+}
+
+/// Есть ли среди слов одна из форм
+fn has_form(words: &[&str], forms: &[WordForms]) -> bool {
+    words.iter().any(|word| is_form_of(word, forms))
+}
+
+fn format_day(day: &DailyForecast, units: &WeatherUnits) -> String {
+    let mut text = format!(
+        "🌡️ {}…{}, {} {}, 💨 до {}, 🔆 УФ {:.0}",
+        units.temperature(day.temperature_min),
+        units.temperature(day.temperature_max),
+        wmo::icon(day.weather_code, true),
+        wmo::description(day.weather_code).to_lowercase(),
+        units.wind(day.wind_speed_max),
+        day.uv_index_max
+    );
+    if let (Some(sunrise), Some(sunset)) = (day.sunrise, day.sunset) {
+        text.push_str(&format!(", 🌅 {} – {}", sunrise.format("%H:%M"), sunset.format("%H:%M")));
+    }
+    text
+}
+
+fn day_name(days: i64, date: NaiveDate) -> String {
+    match days {
+        0 => "сегодня".to_string(),
+        1 => "завтра".to_string(),
+        2 => "послезавтра".to_string(),
+        _ => format!("{} {}", weekday_short(date.weekday()), date.format("%d.%m")),
+    }
+}
+
+pub fn weekday_short(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "Пн",
+        Weekday::Tue => "Вт",
+        Weekday::Wed => "Ср",
+        Weekday::Thu => "Чт",
+        Weekday::Fri => "Пт",
+        Weekday::Sat => "Сб",
+        Weekday::Sun => "Вс",
+    }
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn out_of_range() -> String {
+    "Прогноз есть только на ближайшие 7 дней.".to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn weather_questions_are_recognized() {
+        use WeatherQuestion::*;
+        assert_eq!(WeatherQuestion::parse("Какая сейчас погода?"), Some(Current));
+        assert_eq!(
+            WeatherQuestion::parse("будет ли дождь завтра"),
+            Some(Rain { day: AskedDay::Offset(1) })
+        );
+        assert_eq!(WeatherQuestion::parse("гроза будет?"), Some(Rain { day: AskedDay::Offset(0) }));
+        assert_eq!(
+            WeatherQuestion::parse("нужен ли зонт"),
+            Some(Rain { day: AskedDay::Offset(0) })
+        );
+        assert_eq!(
+            WeatherQuestion::parse("погода в среду"),
+            Some(Day { day: AskedDay::Weekday(Weekday::Wed) })
+        );
+        assert_eq!(
+            WeatherQuestion::parse("прогноз на послезавтра"),
+            Some(Day { day: AskedDay::Offset(2) })
+        );
+        assert_eq!(WeatherQuestion::parse("прогноз на неделю"), Some(Week));
+        assert_eq!(WeatherQuestion::parse("погода на 7 дней"), Some(Week));
+    }
+
+    #[test]
+    fn ordinary_messages_are_not_weather_questions() {
+        for text in [
+            "сделай прогноз продаж на квартал",
+            "прогноз курса доллара на завтра",
+            "среди друзей есть программист",
+            "это угроза срыву сроков",
+            "купил новый зонт",
+            "снегурочка придёт",
+        ] {
+            assert_eq!(WeatherQuestion::parse(text), None, "{}", text);
+        }
+    }
+
+    #[test]
+    fn outdoor_and_air_questions_need_a_day_or_the_street() {
+        use WeatherQuestion::*;
+        assert_eq!(
+            WeatherQuestion::parse("можно ли сегодня бегать на улице"),
+            Some(Outdoor { day: AskedDay::Offset(0) })
+        );
+        assert_eq!(
+            WeatherQuestion::parse("стоит ли завтра гулять"),
+            Some(Outdoor { day: AskedDay::Offset(1) })
+        );
+        assert_eq!(
+            WeatherQuestion::parse("можно ли побегать на свежем воздухе"),
+            Some(Outdoor { day: AskedDay::Offset(0) })
+        );
+        assert_eq!(WeatherQuestion::parse("какой сейчас воздух"), Some(AirQuality));
+        assert_eq!(WeatherQuestion::parse("качество воздуха"), Some(AirQuality));
+        assert_eq!(WeatherQuestion::parse("много ли пыльцы"), Some(AirQuality));
+
+        for text in [
+            "люблю гулять с собакой, посоветуй породу",
+            "как научиться бегать быстрее",
+            "нужен воздух в шинах велосипеда",
+            "воздушный шар из бумаги",
+            "я смог решить задачу",
+            "у меня аллергия на кошек",
+        ] {
+            assert_eq!(WeatherQuestion::parse(text), None, "{}", text);
+        }
+    }
+
+    fn conversion(amount: f64, from: &str, to: Option<&str>) -> Option<ConversionQuestion> {
+        Some(ConversionQuestion {
+            amount,
+            from: from.to_string(),
+            to: to.map(str::to_string),
+        })
+    }
+
+    #[test]
+    fn conversions_need_an_amount_next_to_a_currency() {
+        assert_eq!(
+            ConversionQuestion::parse("сколько будет 250 евро в долларах"),
+            conversion(250.0, "EUR", Some("USD"))
+        );
+        assert_eq!(ConversionQuestion::parse("100 usd в EUR"), conversion(100.0, "USD", Some("EUR")));
+        assert_eq!(
+            ConversionQuestion::parse("переведи 10 000 рублей в тенге"),
+            conversion(10000.0, "RUB", Some("KZT"))
+        );
+        assert_eq!(ConversionQuestion::parse("$1.5 тыс в рублях"), conversion(1500.0, "USD", Some("RUB")));
+        assert_eq!(
+            ConversionQuestion::parse("100 белорусских рублей в евро"),
+            conversion(100.0, "BYN", Some("EUR"))
+        );
+        assert_eq!(ConversionQuestion::parse("convert 50 GBP to JPY"), conversion(50.0, "GBP", Some("JPY")));
+        assert_eq!(ConversionQuestion::parse("сколько будет 100 долларов"), conversion(100.0, "USD", None));
+        assert_eq!(ConversionQuestion::parse("5 лир в рублях"), conversion(5.0, "TRY", Some("RUB")));
+    }
+
+    #[test]
+    fn ordinary_messages_are_not_conversions() {
+        for text in [
+            "почему доллар вырос на 5% к евро",
+            "сколько команд на евро 2024",
+            "посмотрел 2 драмы во Франкфурте",
+            "добавь 3 лирики в плейлист",
+            "у меня 2 ларингита в году",
+            "try 100 times in all cases",
+            "100 usd в eur и обратно",
+            "сколько стоит 1 биткоин в рублях",
+            "купил 100 долларов",
+        ] {
+            assert_eq!(ConversionQuestion::parse(text), None, "{}", text);
+        }
+    }
+
+    fn crypto(coins: &[&str], vs: Option<&str>, amount: Option<f64>) -> Option<CryptoQuestion> {
+        Some(CryptoQuestion {
+            coins: coins.iter().map(|c| c.to_string()).collect(),
+            vs: vs.map(str::to_string),
+            amount,
+        })
+    }
+
+    #[test]
+    fn crypto_questions_are_recognized() {
+        assert_eq!(CryptoQuestion::parse("сколько стоит биткоин"), crypto(&["BTC"], None, None));
+        assert_eq!(
+            CryptoQuestion::parse("курс эфира в долларах"),
+            crypto(&["ETH"], Some("USD"), None)
+        );
+        assert_eq!(
+            CryptoQuestion::parse("0.5 BTC в рублях"),
+            crypto(&["BTC"], Some("RUB"), Some(0.5))
+        );
+        assert_eq!(CryptoQuestion::parse("как там крипта"), crypto(&[], None, None));
+        assert_eq!(CryptoQuestion::parse("что там с тонкоином"), crypto(&["TON"], None, None));
+        assert_eq!(CryptoQuestion::parse("цена TON и SOL"), crypto(&["TON", "SOL"], None, None));
+    }
+
+    #[test]
+    fn ordinary_messages_are_not_crypto_questions() {
+        for text in [
+            "оцени мой скрипт",
+            "что там со скриптом",
+            "как там погода",
+            "сколько ton весит кит",
+            "what is the sol of ada",
+            "в прямом эфире было 5 гостей",
+            "биткоин придумал Сатоси",
+        ] {
+            assert_eq!(CryptoQuestion::parse(text), None, "{}", text);
+        }
+    }
+
+    fn stocks(tickers: &[&str], amount: Option<f64>) -> Option<StockQuestion> {
+        Some(StockQuestion {
+            tickers: tickers.iter().map(|t| t.to_string()).collect(),
+            amount,
+        })
+    }
+
+    #[test]
+    fn stock_questions_are_recognized() {
+        let watchlist = ["PIKK".to_string()];
+        let parse = |text| StockQuestion::parse(text, &watchlist);
+        assert_eq!(parse("как там Сбербанк"), stocks(&["SBER"], None));
+        assert_eq!(parse("сколько стоит газпром"), stocks(&["GAZP"], None));
+        assert_eq!(parse("котировки Газпром нефти"), stocks(&["SIBN"], None));
+        assert_eq!(parse("10 акций сбера"), stocks(&["SBER"], Some(10.0)));
+        assert_eq!(parse("что там с акциями"), stocks(&[], None));
+        assert_eq!(parse("как там PIKK"), stocks(&["PIKK"], None));
+        assert_eq!(parse("Как там SBER"), stocks(&["SBER"], None));
+        assert_eq!(parse("цена Интер РАО и Озона"), stocks(&["IRAO", "OZON"], None));
+    }
+
+    #[test]
+    fn ordinary_messages_are_not_stock_questions() {
+        for text in [
+            "сколько сбережений нужно",
+            "что сделать с магнитом",
+            "полюса Земли",
+            "озоновый слой",
+            "сколько длится акция в магазине",
+            "как там сбер в 2024",
+            "сколько стоит 3 кг магнита",
+            "что с ВТБ",
+            "как там sber",
+            "сколько стоит подписка sber prime",
+            "котировки gazp",
+        ] {
+            assert_eq!(StockQuestion::parse(text, &[]), None, "{}", text);
+        }
+    }
+
+    #[test]
+    fn weekday_is_counted_from_the_given_day() {
+        let wednesday = NaiveDate::from_ymd_opt(2026, 10, 14).unwrap();
+        assert_eq!(AskedDay::Weekday(Weekday::Wed).days_from(wednesday), 0);
+        assert_eq!(AskedDay::Weekday(Weekday::Mon).days_from(wednesday), 5);
+        assert_eq!(AskedDay::Offset(1).days_from(wednesday), 1);
+    }
+}
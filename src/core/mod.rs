@@ -1,5 +1,8 @@
 pub mod agent;
+pub mod commands;
+pub mod intents;
 pub mod tts;
 
 pub use agent::ClippyAgent;
+pub use commands::{AlertCommand, ChatCommand, CurrencyCommand, PlaceCommand};
 pub use tts::TextToSpeech;
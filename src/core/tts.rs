@@ -1,17 +1,30 @@
 use crate::config::Config;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 pub struct TextToSpeech {
     config: Config,
+    /// Инкогнито: только системный голос, без временных аудиофайлов на диске
+    incognito: AtomicBool,
 }
 
 impl TextToSpeech {
     pub fn new(config: Config) -> Self {
-        Self { config }
+        Self {
+            config,
+            incognito: AtomicBool::new(false),
+        }
+    }
+
+    pub fn set_incognito(&self, enabled: bool) {
+        self.incognito.store(enabled, Ordering::Relaxed);
     }
     
     pub async fn speak(&self, text: &str) -> Result<(), String> {
-        // Используем Google Cloud Text-to-Speech API
-        if let Some(api_key) = &self.config.google_cloud_api_key {
+        // Используем Google Cloud Text-to-Speech API (в инкогнито — нет: текст ушёл бы
+        // в облако, а аудио — во временный файл)
+        let api_key = self.config.google_cloud_api_key.as_ref()
+            .filter(|_| !self.incognito.load(Ordering::Relaxed));
+        if let Some(api_key) = api_key {
             self.speak_google_cloud(text, api_key).await
         } else {
             // Fallback: используем системный TTS на macOS
@@ -1,5 +1,6 @@
 /// Main GUI module that delegates to UI submodules
 use crate::ui::app::ClippyApp;
+use crate::ui::chat::ChatMessage;
 use eframe::egui;
 
 impl eframe::App for ClippyApp {
@@ -46,7 +47,7 @@ impl eframe::App for ClippyApp {
         if !self.greeting_shown && self.start_time.elapsed().as_secs() >= 3 {
             self.greeting_shown = true;
             let greeting = "Привет! 👋 Нажми на зелёную кнопку, чтобы поговорить.".to_string();
-            self.messages.push(("clippy".to_string(), greeting.clone()));
+            self.messages.push(ChatMessage::new("clippy", greeting.clone()));
 
             let tts = std::sync::Arc::clone(&self.tts);
             tokio::spawn(async move {
@@ -59,8 +60,10 @@ impl eframe::App for ClippyApp {
         }
 
         // Process responses from AI agent
-        while let Ok(response) = self.response_receiver.try_recv() {
-            self.messages.push(("clippy".to_string(), response.clone()));
+        while let Ok((response, uid)) = self.response_receiver.try_recv() {
+            let mut message = ChatMessage::new("clippy", response.clone());
+            message.uid = uid;
+            self.messages.push(message);
             self.is_thinking = false;
 
             let tts = std::sync::Arc::clone(&self.tts);
@@ -76,6 +79,9 @@ impl eframe::App for ClippyApp {
         // Process widget updates (weather, currency data) from background tasks
         self.process_widget_updates();
 
+        // Trash batches for the undo toast after clearing history
+        self.process_history_events(ctx);
+
         let mut image_rect: Option<egui::Rect> = None;
 
         // Render main UI
@@ -142,6 +148,7 @@ impl eframe::App for ClippyApp {
         if let Some(rect) = image_rect {
             log::debug!("📍 Image rect: min={:?}, max={:?}", rect.min, rect.max);
             self.draw_show_button(ctx, rect);
+            self.draw_alert_bubble(ctx, rect);
 
             if self.chat_visible {
                 // Вычисляем примерную позицию чата для вывода виджетов
@@ -1,10 +1,11 @@
 /// Модуль для инициализации логирования
-/// Логирует в файл logs/clippy.log и консоль в режиме development
+/// Логирует в файл <каталог логов профиля>/clippy.log и консоль в режиме development
 
 use std::io::Write;
+use std::path::Path;
 use log::LevelFilter;
 
-pub fn init() {
+pub fn init(log_dir: &Path) {
     let mut builder = env_logger::Builder::new();
     
     // Читаем RUST_LOG переменную если она установлена
@@ -33,9 +34,8 @@ pub fn init() {
     });
     
     // Создаем директорию для логов если её нет
-    let log_dir = "logs";
-    if !std::path::Path::new(log_dir).exists() {
-        let _ = std::fs::create_dir(log_dir);
+    if !log_dir.exists() {
+        let _ = std::fs::create_dir_all(log_dir);
     }
     
     // Инициализируем логирование в файл
@@ -44,7 +44,7 @@ pub fn init() {
             std::fs::OpenOptions::new()
                 .create(true)
                 .append(true)
-                .open("logs/clippy.log")
+                .open(log_dir.join("clippy.log"))
                 .expect("Не удалось открыть файл логов")
         )))
         .init();
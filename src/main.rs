@@ -6,21 +6,29 @@ mod ui;
 mod core;
 mod gui;
 mod logger;
+mod profile;
 
 use config::Config;
+use profile::Profile;
 use ui::ClippyApp;
 use eframe::NativeOptions;
 
 fn main() -> Result<(), eframe::Error> {
+    // Профиль выбирается до всего остального: от него зависят логи, настройки и БД
+    let profile = Profile::from_startup(std::env::args().skip(1));
+    if let Err(e) = profile.ensure_dirs() {
+        eprintln!("Не удалось создать каталог профиля {}: {}", profile.name, e);
+    }
+
     // Инициализируем логирование
-    logger::init();
-    log::info!("🚀 Скрепыш запущен");
+    logger::init(&profile.log_dir());
+    log::info!("🚀 Скрепыш запущен (профиль: {})", profile.name);
 
     // Создаем tokio runtime для async операций
     let rt = tokio::runtime::Runtime::new().unwrap();
     let _guard = rt.enter();
 
-    let config = Config::default();
+    let config = Config::for_profile(profile);
     log::info!("📁 Конфигурация загружена");
     let clippy_name = config.clippy_name.clone();
 
@@ -0,0 +1,146 @@
+/// Профили пользователей: у каждого своя БД, `.env` с ключами API,
+/// логи и резервные копии в ~/.config/clippy/profiles/<имя>/.
+/// Профиль `default` использует прежние пути (~/.config/clippy и `.env` рабочего каталога)
+use anyhow::Result;
+use std::path::PathBuf;
+
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Файл с именем профиля, выбранного командой `/profile`
+const LAST_PROFILE_FILE: &str = "last_profile";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profile {
+    pub name: String,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self {
+            name: DEFAULT_PROFILE.to_string(),
+        }
+    }
+}
+
+impl Profile {
+    /// Имя профиля: латиница, цифры, `-` и `_` (используется как имя каталога)
+    pub fn new(name: &str) -> Result<Self> {
+        let name = name.trim();
+        let valid = !name.is_empty()
+            && name.len() <= 32
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(anyhow::anyhow!(
+                "Некорректное имя профиля '{}': допустимы латиница, цифры, - и _",
+                name
+            ));
+        }
+        Ok(Self {
+            name: name.to_lowercase(),
+        })
+    }
+
+    /// Профиль при запуске: `--profile <имя>`, затем CLIPPY_PROFILE,
+    /// затем последний выбранный командой `/profile`, иначе `default`
+    pub fn from_startup(mut args: impl Iterator<Item = String>) -> Self {
+        let mut from_args = None;
+        while let Some(arg) = args.next() {
+            if let Some(name) = arg.strip_prefix("--profile=") {
+                from_args = Some(name.to_string());
+            } else if arg == "--profile" || arg == "-p" {
+                from_args = args.next();
+            }
+        }
+
+        let requested = from_args
+            .or_else(|| std::env::var("CLIPPY_PROFILE").ok())
+            .or_else(Self::remembered)
+            .filter(|name| !name.trim().is_empty());
+
+        match requested.map(|name| Self::new(&name)) {
+            Some(Ok(profile)) => profile,
+            Some(Err(e)) => {
+                eprintln!("{}; используется профиль {}", e, DEFAULT_PROFILE);
+                Self::default()
+            }
+            None => Self::default(),
+        }
+    }
+
+    pub fn is_default(&self) -> bool {
+        self.name == DEFAULT_PROFILE
+    }
+
+    /// ~/.config/clippy
+    pub fn root() -> PathBuf {
+        dirs::home_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join(".config/clippy")
+    }
+
+    /// Каталог данных профиля
+    pub fn dir(&self) -> PathBuf {
+        if self.is_default() {
+            Self::root()
+        } else {
+            Self::root().join("profiles").join(&self.name)
+        }
+    }
+
+    pub fn db_path(&self) -> PathBuf {
+        self.dir().join("clippy.db")
+    }
+
+    /// `.env` профиля с настройками и ключами API
+    pub fn env_file(&self) -> PathBuf {
+        if self.is_default() {
+            PathBuf::from(".env")
+        } else {
+            self.dir().join(".env")
+        }
+    }
+
+    pub fn log_dir(&self) -> PathBuf {
+        if self.is_default() {
+            PathBuf::from("logs")
+        } else {
+            self.dir().join("logs")
+        }
+    }
+
+    /// Создаёт каталоги профиля, если их ещё нет
+    pub fn ensure_dirs(&self) -> Result<()> {
+        std::fs::create_dir_all(self.dir())?;
+        Ok(())
+    }
+
+    /// Все профили: `default` и каталоги в ~/.config/clippy/profiles
+    pub fn list() -> Vec<String> {
+        let mut names: Vec<String> = std::fs::read_dir(Self::root().join("profiles"))
+            .map(|entries| {
+                entries
+                    .filter_map(|e| e.ok())
+                    .filter(|e| e.path().is_dir())
+                    .filter_map(|e| e.file_name().to_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default();
+        names.sort();
+        names.insert(0, DEFAULT_PROFILE.to_string());
+        names
+    }
+
+    /// Запоминает профиль для следующего запуска
+    pub fn remember(&self) -> Result<()> {
+        self.ensure_dirs()?;
+        std::fs::create_dir_all(Self::root())?;
+        std::fs::write(Self::root().join(LAST_PROFILE_FILE), &self.name)?;
+        Ok(())
+    }
+
+    fn remembered() -> Option<String> {
+        std::fs::read_to_string(Self::root().join(LAST_PROFILE_FILE))
+            .ok()
+            .map(|name| name.trim().to_string())
+    }
+}
@@ -0,0 +1,220 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::cache::{Cached, DiskCache, AIR_QUALITY_TTL};
+use super::location::Place;
+use crate::config::Config;
+
+/// Пыльца (зёрен/м³), начиная с которой она заметна аллергикам
+const POLLEN_MODERATE: f32 = 10.0;
+const POLLEN_HIGH: f32 = 50.0;
+
+/// Уровень загрязнения по европейскому индексу качества воздуха (EAQI)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum AqiLevel {
+    Good,
+    Fair,
+    Moderate,
+    Poor,
+    VeryPoor,
+    ExtremelyPoor,
+}
+
+impl AqiLevel {
+    pub fn from_european_aqi(aqi: i32) -> Self {
+        match aqi {
+            i32::MIN..=20 => Self::Good,
+            21..=40 => Self::Fair,
+            41..=60 => Self::Moderate,
+            61..=80 => Self::Poor,
+            81..=100 => Self::VeryPoor,
+            _ => Self::ExtremelyPoor,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Good => "хороший",
+            Self::Fair => "приемлемый",
+            Self::Moderate => "умеренный",
+            Self::Poor => "плохой",
+            Self::VeryPoor => "очень плохой",
+            Self::ExtremelyPoor => "опасный",
+        }
+    }
+
+    pub fn icon(&self) -> &'static str {
+        match self {
+            Self::Good | Self::Fair => "🟢",
+            Self::Moderate => "🟡",
+            Self::Poor => "🟠",
+            Self::VeryPoor | Self::ExtremelyPoor => "🔴",
+        }
+    }
+}
+
+/// Концентрация пыльцы одного вида
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Pollen {
+    pub name: String,
+    /// Зёрен на м³
+    pub grains: f32,
+}
+
+/// Качество воздуха сейчас. Пыльца есть только для Европы и в сезон цветения
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AirQuality {
+    pub place: String,
+    pub european_aqi: Option<i32>,
+    pub us_aqi: Option<i32>,
+    /// Концентрации, мкг/м³
+    pub pm2_5: Option<f32>,
+    pub pm10: Option<f32>,
+    pub nitrogen_dioxide: Option<f32>,
+    pub ozone: Option<f32>,
+    pub pollen: Vec<Pollen>,
+}
+
+impl AirQuality {
+    pub fn level(&self) -> Option<AqiLevel> {
+        self.european_aqi.map(AqiLevel::from_european_aqi)
+    }
+
+    /// Виды пыльцы с заметной концентрацией, самые сильные первыми
+    pub fn notable_pollen(&self) -> Vec<&Pollen> {
+        let mut notable: Vec<&Pollen> = self.pollen.iter().filter(|p| p.grains >= POLLEN_MODERATE).collect();
+        notable.sort_by(|a, b| b.grains.total_cmp(&a.grains));
+        notable
+    }
+
+    pub fn has_high_pollen(&self) -> bool {
+        self.pollen.iter().any(|p| p.grains >= POLLEN_HIGH)
+    }
+
+    /// Текст для ответа в чате
+    pub fn format(&self) -> String {
+        let mut text = format!("🌬️ Качество воздуха в городе {}:", self.place);
+        match (self.european_aqi, self.level()) {
+            (Some(aqi), Some(level)) => {
+                text.push_str(&format!("\n• {} Индекс EAQI: {} — {}", level.icon(), aqi, level.label()))
+            }
+            _ => text.push_str("\n• Индекс качества воздуха недоступен"),
+        }
+        if let Some(us_aqi) = self.us_aqi {
+            text.push_str(&format!(" (US AQI {})", us_aqi));
+        }
+
+        let pollutants = [
+            ("PM2.5", self.pm2_5),
+            ("PM10", self.pm10),
+            ("NO₂", self.nitrogen_dioxide),
+            ("O₃", self.ozone),
+        ];
+        let values: Vec<String> = pollutants
+            .iter()
+            .filter_map(|(name, value)| value.map(|v| format!("{} {:.0}", name, v)))
+            .collect();
+        if !values.is_empty() {
+            text.push_str(&format!("\n• {} мкг/м³", values.join(", ")));
+        }
+
+        let pollen = self.notable_pollen();
+        if pollen.is_empty() {
+            if !self.pollen.is_empty() {
+                text.push_str("\n• 🌼 Пыльцы почти нет");
+            }
+        } else {
+            let list: Vec<String> = pollen.iter().map(|p| format!("{} {:.0}", p.name, p.grains)).collect();
+            text.push_str(&format!("\n• 🌼 Пыльца (зёрен/м³): {}", list.join(", ")));
+        }
+        text
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct AirQualityResponse {
+    current: CurrentAirQuality,
+}
+
+#[derive(Debug, Deserialize)]
+struct CurrentAirQuality {
+    european_aqi: Option<f32>,
+    us_aqi: Option<f32>,
+    pm2_5: Option<f32>,
+    pm10: Option<f32>,
+    nitrogen_dioxide: Option<f32>,
+    ozone: Option<f32>,
+    alder_pollen: Option<f32>,
+    birch_pollen: Option<f32>,
+    grass_pollen: Option<f32>,
+    mugwort_pollen: Option<f32>,
+    olive_pollen: Option<f32>,
+    ragweed_pollen: Option<f32>,
+}
+
+/// Сервис качества воздуха и пыльцы через Open-Meteo Air Quality API
+pub struct AirQualityService {
+    http_client: reqwest::Client,
+    cache: DiskCache,
+}
+
+impl AirQualityService {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            http_client: reqwest::Client::new(),
+            cache: DiskCache::from_config(config),
+        }
+    }
+
+    /// Качество воздуха для места (из кэша, если оно свежее)
+    pub async fn get_air_quality(&self, place: &Place) -> Result<Cached<AirQuality>> {
+        let key = format!("air:{:.3},{:.3}", place.latitude, place.longitude);
+        self.cache
+            .get_or_fetch(&key, AIR_QUALITY_TTL, || self.fetch_air_quality(place))
+            .await
+    }
+
+    async fn fetch_air_quality(&self, place: &Place) -> Result<AirQuality> {
+        let url = format!(
+            "https://air-quality-api.open-meteo.com/v1/air-quality?latitude={}&longitude={}\
+             &current=european_aqi,us_aqi,pm2_5,pm10,nitrogen_dioxide,ozone,\
+             alder_pollen,birch_pollen,grass_pollen,mugwort_pollen,olive_pollen,ragweed_pollen\
+             &timezone=auto",
+            place.latitude, place.longitude
+        );
+
+        let response = self.http_client.get(&url).send().await?;
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!("Сервис качества воздуха ответил {}", response.status()));
+        }
+        let current = response.json::<AirQualityResponse>().await?.current;
+
+        let pollen = [
+            ("ольха", current.alder_pollen),
+            ("берёза", current.birch_pollen),
+            ("злаки", current.grass_pollen),
+            ("полынь", current.mugwort_pollen),
+            ("олива", current.olive_pollen),
+            ("амброзия", current.ragweed_pollen),
+        ]
+        .into_iter()
+        .filter_map(|(name, grains)| {
+            grains.map(|grains| Pollen {
+                name: name.to_string(),
+                grains,
+            })
+        })
+        .collect();
+
+        Ok(AirQuality {
+            place: place.name.clone(),
+            european_aqi: current.european_aqi.map(|v| v.round() as i32),
+            us_aqi: current.us_aqi.map(|v| v.round() as i32),
+            pm2_5: current.pm2_5,
+            pm10: current.pm10,
+            nitrogen_dioxide: current.nitrogen_dioxide,
+            ozone: current.ozone,
+            pollen,
+        })
+    }
+}
@@ -0,0 +1,78 @@
+use anyhow::Result;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::path::Path;
+
+/// Содержимое крупнее порога хранится файлом в каталоге `attachments/` рядом с БД,
+/// остальное — BLOB в самой БД
+pub const INLINE_LIMIT: usize = 512 * 1024;
+
+/// Вложение (файл или изображение), привязанное к сообщению по его uid
+#[derive(Debug, Clone, Serialize)]
+pub struct Attachment {
+    pub id: i64,
+    pub message_uid: String,
+    pub mime_type: String,
+    pub filename: String,
+    pub size: u64,
+    /// SHA-256 содержимого: одинаковые файлы хранятся один раз
+    pub hash: String,
+    pub created_at: String,
+}
+
+/// Вложение, прочитанное с диска или из экспорта, до записи в хранилище
+#[derive(Debug, Clone)]
+pub struct NewAttachment {
+    pub filename: String,
+    pub mime_type: String,
+    pub data: Vec<u8>,
+}
+
+impl NewAttachment {
+    /// Читает файл; MIME-тип определяется по расширению, если не задан
+    pub fn from_file(path: &Path, filename: Option<&str>, mime_type: Option<&str>) -> Result<Self> {
+        let data = std::fs::read(path)?;
+        let filename = filename
+            .map(str::to_string)
+            .or_else(|| path.file_name().and_then(|n| n.to_str()).map(str::to_string))
+            .unwrap_or_else(|| "attachment".to_string());
+        let mime_type = mime_type
+            .map(str::to_string)
+            .unwrap_or_else(|| guess_mime(&filename).to_string());
+
+        Ok(Self {
+            filename,
+            mime_type,
+            data,
+        })
+    }
+}
+
+/// Адрес содержимого: SHA-256 в hex
+pub fn content_hash(data: &[u8]) -> String {
+    Sha256::digest(data).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// MIME-тип по расширению файла
+pub fn guess_mime(filename: &str) -> &'static str {
+    let ext = filename.rsplit_once('.').map(|(_, e)| e.to_lowercase()).unwrap_or_default();
+    match ext.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "pdf" => "application/pdf",
+        "txt" | "log" => "text/plain",
+        "md" => "text/markdown",
+        "csv" => "text/csv",
+        "json" => "application/json",
+        "html" | "htm" => "text/html",
+        "zip" => "application/zip",
+        "docx" => "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        "xlsx" => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        "mp3" => "audio/mpeg",
+        "wav" => "audio/wav",
+        _ => "application/octet-stream",
+    }
+}
@@ -0,0 +1,402 @@
+use anyhow::{Context, Result};
+use chrono::Local;
+use rusqlite::{Connection, OpenFlags};
+use std::path::{Path, PathBuf};
+
+use super::cache::CACHE_DIR;
+use super::currency::WATCHLIST_FILE;
+use super::location::PLACES_FILE;
+use super::storage::default_db_path;
+use super::storage_actor::StorageHandle;
+use crate::config::Config;
+
+/// Префикс каталогов копий: clippy-YYYYMMDD-HHMMSS-mmm
+const BACKUP_PREFIX: &str = "clippy-";
+/// Файл БД внутри копии
+const DB_FILE: &str = "clippy.db";
+/// Копия `.env` профиля с настройками (без ключей и паролей)
+const CONFIG_FILE: &str = "config.env";
+/// Окончания имён переменных `.env`, значения которых не попадают в копию
+const SECRET_SUFFIXES: &[&str] = &["_KEY", "_SECRET", "_TOKEN", "_PASSWORD", "_PASSPHRASE"];
+/// Каталог крупных вложений рядом с БД
+const ATTACHMENTS_DIR: &str = "attachments";
+/// Файлы данных в каталоге профиля
+const DATA_FILES: &[&str] = &[PLACES_FILE, WATCHLIST_FILE];
+
+/// Резервная копия на диске
+#[derive(Debug, Clone)]
+pub struct BackupInfo {
+    pub name: String,
+    pub path: PathBuf,
+    pub size_bytes: u64,
+}
+
+/// Итог восстановления
+#[derive(Debug, Clone)]
+pub struct RestoreReport {
+    pub restored: BackupInfo,
+    /// Копия состояния до восстановления (для отката)
+    pub safety: BackupInfo,
+}
+
+impl std::fmt::Display for RestoreReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "♻️ Данные восстановлены из {}. Прежнее состояние сохранено в {}. \
+             Изменения настроек вступят в силу после перезапуска",
+            self.restored.name, self.safety.name
+        )
+    }
+}
+
+/// Создание, ротация и восстановление резервных копий: БД (онлайн через
+/// SQLite backup API), `.env` без секретов, сохранённые места, кэши и крупные вложения
+#[derive(Debug, Clone)]
+pub struct BackupManager {
+    backup_dir: PathBuf,
+    db_path: PathBuf,
+    /// Каталог БД: в нём лежат вложения и по умолчанию копии
+    db_dir: PathBuf,
+    /// Каталог файлов профиля (`Config::data_dir`)
+    data_dir: PathBuf,
+    config_file: PathBuf,
+    keep: usize,
+}
+
+impl BackupManager {
+    pub fn from_config(config: &Config) -> Self {
+        let db_path = config.db_path.clone().unwrap_or_else(default_db_path);
+        let db_dir = db_path
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| PathBuf::from("."));
+
+        Self {
+            backup_dir: config.backup_dir.clone().unwrap_or_else(|| db_dir.join("backups")),
+            db_path,
+            db_dir,
+            data_dir: config.data_dir(),
+            config_file: config.profile.env_file(),
+            keep: config.backup_keep,
+        }
+    }
+
+    /// Создаёт копию и удаляет самые старые сверх лимита
+    pub async fn create(&self) -> Result<BackupInfo> {
+        let info = self.snapshot().await?;
+        self.rotate()?;
+        Ok(info)
+    }
+
+    /// Проверяет копию (`PRAGMA integrity_check`) и заменяет ею текущие данные.
+    /// `key` — имя копии или номер из списка (1 — самая свежая)
+    pub async fn restore(&self, storage: &StorageHandle, key: &str) -> Result<RestoreReport> {
+        let backup = self.find(key)?;
+        let db_file = backup.path.join(DB_FILE);
+        check_integrity(&db_file).with_context(|| format!("Копия {} повреждена", backup.name))?;
+
+        // Сначала сохраняем текущее состояние, чтобы восстановление можно было откатить
+        let safety = self.snapshot().await?;
+
+        storage.call(move |s| s.restore_from(&db_file)).await?;
+        self.restore_extras(&backup.path)?;
+        self.rotate()?;
+
+        Ok(RestoreReport { restored: backup, safety })
+    }
+
+    /// Копии в каталоге, новые первыми
+    pub fn list(&self) -> Result<Vec<BackupInfo>> {
+        if !self.backup_dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut backups = Vec::new();
+        for entry in std::fs::read_dir(&self.backup_dir)? {
+            let path = entry?.path();
+            let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            if !name.starts_with(BACKUP_PREFIX) || !path.join(DB_FILE).is_file() {
+                continue;
+            }
+            backups.push(BackupInfo {
+                name: name.to_string(),
+                size_bytes: dir_size(&path),
+                path,
+            });
+        }
+
+        // Имена содержат время, поэтому сортировка по имени — хронологическая
+        backups.sort_by(|a, b| b.name.cmp(&a.name));
+        Ok(backups)
+    }
+
+    async fn snapshot(&self) -> Result<BackupInfo> {
+        let (name, dir) = self.create_backup_dir()?;
+
+        let db_file = dir.join(DB_FILE);
+        let (source, target) = (self.db_path.clone(), db_file.clone());
+        let result = match tokio::task::spawn_blocking(move || backup_db(&source, &target)).await {
+            Ok(Ok(())) => check_integrity(&db_file).and_then(|_| self.copy_extras(&dir)),
+            Ok(Err(e)) => Err(e),
+            Err(e) => Err(e.into()),
+        };
+        if let Err(e) = result {
+            let _ = std::fs::remove_dir_all(&dir);
+            return Err(e);
+        }
+
+        log::info!("💾 Резервная копия создана: {}", dir.display());
+        Ok(BackupInfo {
+            name,
+            size_bytes: dir_size(&dir),
+            path: dir,
+        })
+    }
+
+    /// Создаёт пустой каталог для новой копии. `create_dir` не принимает уже
+    /// существующий каталог, поэтому при совпадении времени добавляется номер
+    /// и чужая копия не будет ни перезаписана, ни удалена при ошибке
+    fn create_backup_dir(&self) -> Result<(String, PathBuf)> {
+        std::fs::create_dir_all(&self.backup_dir)?;
+        let stamp = format!("{}{}", BACKUP_PREFIX, Local::now().format("%Y%m%d-%H%M%S-%3f"));
+
+        for attempt in 1..100 {
+            let name = match attempt {
+                1 => stamp.clone(),
+                n => format!("{}-{:02}", stamp, n),
+            };
+            let dir = self.backup_dir.join(&name);
+            match std::fs::create_dir(&dir) {
+                Ok(()) => return Ok((name, dir)),
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Err(anyhow::anyhow!("Не удалось подобрать имя для копии в {}", self.backup_dir.display()))
+    }
+
+    fn rotate(&self) -> Result<()> {
+        for old in self.list()?.into_iter().skip(self.keep) {
+            std::fs::remove_dir_all(&old.path)?;
+            log::info!("🗑️ Удалена старая копия {}", old.name);
+        }
+        Ok(())
+    }
+
+    fn find(&self, key: &str) -> Result<BackupInfo> {
+        let backups = self.list()?;
+        let found = match key.trim().parse::<usize>() {
+            Ok(index) if index > 0 => backups.into_iter().nth(index - 1),
+            _ => backups.into_iter().find(|b| b.name == key.trim()),
+        };
+        found.ok_or_else(|| anyhow::anyhow!("Копия '{}' не найдена", key.trim()))
+    }
+
+    /// Каталоги, которые копируются целиком, и где они лежат
+    fn data_dirs(&self) -> [(&'static str, &Path); 2] {
+        [(CACHE_DIR, &self.data_dir), (ATTACHMENTS_DIR, &self.db_dir)]
+    }
+
+    fn copy_extras(&self, dir: &Path) -> Result<()> {
+        if self.config_file.is_file() {
+            let config = std::fs::read_to_string(&self.config_file)?;
+            std::fs::write(dir.join(CONFIG_FILE), strip_secrets(&config))?;
+        }
+        for (name, base) in self.data_dirs() {
+            let source = base.join(name);
+            if source.is_dir() {
+                copy_dir(&source, &dir.join(name))?;
+            }
+        }
+        for name in DATA_FILES {
+            let source = self.data_dir.join(name);
+            if source.is_file() {
+                std::fs::copy(&source, dir.join(name))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn restore_extras(&self, dir: &Path) -> Result<()> {
+        // Ключей в копии нет — берём их из текущего `.env`
+        let config = dir.join(CONFIG_FILE);
+        if config.is_file() {
+            let current = std::fs::read_to_string(&self.config_file).unwrap_or_default();
+            let restored = with_secrets(&std::fs::read_to_string(&config)?, &current);
+            std::fs::write(&self.config_file, restored)?;
+        }
+        for (name, base) in self.data_dirs() {
+            let source = dir.join(name);
+            if !source.is_dir() {
+                continue;
+            }
+            let target = base.join(name);
+            if target.exists() {
+                std::fs::remove_dir_all(&target)?;
+            }
+            copy_dir(&source, &target)?;
+        }
+        for name in DATA_FILES {
+            let source = dir.join(name);
+            if source.is_file() {
+                std::fs::copy(&source, self.data_dir.join(name))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Строка `.env` с ключом или паролем
+fn is_secret_line(line: &str) -> bool {
+    let Some((key, _)) = line.split_once('=') else {
+        return false;
+    };
+    let key = key.trim().trim_start_matches("export ").trim().to_uppercase();
+    SECRET_SUFFIXES.iter().any(|suffix| key.ends_with(suffix))
+}
+
+/// Содержимое `.env` без ключей и паролей
+fn strip_secrets(env: &str) -> String {
+    env.lines()
+        .filter(|line| !is_secret_line(line))
+        .map(|line| format!("{}\n", line))
+        .collect()
+}
+
+/// `.env` из копии, дополненный ключами и паролями из текущего `.env`
+fn with_secrets(restored: &str, current: &str) -> String {
+    let mut result = strip_secrets(restored);
+    for line in current.lines().filter(|line| is_secret_line(line)) {
+        result.push_str(line);
+        result.push('\n');
+    }
+    result
+}
+
+/// Онлайн-копия через SQLite backup API из отдельного соединения только для
+/// чтения: в режиме WAL запись в БД на время копирования не блокируется
+fn backup_db(source: &Path, target: &Path) -> Result<()> {
+    let conn = Connection::open_with_flags(source, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    conn.busy_timeout(std::time::Duration::from_secs(5))?;
+    conn.backup(rusqlite::DatabaseName::Main, target, None)?;
+    log::info!("💾 БД скопирована в {}", target.display());
+    Ok(())
+}
+
+/// Проверяет файл БД через `PRAGMA integrity_check`
+pub fn check_integrity(path: &Path) -> Result<()> {
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let mut stmt = conn.prepare("PRAGMA integrity_check")?;
+    let problems: Vec<String> = stmt
+        .query_map([], |row| row.get(0))?
+        .collect::<rusqlite::Result<_>>()?;
+
+    if problems.len() == 1 && problems[0] == "ok" {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!("integrity_check: {}", problems.join("; ")))
+    }
+}
+
+fn copy_dir(source: &Path, target: &Path) -> Result<()> {
+    std::fs::create_dir_all(target)?;
+    for entry in std::fs::read_dir(source)? {
+        let entry = entry?;
+        let path = entry.path();
+        if path.is_dir() {
+            copy_dir(&path, &target.join(entry.file_name()))?;
+        } else {
+            std::fs::copy(&path, target.join(entry.file_name()))?;
+        }
+    }
+    Ok(())
+}
+
+fn dir_size(path: &Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .filter_map(|e| e.ok())
+        .map(|e| match e.metadata() {
+            Ok(meta) if meta.is_dir() => dir_size(&e.path()),
+            Ok(meta) => meta.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::storage::SQLiteStorage;
+    use uuid::Uuid;
+
+    struct TempBackups {
+        root: PathBuf,
+        manager: BackupManager,
+    }
+
+    impl TempBackups {
+        fn new() -> Self {
+            let root = std::env::temp_dir().join(format!("clippy-test-{}", Uuid::new_v4()));
+            let db_path = root.join("clippy.db");
+            SQLiteStorage::new(Some(db_path.clone())).unwrap();
+            let manager = BackupManager {
+                backup_dir: root.join("backups"),
+                db_path,
+                db_dir: root.clone(),
+                data_dir: root.clone(),
+                config_file: root.join(".env"),
+                keep: 10,
+            };
+            Self { root, manager }
+        }
+    }
+
+    impl Drop for TempBackups {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.root);
+        }
+    }
+
+    #[tokio::test]
+    async fn snapshots_in_the_same_second_get_their_own_directories() {
+        let temp = TempBackups::new();
+        let mut names = Vec::new();
+        for _ in 0..3 {
+            names.push(temp.manager.create().await.unwrap().name);
+        }
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), 3);
+        assert_eq!(temp.manager.list().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn config_copy_has_no_secrets_and_restore_keeps_current_ones() {
+        let temp = TempBackups::new();
+        std::fs::write(
+            &temp.manager.config_file,
+            "GIGACHAT_API_KEY=old-key\nCLIPPY_CITY=Москва\nGIGACHAT_MAX_TOKENS=500\n",
+        )
+        .unwrap();
+        let backup = temp.manager.create().await.unwrap();
+
+        let copy = std::fs::read_to_string(backup.path.join(CONFIG_FILE)).unwrap();
+        assert!(!copy.contains("old-key"));
+        assert!(copy.contains("CLIPPY_CITY=Москва"));
+        assert!(copy.contains("GIGACHAT_MAX_TOKENS=500"));
+
+        std::fs::write(&temp.manager.config_file, "export GIGACHAT_API_KEY=new-key\nCLIPPY_CITY=Казань\n").unwrap();
+        temp.manager.restore_extras(&backup.path).unwrap();
+
+        let restored = std::fs::read_to_string(&temp.manager.config_file).unwrap();
+        assert!(restored.contains("CLIPPY_CITY=Москва"));
+        assert!(restored.contains("export GIGACHAT_API_KEY=new-key"));
+        assert!(!restored.contains("Казань"));
+    }
+}
@@ -0,0 +1,339 @@
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::Duration;
+use uuid::Uuid;
+
+use super::attachment::content_hash;
+use crate::config::Config;
+
+/// Каталог кэша в каталоге профиля
+pub const CACHE_DIR: &str = "cache";
+/// Геокодинг почти не меняется
+pub const GEOCODING_TTL: Duration = Duration::from_secs(30 * 24 * 3600);
+pub const WEATHER_TTL: Duration = Duration::from_secs(10 * 60);
+pub const RATES_TTL: Duration = Duration::from_secs(60 * 60);
+pub const AIR_QUALITY_TTL: Duration = Duration::from_secs(30 * 60);
+pub const CRYPTO_TTL: Duration = Duration::from_secs(5 * 60);
+pub const STOCKS_TTL: Duration = Duration::from_secs(2 * 60);
+/// Данные старше суток (или старше TTL, если он длиннее) не показываются
+/// даже с пометкой о том, что связи нет
+const STALE_MAX_AGE: Duration = Duration::from_secs(24 * 3600);
+
+/// Запись кэша на диске
+#[derive(Debug, Serialize, Deserialize)]
+struct Entry<T> {
+    /// Хеш ключа: запрос (например, название города) на диске не хранится
+    key: String,
+    stored_at: DateTime<Local>,
+    value: T,
+}
+
+/// Данные из кэша или сети. `stale` — сеть недоступна и показаны последние
+/// известные данные, полученные в `fetched_at`
+#[derive(Debug, Clone)]
+pub struct Cached<T> {
+    pub value: T,
+    pub fetched_at: DateTime<Local>,
+    pub stale: bool,
+}
+
+impl<T> Cached<T> {
+    /// Пометка для устаревших данных: "нет связи, данные от 14:05"
+    pub fn stale_note(&self) -> Option<String> {
+        self.stale
+            .then(|| format!("⚠️ Нет связи, данные от {}", time_label(self.fetched_at)))
+    }
+
+    /// Время получения: "14:05", для прошлых дней — "12.10 14:05"
+    pub fn fetched_label(&self) -> String {
+        time_label(self.fetched_at)
+    }
+
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Cached<U> {
+        Cached {
+            value: f(self.value),
+            fetched_at: self.fetched_at,
+            stale: self.stale,
+        }
+    }
+}
+
+/// Записи, которые пока не должны попадать на диск: имя файла → JSON записи.
+/// `None` — обычный режим
+#[derive(Debug, Default)]
+struct MemoryLayer(Mutex<Option<HashMap<PathBuf, String>>>);
+
+/// Общий слой всех кэшей из `DiskCache::from_config`
+static SHARED_MEMORY: LazyLock<Arc<MemoryLayer>> = LazyLock::new(Arc::default);
+
+/// Кэш ответов внешних API в каталоге `cache/` профиля: свежие данные отдаются
+/// без запроса, просроченные обновляются из сети, а при ошибке сети отдаются
+/// последние известные с пометкой `stale`. В режиме «только память» (инкогнито)
+/// новые записи живут в памяти процесса и исчезают при выходе из режима
+#[derive(Debug, Clone)]
+pub struct DiskCache {
+    dir: PathBuf,
+    memory: Arc<MemoryLayer>,
+}
+
+impl DiskCache {
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            memory: Arc::default(),
+        }
+    }
+
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            memory: Arc::clone(&SHARED_MEMORY),
+            ..Self::new(config.data_dir().join(CACHE_DIR))
+        }
+    }
+
+    /// Включает режим «только память» для всех кэшей из `from_config`:
+    /// на диск ничего не пишется, а при выключении записи из памяти стираются
+    pub fn set_memory_only(enabled: bool) {
+        SHARED_MEMORY.set_enabled(enabled);
+    }
+
+    fn key_hash(key: &str) -> String {
+        content_hash(key.as_bytes())
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", &Self::key_hash(key)[..32]))
+    }
+
+    /// Последнее сохранённое значение и время его получения
+    pub fn read<T: DeserializeOwned>(&self, key: &str) -> Option<(T, DateTime<Local>)> {
+        let path = self.path(key);
+        let json = match self.memory.get(&path) {
+            Some(json) => json,
+            None => std::fs::read_to_string(&path).ok()?,
+        };
+        match serde_json::from_str::<Entry<T>>(&json) {
+            Ok(entry) if entry.key == Self::key_hash(key) => Some((entry.value, entry.stored_at)),
+            Ok(_) => None,
+            Err(e) => {
+                log::debug!("Кэш {} не читается: {}", key, e);
+                None
+            }
+        }
+    }
+
+    pub fn write<T: Serialize>(&self, key: &str, value: &T) -> Result<()> {
+        let entry = Entry {
+            key: Self::key_hash(key),
+            stored_at: Local::now(),
+            value,
+        };
+        let path = self.path(key);
+        let json = serde_json::to_string(&entry)?;
+        let Some(json) = self.memory.put(&path, json) else {
+            return Ok(());
+        };
+
+        // Через временный файл со своим именем у каждой записи, чтобы параллельный
+        // читатель не увидел половину записи, а писатели не мешали друг другу
+        std::fs::create_dir_all(&self.dir)?;
+        let tmp = path.with_extension(format!("tmp-{}", Uuid::new_v4().simple()));
+        std::fs::write(&tmp, json)?;
+        if let Err(e) = std::fs::rename(&tmp, &path) {
+            let _ = std::fs::remove_file(&tmp);
+            return Err(e.into());
+        }
+        Ok(())
+    }
+
+    /// Значение из кэша, если ему меньше `ttl`, иначе из `fetch`; при ошибке
+    /// `fetch` — последнее известное значение с пометкой `stale`, если оно не
+    /// старше `STALE_MAX_AGE` (или `ttl`, если он длиннее)
+    pub async fn get_or_fetch<T, F, Fut>(&self, key: &str, ttl: Duration, fetch: F) -> Result<Cached<T>>
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let cached = match self.read::<T>(key) {
+            Some((value, fetched_at)) if is_fresh(fetched_at, ttl) => {
+                return Ok(Cached {
+                    value,
+                    fetched_at,
+                    stale: false,
+                });
+            }
+            other => other,
+        };
+
+        match fetch().await {
+            Ok(value) => {
+                if let Err(e) = self.write(key, &value) {
+                    log::warn!("⚠️ Не удалось записать кэш {}: {}", key, e);
+                }
+                Ok(Cached {
+                    value,
+                    fetched_at: Local::now(),
+                    stale: false,
+                })
+            }
+            Err(e) => match cached.filter(|(_, fetched_at)| is_fresh(*fetched_at, ttl.max(STALE_MAX_AGE))) {
+                Some((value, fetched_at)) => {
+                    log::warn!("⚠️ {}: {} — используются данные от {}", key, e, time_label(fetched_at));
+                    Ok(Cached {
+                        value,
+                        fetched_at,
+                        stale: true,
+                    })
+                }
+                None => Err(e),
+            },
+        }
+    }
+}
+
+impl MemoryLayer {
+    fn set_enabled(&self, enabled: bool) {
+        let mut entries = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        match (enabled, entries.is_some()) {
+            (true, false) => *entries = Some(HashMap::new()),
+            (false, true) => *entries = None,
+            _ => {}
+        }
+    }
+
+    fn get(&self, path: &Path) -> Option<String> {
+        let entries = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        entries.as_ref()?.get(path).cloned()
+    }
+
+    /// Сохраняет запись в памяти; возвращает её обратно, если режим выключен
+    /// и запись нужно писать на диск
+    fn put(&self, path: &Path, json: String) -> Option<String> {
+        let mut entries = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        match entries.as_mut() {
+            Some(entries) => {
+                entries.insert(path.to_path_buf(), json);
+                None
+            }
+            None => Some(json),
+        }
+    }
+}
+
+fn time_label(time: DateTime<Local>) -> String {
+    if time.date_naive() == Local::now().date_naive() {
+        time.format("%H:%M").to_string()
+    } else {
+        time.format("%d.%m %H:%M").to_string()
+    }
+}
+
+fn is_fresh(stored_at: DateTime<Local>, ttl: Duration) -> bool {
+    Local::now()
+        .signed_duration_since(stored_at)
+        .to_std()
+        .is_ok_and(|age| age < ttl)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TempCache {
+        cache: DiskCache,
+    }
+
+    impl TempCache {
+        fn new() -> Self {
+            let dir = std::env::temp_dir().join(format!("clippy-test-{}", Uuid::new_v4()));
+            Self {
+                cache: DiskCache::new(dir),
+            }
+        }
+
+        fn files(&self) -> Vec<String> {
+            std::fs::read_dir(&self.cache.dir)
+                .map(|entries| {
+                    entries
+                        .filter_map(|e| e.ok())
+                        .map(|e| e.file_name().to_string_lossy().into_owned())
+                        .collect()
+                })
+                .unwrap_or_default()
+        }
+
+        /// Кладёт запись, полученную `age` назад
+        fn write_aged(&self, key: &str, value: &str, age: chrono::Duration) {
+            std::fs::create_dir_all(&self.cache.dir).unwrap();
+            let entry = Entry {
+                key: DiskCache::key_hash(key),
+                stored_at: Local::now() - age,
+                value,
+            };
+            std::fs::write(self.cache.path(key), serde_json::to_vec(&entry).unwrap()).unwrap();
+        }
+    }
+
+    impl Drop for TempCache {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.cache.dir);
+        }
+    }
+
+    async fn offline() -> Result<String> {
+        Err(anyhow::anyhow!("нет сети"))
+    }
+
+    #[test]
+    fn entries_do_not_keep_the_key_in_plain_text() {
+        let temp = TempCache::new();
+        temp.cache.write("geocode:Нижний Тагил", &"ok").unwrap();
+
+        assert_eq!(temp.cache.read::<String>("geocode:Нижний Тагил").unwrap().0, "ok");
+        let files = temp.files();
+        assert_eq!(files.len(), 1, "временный файл не должен оставаться: {:?}", files);
+        let json = std::fs::read_to_string(temp.cache.dir.join(&files[0])).unwrap();
+        assert!(!json.contains("Тагил"));
+    }
+
+    #[test]
+    fn memory_only_mode_writes_nothing_and_forgets_on_exit() {
+        let temp = TempCache::new();
+        temp.cache.memory.set_enabled(true);
+        temp.cache.write("weather", &"тайное").unwrap();
+        assert_eq!(temp.cache.read::<String>("weather").unwrap().0, "тайное");
+        assert!(temp.files().is_empty());
+
+        temp.cache.memory.set_enabled(false);
+        assert!(temp.cache.read::<String>("weather").is_none());
+    }
+
+    #[tokio::test]
+    async fn stale_fallback_has_an_age_limit() {
+        let temp = TempCache::new();
+        temp.write_aged("recent", "вчера", chrono::Duration::hours(3));
+        temp.write_aged("old", "давно", chrono::Duration::days(3));
+
+        let recent = temp.cache.get_or_fetch("recent", WEATHER_TTL, offline).await.unwrap();
+        assert!(recent.stale);
+        assert_eq!(recent.value, "вчера");
+        assert!(temp.cache.get_or_fetch("old", WEATHER_TTL, offline).await.is_err());
+        // Геокодинг живёт дольше суток — и его можно показывать дольше
+        assert!(temp.cache.get_or_fetch("old", GEOCODING_TTL, offline).await.is_ok());
+    }
+
+    #[test]
+    fn label_has_a_date_for_earlier_days() {
+        let now = Local::now();
+        assert_eq!(time_label(now), now.format("%H:%M").to_string());
+        let earlier = now - chrono::Duration::days(2);
+        assert_eq!(time_label(earlier), earlier.format("%d.%m %H:%M").to_string());
+    }
+}
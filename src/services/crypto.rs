@@ -0,0 +1,88 @@
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Nonce};
+use anyhow::Result;
+use argon2::Argon2;
+use base64::Engine;
+
+/// Префикс зашифрованного содержимого в БД (версия формата на случай миграций)
+const ENCRYPTED_PREFIX: &str = "enc:v1:";
+const NONCE_LEN: usize = 12;
+pub const SALT_LEN: usize = 16;
+
+/// Шифрование содержимого сообщений (AES-256-GCM, ключ из пароля через Argon2id)
+#[derive(Clone)]
+pub struct ContentCipher {
+    cipher: Aes256Gcm,
+}
+
+impl ContentCipher {
+    /// Выводит ключ из пароля и соли
+    pub fn from_passphrase(passphrase: &str, salt: &[u8]) -> Result<Self> {
+        let mut key = [0u8; 32];
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+            .map_err(|e| anyhow::anyhow!("Ошибка вывода ключа: {}", e))?;
+
+        let cipher = Aes256Gcm::new_from_slice(&key)
+            .map_err(|e| anyhow::anyhow!("Некорректный ключ: {}", e))?;
+        Ok(Self { cipher })
+    }
+
+    /// Генерирует случайную соль для нового ключа
+    pub fn generate_salt() -> Vec<u8> {
+        let mut salt = vec![0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        salt
+    }
+
+    /// Проверяет, зашифрован ли текст этим форматом
+    pub fn is_encrypted(text: &str) -> bool {
+        text.starts_with(ENCRYPTED_PREFIX)
+    }
+
+    /// Шифрует текст: `enc:v1:` + base64(nonce || ciphertext)
+    pub fn encrypt(&self, plaintext: &str) -> Result<String> {
+        let payload = self.encrypt_bytes(plaintext.as_bytes())?;
+        Ok(format!(
+            "{}{}",
+            ENCRYPTED_PREFIX,
+            base64::engine::general_purpose::STANDARD.encode(payload)
+        ))
+    }
+
+    /// Шифрует двоичные данные (вложения): nonce || ciphertext
+    pub fn encrypt_bytes(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, plaintext)
+            .map_err(|e| anyhow::anyhow!("Ошибка шифрования: {}", e))?;
+
+        let mut payload = nonce.to_vec();
+        payload.extend_from_slice(&ciphertext);
+        Ok(payload)
+    }
+
+    /// Расшифровывает данные, зашифрованные `encrypt_bytes`
+    pub fn decrypt_bytes(&self, payload: &[u8]) -> Result<Vec<u8>> {
+        if payload.len() < NONCE_LEN {
+            return Err(anyhow::anyhow!("Повреждённые зашифрованные данные"));
+        }
+
+        let (nonce, ciphertext) = payload.split_at(NONCE_LEN);
+        self.cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| anyhow::anyhow!("Неверный пароль или повреждённые данные"))
+    }
+
+    /// Расшифровывает текст; незашифрованный текст возвращается как есть
+    pub fn decrypt(&self, text: &str) -> Result<String> {
+        let Some(encoded) = text.strip_prefix(ENCRYPTED_PREFIX) else {
+            return Ok(text.to_string());
+        };
+
+        let payload = base64::engine::general_purpose::STANDARD.decode(encoded)?;
+        Ok(String::from_utf8(self.decrypt_bytes(&payload)?)?)
+    }
+}
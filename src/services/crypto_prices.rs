@@ -0,0 +1,228 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::cache::{Cached, DiskCache, CRYPTO_TTL};
+use super::iso4217;
+use crate::config::Config;
+
+/// API по умолчанию (CoinGecko); CLIPPY_CRYPTO_API_URL подменяет его, например, на мок
+pub const DEFAULT_CRYPTO_API_URL: &str = "https://api.coingecko.com/api/v3";
+/// Валюты, в которых можно спросить цену
+pub const CRYPTO_VS_CURRENCIES: [&str; 2] = ["RUB", "USD"];
+
+/// Криптовалюта: тикер, id в CoinGecko и название
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Coin {
+    pub symbol: &'static str,
+    pub id: &'static str,
+    pub name: &'static str,
+}
+
+const fn coin(symbol: &'static str, id: &'static str, name: &'static str) -> Coin {
+    Coin { symbol, id, name }
+}
+
+/// Поддерживаемые монеты
+pub const COINS: &[Coin] = &[
+    coin("BTC", "bitcoin", "Биткоин"),
+    coin("ETH", "ethereum", "Эфир"),
+    coin("USDT", "tether", "Tether"),
+    coin("USDC", "usd-coin", "USD Coin"),
+    coin("BNB", "binancecoin", "BNB"),
+    coin("SOL", "solana", "Solana"),
+    coin("XRP", "ripple", "XRP"),
+    coin("TON", "the-open-network", "Toncoin"),
+    coin("DOGE", "dogecoin", "Dogecoin"),
+    coin("TRX", "tron", "TRON"),
+    coin("ADA", "cardano", "Cardano"),
+    coin("LTC", "litecoin", "Litecoin"),
+];
+
+/// Монета по тикеру (без учёта регистра)
+pub fn find_coin(symbol: &str) -> Option<Coin> {
+    COINS.iter().copied().find(|c| c.symbol.eq_ignore_ascii_case(symbol))
+}
+
+/// Цена монеты сейчас
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CryptoQuote {
+    pub symbol: String,
+    pub name: String,
+    /// Валюта цены (RUB или USD)
+    pub vs: String,
+    pub price: f64,
+    /// Изменение за 24 часа, %
+    pub change_24h: Option<f64>,
+    /// Почасовые цены за неделю, от старых к новым
+    #[serde(default)]
+    pub sparkline: Vec<f64>,
+}
+
+impl CryptoQuote {
+    /// "5 912 345.12 ₽"; цены меньше единицы — с большей точностью
+    pub fn format_price(&self) -> String {
+        if self.price < 1.0 {
+            format!("{:.4} {}", self.price, iso4217::symbol(&self.vs))
+        } else {
+            iso4217::format_amount(self.price, &self.vs)
+        }
+    }
+
+    /// Короткая цена для виджета: "5.91 млн ₽", "67 123 $", "0.1234 $"
+    pub fn format_short(&self) -> String {
+        let symbol = iso4217::symbol(&self.vs);
+        if self.price >= 1e6 {
+            format!("{:.2} млн {}", self.price / 1e6, symbol)
+        } else if self.price >= 1e3 {
+            iso4217::format_amount(self.price.round(), &self.vs).replace(".00", "")
+        } else {
+            self.format_price()
+        }
+    }
+
+    /// "+2.31%" / "-0.80%"
+    pub fn format_change(&self) -> Option<String> {
+        self.change_24h.map(|change| format!("{:+.2}%", change))
+    }
+
+    /// Строка для ответа в чате
+    pub fn format(&self) -> String {
+        let mut text = format!("{} ({}): {}", self.name, self.symbol, self.format_price());
+        if let Some(change) = self.format_change() {
+            let icon = if self.change_24h.unwrap_or_default() >= 0.0 { "📈" } else { "📉" };
+            text.push_str(&format!(", за сутки {} {}", icon, change));
+        }
+        text
+    }
+}
+
+/// Элемент ответа `/coins/markets`
+#[derive(Debug, Deserialize)]
+struct MarketEntry {
+    id: String,
+    current_price: Option<f64>,
+    price_change_percentage_24h: Option<f64>,
+    sparkline_in_7d: Option<Sparkline>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Sparkline {
+    price: Vec<f64>,
+}
+
+/// Цены криптовалют через CoinGecko-совместимый API (`/coins/markets`)
+pub struct CryptoService {
+    http_client: reqwest::Client,
+    cache: DiskCache,
+    endpoint: String,
+    /// Монеты для виджетов (CLIPPY_CRYPTO_COINS)
+    coins: Vec<String>,
+    /// Валюта цен на виджетах (CLIPPY_CRYPTO_VS)
+    vs: String,
+}
+
+impl CryptoService {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            http_client: reqwest::Client::new(),
+            cache: DiskCache::from_config(config),
+            endpoint: config.crypto_api_url.trim_end_matches('/').to_string(),
+            coins: config.crypto_coins.clone(),
+            vs: config.crypto_vs.clone(),
+        }
+    }
+
+    pub fn coins(&self) -> &[String] {
+        &self.coins
+    }
+
+    pub fn vs(&self) -> &str {
+        &self.vs
+    }
+
+    /// Цены монет для виджетов
+    pub async fn get_quotes(&self) -> Result<Cached<Vec<CryptoQuote>>> {
+        self.get_prices(&self.coins, &self.vs).await
+    }
+
+    /// Цены монет в валюте `vs` в порядке `coins` (из кэша, если они свежие;
+    /// без сети — последние известные)
+    pub async fn get_prices(&self, coins: &[String], vs: &str) -> Result<Cached<Vec<CryptoQuote>>> {
+        let key = format!("crypto:{}:{}:{}", self.endpoint, coins.join(","), vs);
+        self.cache
+            .get_or_fetch(&key, CRYPTO_TTL, || self.fetch_prices(coins, vs))
+            .await
+    }
+
+    async fn fetch_prices(&self, coins: &[String], vs: &str) -> Result<Vec<CryptoQuote>> {
+        let known: Vec<Coin> = coins.iter().filter_map(|symbol| find_coin(symbol)).collect();
+        if known.is_empty() {
+            return Err(anyhow::anyhow!("Нет известных монет в списке: {}", coins.join(", ")));
+        }
+        let ids: Vec<&str> = known.iter().map(|c| c.id).collect();
+        let url = format!(
+            "{}/coins/markets?vs_currency={}&ids={}&sparkline=true&price_change_percentage=24h",
+            self.endpoint,
+            vs.to_lowercase(),
+            ids.join(",")
+        );
+
+        let response = self.http_client.get(&url).send().await?;
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!("Сервис цен криптовалют ответил {}", response.status()));
+        }
+        let entries = response.json::<Vec<MarketEntry>>().await?;
+        Ok(parse_markets(&entries, &known, vs))
+    }
+}
+
+/// Цены монет `known` в их порядке; монеты без цены в ответе пропускаются
+fn parse_markets(entries: &[MarketEntry], known: &[Coin], vs: &str) -> Vec<CryptoQuote> {
+    known
+        .iter()
+        .filter_map(|coin| {
+            let entry = entries.iter().find(|e| e.id == coin.id)?;
+            Some(CryptoQuote {
+                symbol: coin.symbol.to_string(),
+                name: coin.name.to_string(),
+                vs: vs.to_string(),
+                price: entry.current_price?,
+                change_24h: entry.price_change_percentage_24h,
+                sparkline: entry.sparkline_in_7d.as_ref().map(|s| s.price.clone()).unwrap_or_default(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Сокращённый ответ `/coins/markets?vs_currency=rub&sparkline=true`: лишние поля
+    /// оставлены, чтобы проверить, что они не мешают разбору; у TON нет цены
+    const MARKETS_RESPONSE: &str = r#"[
+        {"id": "ethereum", "symbol": "eth", "name": "Ethereum", "current_price": 312456.7,
+         "market_cap": 37712345678901, "price_change_percentage_24h": -1.2345,
+         "sparkline_in_7d": {"price": [310000.1, 311000.2, 312456.7]}},
+        {"id": "bitcoin", "symbol": "btc", "name": "Bitcoin", "current_price": 8912345.12,
+         "market_cap": 176543210987654, "price_change_percentage_24h": 2.31,
+         "sparkline_in_7d": null},
+        {"id": "the-open-network", "symbol": "ton", "name": "Toncoin", "current_price": null,
+         "price_change_percentage_24h": null}
+    ]"#;
+
+    #[test]
+    fn markets_response_is_parsed_in_requested_order() {
+        let entries: Vec<MarketEntry> = serde_json::from_str(MARKETS_RESPONSE).unwrap();
+        let known: Vec<Coin> = ["BTC", "ETH", "TON", "DOGE"].iter().filter_map(|s| find_coin(s)).collect();
+        let quotes = parse_markets(&entries, &known, "RUB");
+
+        assert_eq!(quotes.iter().map(|q| q.symbol.as_str()).collect::<Vec<_>>(), ["BTC", "ETH"]);
+        assert_eq!(quotes[0].price, 8912345.12);
+        assert!(quotes[0].sparkline.is_empty());
+        assert_eq!(quotes[0].format_short(), "8.91 млн ₽");
+        assert_eq!(quotes[1].name, "Эфир");
+        assert_eq!(quotes[1].format_change().as_deref(), Some("-1.23%"));
+        assert_eq!(quotes[1].sparkline.len(), 3);
+    }
+}
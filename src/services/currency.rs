@@ -1,112 +1,750 @@
 use anyhow::Result;
+use chrono::{Duration, Local, NaiveDate};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
+use super::cache::{Cached, DiskCache, RATES_TTL};
+use super::iso4217;
+use super::storage_actor::StorageHandle;
+use crate::config::Config;
+
+/// Файл со списком валют в каталоге профиля
+pub const WATCHLIST_FILE: &str = "currencies.json";
+/// За сколько дней хранится и догружается история курсов
+pub const HISTORY_DAYS: i64 = 365;
+
+/// Источник курсов валют (CLIPPY_RATES_SOURCE: cbr | exchangerate)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum RateSource {
+    /// Официальные курсы Банка России (XML_daily.asp)
+    #[default]
+    Cbr,
+    /// exchangerate-api.com
+    ExchangerateApi,
+}
+
+impl RateSource {
+    /// Все источники в порядке запасных вариантов
+    pub const ALL: [RateSource; 2] = [Self::Cbr, Self::ExchangerateApi];
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "cbr" | "цб" | "цбрф" => Some(Self::Cbr),
+            "exchangerate" | "exchangerate-api" => Some(Self::ExchangerateApi),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Cbr => "ЦБ РФ",
+            Self::ExchangerateApi => "exchangerate-api",
+        }
+    }
+
+    /// Идентификатор для хранения в БД (читается обратно через `parse`)
+    pub fn id(&self) -> &'static str {
+        match self {
+            Self::Cbr => "cbr",
+            Self::ExchangerateApi => "exchangerate",
+        }
+    }
+}
+
+/// Курс валюты в рублях за единицу на дату — точка истории курсов (в БД
+/// хранятся курсы ЦБ к рублю, курсы к другой базовой валюте считаются из них)
+#[derive(Debug, Clone, PartialEq)]
+pub struct RatePoint {
+    pub currency: String,
+    pub date: NaiveDate,
+    pub rate: f64,
+    pub source: RateSource,
+}
+
+/// Курс валюты в базовой валюте за одну единицу
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExchangeRate {
     pub currency: String,
-    pub rate: f32,
+    #[serde(default = "default_base")]
+    pub base: String,
+    pub rate: f64,
+    /// За сколько единиц валюты источник публикует курс (у ЦБ иена — за 100)
+    #[serde(default = "default_nominal")]
+    pub nominal: u32,
+    /// На какую дату установлен курс
+    #[serde(default)]
+    pub date: Option<NaiveDate>,
+    #[serde(default)]
+    pub source: RateSource,
+}
+
+fn default_nominal() -> u32 {
+    1
+}
+
+fn default_base() -> String {
+    "RUB".to_string()
+}
+
+impl ExchangeRate {
+    /// Курс за номинал источника: "92.50 ₽" или "61.23 ₽ за 100"
+    pub fn format_value(&self) -> String {
+        let value = self.rate * self.nominal as f64;
+        let symbol = iso4217::symbol(&self.base);
+        let amount = if value < 1.0 {
+            format!("{:.4} {}", value, symbol)
+        } else {
+            format!("{:.2} {}", value, symbol)
+        };
+        if self.nominal > 1 {
+            format!("{} за {}", amount, self.nominal)
+        } else {
+            amount
+        }
+    }
+
+    /// Откуда курс: "ЦБ РФ · 18.10"
+    pub fn source_label(&self) -> String {
+        match self.date {
+            Some(date) => format!("{} · {}", self.source.label(), date.format("%d.%m")),
+            None => self.source.label().to_string(),
+        }
+    }
+}
+
+/// Результат пересчёта суммы из одной валюты в другую
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conversion {
+    pub amount: f64,
+    pub from: String,
+    pub to: String,
+    /// Сумма в `to`, округлённая до минимальной единицы валюты
+    pub result: f64,
+    /// Сколько `to` дают за единицу `from`
+    pub rate: f64,
+    pub date: Option<NaiveDate>,
+    pub source: RateSource,
+}
+
+impl Conversion {
+    /// "250 € = 27 143.75 ₽ (1 EUR = 108.575 RUB, ЦБ РФ · 18.10)"
+    pub fn format(&self) -> String {
+        let source = match self.date {
+            Some(date) => format!("{} · {}", self.source.label(), date.format("%d.%m")),
+            None => self.source.label().to_string(),
+        };
+        format!(
+            "{} = {} (1 {} = {} {}, {})",
+            iso4217::format_amount(self.amount, &self.from),
+            iso4217::format_amount(self.result, &self.to),
+            self.from,
+            format_rate(self.rate),
+            self.to,
+            source
+        )
+    }
+}
+
+/// Курс с четырьмя значащими цифрами после ведущих нулей: 108.5750, 0.009213
+fn format_rate(rate: f64) -> String {
+    let digits = if rate >= 1.0 { 4 } else { (4 - rate.log10().floor() as i32 - 1).clamp(4, 10) as usize };
+    format!("{:.*}", digits, rate)
+}
+
+/// Пересчитывает сумму по курсам к базовой валюте: кросс-курс `from`/`to`
+/// через базу, результат округляется по правилам валюты `to`
+pub fn convert_with(rates: &[ExchangeRate], base: &str, amount: f64, from: &str, to: &str) -> Result<Conversion> {
+    let rate_of = |code: &str| -> Result<(f64, Option<&ExchangeRate>)> {
+        if code == base {
+            return Ok((1.0, None));
+        }
+        rates
+            .iter()
+            .find(|r| r.currency == code && r.rate > 0.0)
+            .map(|r| (r.rate, Some(r)))
+            .ok_or_else(|| anyhow::anyhow!("Нет курса {} к {}", code, base))
+    };
+    let (from_rate, from_quote) = rate_of(from)?;
+    let (to_rate, to_quote) = rate_of(to)?;
+    let quote = from_quote.or(to_quote);
+
+    let rate = from_rate / to_rate;
+    Ok(Conversion {
+        amount,
+        from: from.to_string(),
+        to: to.to_string(),
+        result: iso4217::round(amount * rate, to),
+        rate,
+        date: quote.and_then(|q| q.date),
+        source: quote.map(|q| q.source).unwrap_or_default(),
+    })
+}
+
+/// Отслеживаемые валюты и базовая валюта, к которой считаются курсы
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Watchlist {
+    pub base: String,
+    pub codes: Vec<String>,
+}
+
+impl Watchlist {
+    /// Список из файла; если файла нет — `defaults` (из настроек)
+    pub fn load(path: &Path, defaults: &Watchlist) -> Self {
+        match std::fs::read_to_string(path) {
+            Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                log::warn!("⚠️ Не удалось прочитать {}: {}", path.display(), e);
+                defaults.clone()
+            }),
+            Err(_) => defaults.clone(),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Добавляет валюту; false — она уже в списке
+    pub fn add(&mut self, code: &str) -> Result<bool> {
+        let code = known_code(code)?;
+        if self.codes.contains(&code) {
+            return Ok(false);
+        }
+        self.codes.push(code);
+        Ok(true)
+    }
+
+    /// Убирает валюту; false — её не было в списке
+    pub fn remove(&mut self, code: &str) -> bool {
+        let before = self.codes.len();
+        self.codes.retain(|c| !c.eq_ignore_ascii_case(code));
+        self.codes.len() != before
+    }
+
+    /// Меняет базовую валюту; сама база из списка убирается
+    pub fn set_base(&mut self, code: &str) -> Result<()> {
+        let code = known_code(code)?;
+        self.codes.retain(|c| *c != code);
+        self.base = code;
+        Ok(())
+    }
+}
+
+/// Код из таблицы ISO 4217 в верхнем регистре
+fn known_code(code: &str) -> Result<String> {
+    iso4217::lookup(code.trim())
+        .map(|c| c.code.to_string())
+        .ok_or_else(|| anyhow::anyhow!("Неизвестный код валюты «{}»", code.trim()))
 }
 
 // Response structure for Exchangerate-API
 #[derive(Debug, Deserialize)]
 struct ExchangerateApiResponse {
-    rates: HashMap<String, f32>,
+    rates: HashMap<String, f64>,
+    date: Option<String>,
 }
 
-/// Сервис для получения курсов валют через Exchangerate-API
+/// Сервис курсов валют из списка `/currency` к базовой валюте. Источники
+/// опрашиваются по цепочке: сначала основной (CLIPPY_RATES_SOURCE), при ошибке — остальные
 pub struct CurrencyService {
     http_client: reqwest::Client,
-    base_currency: String,
+    cache: DiskCache,
+    watchlist_file: PathBuf,
+    /// Список по умолчанию (CLIPPY_BASE_CURRENCY, CLIPPY_CURRENCIES)
+    default_watchlist: Watchlist,
+    providers: Vec<RateSource>,
 }
 
 impl CurrencyService {
-    pub fn new() -> Self {
+    pub fn from_config(config: &Config) -> Self {
+        let primary = config.rates_source;
+        let mut providers = vec![primary];
+        providers.extend(RateSource::ALL.into_iter().filter(|source| *source != primary));
+
         Self {
             http_client: reqwest::Client::new(),
-            base_currency: "RUB".to_string(),
+            cache: DiskCache::from_config(config),
+            watchlist_file: config.data_dir().join(WATCHLIST_FILE),
+            default_watchlist: Watchlist {
+                base: config.base_currency.clone(),
+                codes: config.currency_watchlist.clone(),
+            },
+            providers,
         }
     }
 
-    /// Получает курсы валют USD, EUR, GBP и JPY к RUB
-    /// Использует бесплатный API exchangerate-api.com
-    pub async fn get_rates(&self) -> Result<Vec<ExchangeRate>> {
-        let target_currencies = vec!["USD", "EUR", "GBP", "JPY", "CNY", "CHF"];
+    pub fn watchlist(&self) -> Watchlist {
+        Watchlist::load(&self.watchlist_file, &self.default_watchlist)
+    }
+
+    pub fn store_watchlist(&self, watchlist: &Watchlist) -> Result<()> {
+        watchlist.save(&self.watchlist_file)
+    }
 
-        // Используем exchangerate-api.com для получения курсов
-        // Endpoint: latest/{base_currency}
+    /// Курсы валют из списка в порядке списка (из кэша, если они свежие;
+    /// без сети — последние известные)
+    pub async fn get_rates(&self) -> Result<Cached<Vec<ExchangeRate>>> {
+        self.rates_for(&self.watchlist()).await
+    }
+
+    async fn rates_for(&self, watchlist: &Watchlist) -> Result<Cached<Vec<ExchangeRate>>> {
+        let key = format!("rates:{}:{}:{:?}", watchlist.base, watchlist.codes.join(","), self.providers[0]);
+        self.cache
+            .get_or_fetch(&key, RATES_TTL, || self.fetch_rates(watchlist))
+            .await
+    }
+
+    /// Пересчитывает `amount` из `from` в `to` по курсам к базовой валюте списка;
+    /// валюты не обязаны быть в списке
+    pub async fn convert(&self, amount: f64, from: &str, to: &str) -> Result<Cached<Conversion>> {
+        let from = known_code(from)?;
+        let to = known_code(to)?;
+        let base = self.watchlist().base;
+        let mut codes: Vec<String> = [&from, &to].into_iter().filter(|c| **c != base).cloned().collect();
+        codes.dedup();
+
+        let rates = if codes.is_empty() {
+            Cached {
+                value: Vec::new(),
+                fetched_at: Local::now(),
+                stale: false,
+            }
+        } else {
+            self.rates_for(&Watchlist { base: base.clone(), codes }).await?
+        };
+        let conversion = convert_with(&rates.value, &base, amount, &from, &to)?;
+        log::info!("💱 {} {} → {} {} (курс {})", amount, from, conversion.result, to, conversion.rate);
+        Ok(rates.map(|_| conversion))
+    }
+
+    /// Курсы от первого ответившего источника цепочки
+    async fn fetch_rates(&self, watchlist: &Watchlist) -> Result<Vec<ExchangeRate>> {
+        let mut last_error = None;
+        for source in &self.providers {
+            let result = match source {
+                RateSource::Cbr => self.fetch_cbr(watchlist).await,
+                RateSource::ExchangerateApi => self.fetch_exchangerate_api(watchlist).await,
+            };
+            match result {
+                Ok(rates) if !rates.is_empty() => return Ok(rates),
+                Ok(_) => last_error = Some(anyhow::anyhow!("{}: в ответе нет курсов валют", source.label())),
+                Err(e) => {
+                    log::warn!("⚠️ Источник курсов {} недоступен: {}", source.label(), e);
+                    last_error = Some(e);
+                }
+            }
+        }
+        Err(last_error.unwrap_or_else(|| anyhow::anyhow!("Нет источников курсов валют")))
+    }
+
+    /// Официальные курсы ЦБ РФ на сегодня; к нерублёвой базе — кросс-курсы через рубль
+    async fn fetch_cbr(&self, watchlist: &Watchlist) -> Result<Vec<ExchangeRate>> {
+        let response = self
+            .http_client
+            .get("https://www.cbr.ru/scripts/XML_daily.asp")
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!("ЦБ РФ ответил {}", response.status()));
+        }
+        // Ответ в windows-1251, но нужные поля (коды, числа, дата) — ASCII
+        let bytes = response.bytes().await?;
+        let daily = parse_cbr_daily(&String::from_utf8_lossy(&bytes))?;
+        cbr_cross_rates(&daily, watchlist)
+    }
+
+    /// Курсы exchangerate-api.com. `latest/<база>` отдаёт, сколько валюты дают
+    /// за единицу базы, поэтому курс переворачивается
+    async fn fetch_exchangerate_api(&self, watchlist: &Watchlist) -> Result<Vec<ExchangeRate>> {
         let url = format!(
             "https://api.exchangerate-api.com/v4/latest/{}",
-            self.base_currency
+            watchlist.base
         );
 
         let response = self.http_client.get(&url).send().await?;
 
         if !response.status().is_success() {
-            log::warn!("⚠️ Ошибка получения курсов валют: {}", response.status());
-            // Fallback на приблизительные значения если API недоступен
-            return Ok(vec![
-                ExchangeRate {
-                    currency: "USD".to_string(),
-                    rate: 90.0,
-                },
-                ExchangeRate {
-                    currency: "EUR".to_string(),
-                    rate: 98.0,
-                },
-                ExchangeRate {
-                    currency: "GBP".to_string(),
-                    rate: 113.0,
-                },
-                ExchangeRate {
-                    currency: "JPY".to_string(),
-                    rate: 0.60,
-                },
-            ]);
+            return Err(anyhow::anyhow!("Сервис курсов валют ответил {}", response.status()));
         }
 
         let api_response: ExchangerateApiResponse = response.json().await?;
+        let date = api_response
+            .date
+            .as_deref()
+            .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok());
+
+        Ok(watchlist
+            .codes
+            .iter()
+            .filter_map(|currency| {
+                let per_base = *api_response.rates.get(currency)?;
+                (per_base > 0.0).then(|| ExchangeRate {
+                    currency: currency.clone(),
+                    base: watchlist.base.clone(),
+                    rate: 1.0 / per_base,
+                    nominal: 1,
+                    date,
+                    source: RateSource::ExchangerateApi,
+                })
+            })
+            .collect())
+    }
 
-        let mut rates = Vec::new();
-        for currency in target_currencies {
-            if let Some(&rate) = api_response.rates.get(currency) {
-                rates.push(ExchangeRate {
-                    currency: currency.to_string(),
-                    rate: rate as f32,
-                });
+    /// Дневные курсы валюты из XML_dynamic.asp ЦБ РФ за период (только рабочие дни)
+    async fn fetch_history(&self, currency: &str, from: NaiveDate, to: NaiveDate) -> Result<Vec<RatePoint>> {
+        let id = cbr_id(currency).ok_or_else(|| anyhow::anyhow!("ЦБ РФ не публикует курс {}", currency))?;
+        let url = format!(
+            "https://www.cbr.ru/scripts/XML_dynamic.asp?date_req1={}&date_req2={}&VAL_NM_RQ={}",
+            from.format("%d/%m/%Y"),
+            to.format("%d/%m/%Y"),
+            id
+        );
+        let response = self.http_client.get(&url).send().await?;
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!("ЦБ РФ ответил {}", response.status()));
+        }
+        let bytes = response.bytes().await?;
+        Ok(parse_cbr_dynamic(&String::from_utf8_lossy(&bytes), currency))
+    }
+
+    /// Догружает в БД недостающую историю курсов ЦБ для валют списка и базы:
+    /// от последней запрошенной даты (или за HISTORY_DAYS дней) до сегодня.
+    /// Ошибка по одной валюте пишется в лог и не мешает остальным
+    pub async fn sync_history(&self, storage: &StorageHandle, watchlist: &Watchlist) {
+        let currencies = watchlist.codes.iter().chain(std::iter::once(&watchlist.base));
+        for currency in currencies.filter(|c| *c != "RUB") {
+            if let Err(e) = self.sync_currency_history(storage, currency).await {
+                log::warn!("⚠️ Ошибка загрузки истории курса {}: {}", currency, e);
             }
         }
+    }
 
-        if rates.is_empty() {
-            log::warn!("⚠️ Не удалось получить курсы валют из API");
+    async fn sync_currency_history(&self, storage: &StorageHandle, currency: &str) -> Result<()> {
+        let today = Local::now().date_naive();
+        let since = today - Duration::days(HISTORY_DAYS);
+        let code = currency.to_string();
+        let (stored, synced) = storage
+            .read(move |s| {
+                let rates = s.rates();
+                Ok((rates.rate_history(&code, since)?, rates.synced_until(&code)?))
+            })
+            .await?;
+        // Выходные и праздники без курса уже запрошены, если отмечены как загруженные
+        let loaded = stored.last().map(|last| last.date).max(synced);
+        let from = loaded.map_or(since, |date| (date + Duration::days(1)).max(since));
+        if from > today {
+            return Ok(());
         }
 
-        Ok(rates)
+        let points = self.fetch_history(currency, from, today).await?;
+        let count = points.len();
+        let code = currency.to_string();
+        storage
+            .call(move |s| {
+                let rates = s.rates_mut();
+                rates.save_rates(&points)?;
+                rates.set_synced_until(&code, today)
+            })
+            .await?;
+        if count > 0 {
+            log::info!("📈 История курса {}: +{} дней", currency, count);
+        }
+        Ok(())
+    }
+
+    /// История курса к базовой валюте за последние `days` дней, от старых к новым.
+    /// Для нерублёвой базы — только даты, на которые есть оба курса ЦБ
+    pub async fn history(&self, storage: &StorageHandle, currency: &str, base: &str, days: i64) -> Result<Vec<RatePoint>> {
+        let since = Local::now().date_naive() - Duration::days(days);
+        let code = currency.to_string();
+        let points = storage.read(move |s| s.rates().rate_history(&code, since)).await?;
+        if base == "RUB" {
+            return Ok(points);
+        }
+
+        let base_code = base.to_string();
+        let base_points: HashMap<NaiveDate, f64> = storage
+            .read(move |s| s.rates().rate_history(&base_code, since))
+            .await?
+            .into_iter()
+            .map(|p| (p.date, p.rate))
+            .collect();
+        Ok(points
+            .into_iter()
+            .filter_map(|p| {
+                let base_rate = *base_points.get(&p.date)?;
+                Some(RatePoint {
+                    rate: p.rate / base_rate,
+                    ..p
+                })
+            })
+            .collect())
     }
 
     /// Форматирует информацию о курсах в читаемый текст
     pub async fn format_rates_info(&self) -> Result<String> {
         let rates = self.get_rates().await?;
-        let mut result = "💱 Курсы валют к рублю (RUB):\n".to_string();
-
-        for rate in rates {
-            let symbol = match rate.currency.as_str() {
-                "USD" => "$",
-                "EUR" => "€",
-                "GBP" => "£",
-                "JPY" => "¥",
-                "CNY" => "¥",
-                "CHF" => "₣",
-                _ => "",
-            };
+        let source = rates
+            .value
+            .first()
+            .map(|r| match r.date {
+                Some(date) => format!("{} на {}", r.source.label(), date.format("%d.%m.%Y")),
+                None => r.source.label().to_string(),
+            })
+            .unwrap_or_default();
+        let base = self.watchlist().base;
+        let mut result = format!("💱 Курсы валют в {} ({}):\n", base, source);
 
-            if rate.rate < 1.0 {
-                result.push_str(&format!("• {} {}: {:.4} ₽\n", symbol, rate.currency, rate.rate));
-            } else {
-                result.push_str(&format!("• {} {}: {:.2} ₽\n", symbol, rate.currency, rate.rate));
-            }
+        for rate in &rates.value {
+            result.push_str(&format!(
+                "• {} {}: {}\n",
+                iso4217::symbol(&rate.currency),
+                rate.currency,
+                rate.format_value()
+            ));
+        }
+
+        if let Some(note) = rates.stale_note() {
+            result.push_str(&note);
         }
 
         Ok(result)
     }
 }
+
+/// Курсы ЦБ РФ к рублю на дату: код → (рублей за единицу, номинал)
+#[derive(Debug, Clone, PartialEq)]
+struct CbrDaily {
+    date: Option<NaiveDate>,
+    rates: HashMap<String, (f64, u32)>,
+}
+
+/// Разбирает XML_daily.asp: `<ValCurs Date="18.10.2026">` и блоки `<Valute>`
+/// с CharCode, Nominal и Value (десятичная запятая). Курс делится на номинал
+fn parse_cbr_daily(xml: &str) -> Result<CbrDaily> {
+    let date = xml
+        .split_once("Date=\"")
+        .and_then(|(_, rest)| rest.split_once('"'))
+        .and_then(|(date, _)| NaiveDate::parse_from_str(date, "%d.%m.%Y").ok());
+
+    let mut rates = HashMap::new();
+    for block in xml.split("<Valute").skip(1) {
+        let Some(code) = tag_text(block, "CharCode") else {
+            continue;
+        };
+        let nominal = tag_text(block, "Nominal").and_then(|n| n.parse::<u32>().ok()).unwrap_or(1).max(1);
+        let Some(value) = tag_text(block, "Value").and_then(|v| v.replace(',', ".").parse::<f64>().ok()) else {
+            log::warn!("⚠️ ЦБ РФ: нет курса для {}", code);
+            continue;
+        };
+        rates.insert(code.to_string(), (value / nominal as f64, nominal));
+    }
+
+    if rates.is_empty() {
+        return Err(anyhow::anyhow!("ЦБ РФ: в ответе нет курсов валют"));
+    }
+    Ok(CbrDaily { date, rates })
+}
+
+/// Курсы валют списка к его базе по курсам ЦБ к рублю, в порядке списка.
+/// Номинал ЦБ сохраняется только для рублёвой базы
+fn cbr_cross_rates(daily: &CbrDaily, watchlist: &Watchlist) -> Result<Vec<ExchangeRate>> {
+    let rub_per = |code: &str| -> Option<(f64, u32)> {
+        if code == "RUB" {
+            Some((1.0, 1))
+        } else {
+            daily.rates.get(code).copied()
+        }
+    };
+    let (base_rate, _) = rub_per(&watchlist.base)
+        .ok_or_else(|| anyhow::anyhow!("ЦБ РФ не публикует курс {}", watchlist.base))?;
+
+    Ok(watchlist
+        .codes
+        .iter()
+        .filter_map(|code| {
+            let Some((rate, nominal)) = rub_per(code) else {
+                log::warn!("⚠️ ЦБ РФ не публикует курс {}", code);
+                return None;
+            };
+            Some(ExchangeRate {
+                currency: code.clone(),
+                base: watchlist.base.clone(),
+                rate: rate / base_rate,
+                nominal: if watchlist.base == "RUB" { nominal } else { 1 },
+                date: daily.date,
+                source: RateSource::Cbr,
+            })
+        })
+        .collect())
+}
+
+/// Разбирает XML_dynamic.asp: записи `<Record Date="01.10.2026">` с Nominal и Value
+fn parse_cbr_dynamic(xml: &str, currency: &str) -> Vec<RatePoint> {
+    xml.split("<Record")
+        .skip(1)
+        .filter_map(|record| {
+            let date = record
+                .split_once("Date=\"")
+                .and_then(|(_, rest)| rest.split_once('"'))
+                .and_then(|(date, _)| NaiveDate::parse_from_str(date, "%d.%m.%Y").ok())?;
+            let nominal = tag_text(record, "Nominal").and_then(|n| n.parse::<u32>().ok()).unwrap_or(1).max(1);
+            let value = tag_text(record, "Value")?.replace(',', ".").parse::<f64>().ok()?;
+            Some(RatePoint {
+                currency: currency.to_string(),
+                date,
+                rate: value / nominal as f64,
+                source: RateSource::Cbr,
+            })
+        })
+        .collect()
+}
+
+/// Внутренний код валюты ЦБ РФ для XML_dynamic.asp
+fn cbr_id(currency: &str) -> Option<&'static str> {
+    let id = match currency {
+        "AUD" => "R01010",
+        "AZN" => "R01020A",
+        "GBP" => "R01035",
+        "AMD" => "R01060",
+        "BYN" => "R01090B",
+        "BGN" => "R01100",
+        "BRL" => "R01115",
+        "HUF" => "R01135",
+        "HKD" => "R01200",
+        "DKK" => "R01215",
+        "USD" => "R01235",
+        "EUR" => "R01239",
+        "INR" => "R01270",
+        "KZT" => "R01335",
+        "CAD" => "R01350",
+        "KGS" => "R01370",
+        "CNY" => "R01375",
+        "MDL" => "R01500",
+        "NOK" => "R01535",
+        "PLN" => "R01565",
+        "RON" => "R01585F",
+        "SGD" => "R01625",
+        "TJS" => "R01670",
+        "TRY" => "R01700J",
+        "TMT" => "R01710A",
+        "UZS" => "R01717",
+        "UAH" => "R01720",
+        "CZK" => "R01760",
+        "SEK" => "R01770",
+        "CHF" => "R01775",
+        "ZAR" => "R01810",
+        "KRW" => "R01815",
+        "JPY" => "R01820",
+        _ => return None,
+    };
+    Some(id)
+}
+
+/// Текст первого тега `<name>…</name>` в блоке
+fn tag_text<'a>(block: &'a str, name: &str) -> Option<&'a str> {
+    let open = format!("<{}>", name);
+    let close = format!("</{}>", name);
+    let start = block.find(&open)? + open.len();
+    let end = start + block[start..].find(&close)?;
+    Some(block[start..end].trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Фрагмент ответа XML_daily.asp (кодировка и лишние теги опущены)
+    const CBR_DAILY: &str = r#"<ValCurs Date="18.10.2026" name="Foreign Currency Market">
+        <Valute ID="R01235"><NumCode>840</NumCode><CharCode>USD</CharCode><Nominal>1</Nominal>
+            <Name>Доллар США</Name><Value>81,5000</Value></Valute>
+        <Valute ID="R01239"><NumCode>978</NumCode><CharCode>EUR</CharCode><Nominal>1</Nominal>
+            <Name>Евро</Name><Value>95,0000</Value></Valute>
+        <Valute ID="R01820"><NumCode>392</NumCode><CharCode>JPY</CharCode><Nominal>100</Nominal>
+            <Name>Японских иен</Name><Value>54,3210</Value></Valute>
+        <Valute ID="R01335"><NumCode>398</NumCode><CharCode>KZT</CharCode><Nominal>100</Nominal>
+            <Name>Казахстанских тенге</Name><Value>нет</Value></Valute>
+    </ValCurs>"#;
+
+    fn watchlist(base: &str, codes: &[&str]) -> Watchlist {
+        Watchlist {
+            base: base.to_string(),
+            codes: codes.iter().map(|c| c.to_string()).collect(),
+        }
+    }
+
+    fn rate(currency: &str, rate: f64) -> ExchangeRate {
+        ExchangeRate {
+            currency: currency.to_string(),
+            base: "RUB".to_string(),
+            rate,
+            nominal: 1,
+            date: NaiveDate::from_ymd_opt(2026, 10, 18),
+            source: RateSource::Cbr,
+        }
+    }
+
+    #[test]
+    fn cbr_daily_divides_by_nominal_and_skips_broken_values() {
+        let daily = parse_cbr_daily(CBR_DAILY).unwrap();
+        assert_eq!(daily.date, NaiveDate::from_ymd_opt(2026, 10, 18));
+        assert_eq!(daily.rates["USD"], (81.5, 1));
+        assert_eq!(daily.rates["JPY"], (0.54321, 100));
+        assert!(!daily.rates.contains_key("KZT"));
+        assert!(parse_cbr_daily("<ValCurs Date=\"18.10.2026\"></ValCurs>").is_err());
+    }
+
+    #[test]
+    fn cbr_cross_rates_go_through_the_ruble() {
+        let daily = parse_cbr_daily(CBR_DAILY).unwrap();
+
+        let rub = cbr_cross_rates(&daily, &watchlist("RUB", &["USD", "JPY", "MXN"])).unwrap();
+        assert_eq!(rub.iter().map(|r| r.currency.as_str()).collect::<Vec<_>>(), ["USD", "JPY"]);
+        assert_eq!(rub[1].nominal, 100);
+
+        let usd = cbr_cross_rates(&daily, &watchlist("USD", &["EUR", "RUB"])).unwrap();
+        assert!((usd[0].rate - 95.0 / 81.5).abs() < 1e-12);
+        assert!((usd[1].rate - 1.0 / 81.5).abs() < 1e-12);
+        assert_eq!(usd[0].nominal, 1);
+
+        assert!(cbr_cross_rates(&daily, &watchlist("MXN", &["USD"])).is_err());
+    }
+
+    #[test]
+    fn cbr_dynamic_records_become_history_points() {
+        let xml = r#"<ValCurs ID="R01820" DateRange1="09.10.2026" DateRange2="10.10.2026" name="Foreign Currency Market Dynamic">
+            <Record Date="09.10.2026" Id="R01820"><Nominal>100</Nominal><Value>54,1234</Value></Record>
+            <Record Date="10.10.2026" Id="R01820"><Nominal>100</Nominal><Value>54,5678</Value></Record>
+            <Record Date="кривая" Id="R01820"><Nominal>100</Nominal><Value>1,0</Value></Record>
+        </ValCurs>"#;
+        let points = parse_cbr_dynamic(xml, "JPY");
+        assert_eq!(points.len(), 2);
+        assert_eq!(points[0].date, NaiveDate::from_ymd_opt(2026, 10, 9).unwrap());
+        assert!((points[1].rate - 0.545678).abs() < 1e-12);
+        assert!(points.iter().all(|p| p.currency == "JPY" && p.source == RateSource::Cbr));
+    }
+
+    #[test]
+    fn conversion_uses_cross_rate_and_rounds_to_the_target() {
+        let rates = [rate("USD", 81.5), rate("EUR", 95.0), rate("JPY", 0.54321)];
+
+        let eur = convert_with(&rates, "RUB", 250.0, "EUR", "RUB").unwrap();
+        assert_eq!(eur.result, 23750.0);
+        assert_eq!(eur.date, NaiveDate::from_ymd_opt(2026, 10, 18));
+
+        let cross = convert_with(&rates, "RUB", 100.0, "EUR", "USD").unwrap();
+        assert!((cross.rate - 95.0 / 81.5).abs() < 1e-12);
+        assert_eq!(cross.result, 116.56);
+
+        let yen = convert_with(&rates, "RUB", 10.0, "USD", "JPY").unwrap();
+        assert_eq!(yen.result, 1500.0);
+
+        assert!(convert_with(&rates, "RUB", 1.0, "MXN", "RUB").is_err());
+        assert_eq!(format_rate(108.575), "108.5750");
+        assert_eq!(format_rate(0.009213), "0.009213");
+    }
+}
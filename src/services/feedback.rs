@@ -0,0 +1,122 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::Path;
+
+use super::attachment::Attachment;
+use super::store::ConversationStore;
+
+/// Оценка и пометки пользователя к ответу Скрепыша
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Feedback {
+    /// 1 — 👍, -1 — 👎, 0 — без оценки
+    pub rating: i8,
+    pub comment: Option<String>,
+    pub bookmarked: bool,
+    pub tags: Vec<String>,
+}
+
+impl Feedback {
+    /// Есть ли в записи хоть что-то, что стоит хранить
+    pub fn is_empty(&self) -> bool {
+        self.rating == 0 && self.comment.is_none() && !self.bookmarked && self.tags.is_empty()
+    }
+
+    /// Теги в виде строки для БД: "a,b,c"
+    pub fn tags_string(&self) -> String {
+        self.tags.join(",")
+    }
+
+    /// Разбирает теги, разделённые запятыми или пробелами
+    pub fn parse_tags(text: &str) -> Vec<String> {
+        text.split(|c: char| c == ',' || c.is_whitespace())
+            .map(|t| t.trim().trim_start_matches('#').to_lowercase())
+            .filter(|t| !t.is_empty())
+            .collect()
+    }
+}
+
+/// Изменение оценки из UI или команды чата
+#[derive(Debug, Clone, PartialEq)]
+pub enum FeedbackAction {
+    /// 👍 (1) или 👎 (-1); повторное нажатие снимает оценку
+    Rate(i8),
+    ToggleBookmark,
+    Comment(String),
+    AddTags(Vec<String>),
+}
+
+impl FeedbackAction {
+    pub fn apply(&self, feedback: &mut Feedback) {
+        match self {
+            Self::Rate(rating) => {
+                feedback.rating = if feedback.rating == *rating { 0 } else { *rating };
+            }
+            Self::ToggleBookmark => feedback.bookmarked = !feedback.bookmarked,
+            Self::Comment(text) => {
+                let text = text.trim();
+                feedback.comment = (!text.is_empty()).then(|| text.to_string());
+            }
+            Self::AddTags(tags) => {
+                for tag in tags {
+                    if !feedback.tags.contains(tag) {
+                        feedback.tags.push(tag.clone());
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Пара «вопрос — оценённый ответ» для выгрузки датасета
+#[derive(Debug, Clone, Serialize)]
+pub struct RatedPair {
+    pub prompt: String,
+    pub response: String,
+    pub rating: i8,
+    pub comment: Option<String>,
+    pub bookmarked: bool,
+    pub tags: Vec<String>,
+    pub model: String,
+    pub timestamp: String,
+    /// Вложения вопроса и ответа (метаданные; содержимое — по хешу)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<Attachment>,
+}
+
+/// Записывает пары в JSONL (одна JSON-строка на пару)
+pub fn write_jsonl(pairs: &[RatedPair], path: &Path) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+    for pair in pairs {
+        writeln!(file, "{}", serde_json::to_string(pair)?)?;
+    }
+    file.flush()?;
+
+    log::info!("📤 Выгружено {} оценённых ответов в {}", pairs.len(), path.display());
+    Ok(())
+}
+
+/// Копирует содержимое вложений пар в каталог `<имя>_attachments/` рядом с JSONL.
+/// Файлы называются хешем, как поле `hash` в выгрузке
+pub fn export_attachments(store: &dyn ConversationStore, pairs: &[RatedPair], jsonl_path: &Path) -> Result<usize> {
+    let stem = jsonl_path.file_stem().and_then(|s| s.to_str()).unwrap_or("feedback");
+    let dir = jsonl_path.with_file_name(format!("{}_attachments", stem));
+
+    let mut written = 0;
+    for attachment in pairs.iter().flat_map(|p| &p.attachments) {
+        let target = dir.join(&attachment.hash);
+        if target.exists() {
+            continue;
+        }
+        if let Some((_, data)) = store.fetch_attachment(attachment.id)? {
+            std::fs::create_dir_all(&dir)?;
+            std::fs::write(&target, data)?;
+            written += 1;
+        }
+    }
+    Ok(written)
+}
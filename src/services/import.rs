@@ -0,0 +1,550 @@
+use anyhow::Result;
+use chrono::{DateTime, Local, TimeZone};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use super::attachment::NewAttachment;
+use super::storage::{ImportedMessage, ImportedSession};
+use super::store::ConversationStore;
+
+/// Поддерживаемые форматы экспорта сторонних ассистентов
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportSource {
+    /// `conversations.json` из экспорта данных ChatGPT
+    ChatGpt,
+    /// JSON-экспорт чатов веб-версии GigaChat
+    GigaChat,
+}
+
+impl ImportSource {
+    /// Распознаёт источник по имени из команды (`chatgpt`, `gigachat`)
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "chatgpt" | "openai" => Some(Self::ChatGpt),
+            "gigachat" | "giga" => Some(Self::GigaChat),
+            _ => None,
+        }
+    }
+
+    /// Идентификатор источника для таблицы sessions
+    pub fn id(&self) -> &'static str {
+        match self {
+            Self::ChatGpt => "chatgpt",
+            Self::GigaChat => "gigachat",
+        }
+    }
+}
+
+/// Итог импорта
+#[derive(Debug, Default, Clone)]
+pub struct ImportReport {
+    pub sessions: usize,
+    pub messages: usize,
+    pub skipped: usize,
+}
+
+impl std::fmt::Display for ImportReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "📥 Импортировано {} сессий ({} сообщений), пропущено {}",
+            self.sessions, self.messages, self.skipped
+        )
+    }
+}
+
+/// Читает файл экспорта и сохраняет найденные разговоры в хранилище
+pub fn import_file(storage: &mut dyn ConversationStore, source: ImportSource, path: &Path) -> Result<ImportReport> {
+    let json = std::fs::read_to_string(path)?;
+    log::info!("📥 Импорт {} из {}", source.id(), path.display());
+
+    // Файлы вложений лежат в каталоге экспорта рядом с JSON
+    let base_dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let sessions = match source {
+        ImportSource::ChatGpt => parse_chatgpt_export(&json, base_dir)?,
+        ImportSource::GigaChat => parse_gigachat_export(&json, base_dir)?,
+    };
+
+    let mut report = ImportReport::default();
+    for session in &sessions {
+        match storage.import_session(session)? {
+            0 => report.skipped += 1,
+            count => {
+                report.sessions += 1;
+                report.messages += count;
+            }
+        }
+    }
+
+    log::info!("✓ {}", report);
+    Ok(report)
+}
+
+// ChatGPT: conversations.json — массив разговоров, каждый хранит дерево узлов
+// в `mapping`; активная ветка восстанавливается от `current_node` к корню
+#[derive(Debug, Deserialize)]
+struct ChatGptConversation {
+    id: Option<String>,
+    conversation_id: Option<String>,
+    title: Option<String>,
+    create_time: Option<f64>,
+    mapping: HashMap<String, ChatGptNode>,
+    current_node: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatGptNode {
+    message: Option<ChatGptMessage>,
+    parent: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatGptMessage {
+    author: ChatGptAuthor,
+    create_time: Option<f64>,
+    content: ChatGptContent,
+    metadata: Option<ChatGptMetadata>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatGptAuthor {
+    role: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatGptContent {
+    #[serde(default)]
+    parts: Vec<serde_json::Value>,
+    text: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatGptMetadata {
+    model_slug: Option<String>,
+    #[serde(default)]
+    attachments: Vec<ChatGptAttachment>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatGptAttachment {
+    id: String,
+    name: Option<String>,
+    #[serde(alias = "mimeType")]
+    mime_type: Option<String>,
+}
+
+/// Разбирает `conversations.json` из экспорта ChatGPT. Вложения ищутся в `base_dir`
+/// по id файла (`file-XXXX-имя`)
+pub fn parse_chatgpt_export(json: &str, base_dir: &Path) -> Result<Vec<ImportedSession>> {
+    let conversations: Vec<ChatGptConversation> = serde_json::from_str(json)?;
+    let mut sessions = Vec::new();
+
+    for conv in conversations {
+        let Some(source_id) = conv.conversation_id.or(conv.id) else {
+            continue;
+        };
+
+        // Идём от текущего узла к корню, затем разворачиваем
+        let mut chain = Vec::new();
+        let mut cursor = conv.current_node.clone();
+        while let Some(node_id) = cursor {
+            let Some(node) = conv.mapping.get(&node_id) else {
+                break;
+            };
+            if let Some(message) = &node.message {
+                chain.push(message);
+            }
+            cursor = node.parent.clone();
+        }
+        chain.reverse();
+
+        // Время сессии: из разговора, иначе по первому сообщению со временем
+        let Some(created_at) = conv
+            .create_time
+            .or_else(|| chain.iter().find_map(|m| m.create_time))
+            .and_then(epoch_to_rfc3339)
+        else {
+            log::warn!("⚠️ Разговор {} без времени пропущен", source_id);
+            continue;
+        };
+        let mut messages = Vec::new();
+        for message in chain {
+            let role = match message.author.role.as_str() {
+                "user" => "user",
+                "assistant" => "assistant",
+                _ => continue,
+            };
+
+            let content = if message.content.parts.is_empty() {
+                message.content.text.clone().unwrap_or_default()
+            } else {
+                message
+                    .content
+                    .parts
+                    .iter()
+                    .filter_map(|p| p.as_str())
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            };
+            if content.trim().is_empty() {
+                continue;
+            }
+
+            let model = message
+                .metadata
+                .as_ref()
+                .and_then(|m| m.model_slug.clone())
+                .unwrap_or_else(|| "ChatGPT".to_string());
+
+            // Вложения из метаданных и картинки из частей сообщения
+            let mut files: Vec<(String, Option<String>, Option<String>)> = message
+                .metadata
+                .iter()
+                .flat_map(|m| &m.attachments)
+                .map(|a| (a.id.clone(), a.name.clone(), a.mime_type.clone()))
+                .collect();
+            for part in &message.content.parts {
+                let pointer = part.get("asset_pointer").and_then(|p| p.as_str());
+                if let Some(id) = pointer.and_then(|p| p.split("://").nth(1)) {
+                    if !files.iter().any(|(known, _, _)| known == id) {
+                        files.push((id.to_string(), None, None));
+                    }
+                }
+            }
+            let attachments = files
+                .iter()
+                .filter_map(|(id, name, mime)| load_export_file(base_dir, id, name.as_deref(), mime.as_deref()))
+                .collect();
+
+            messages.push(ImportedMessage {
+                role: role.to_string(),
+                content,
+                model,
+                timestamp: message
+                    .create_time
+                    .and_then(epoch_to_rfc3339)
+                    .unwrap_or_else(|| created_at.clone()),
+                attachments,
+            });
+        }
+
+        if messages.is_empty() {
+            continue;
+        }
+
+        sessions.push(ImportedSession {
+            session_id: format!("{}:{}", ImportSource::ChatGpt.id(), source_id),
+            title: conv.title,
+            source: ImportSource::ChatGpt.id().to_string(),
+            created_at,
+            messages,
+        });
+    }
+
+    Ok(sessions)
+}
+
+// GigaChat web: массив чатов (или объект с полем `chats`) с линейным списком
+// сообщений; время бывает как RFC 3339, так и unix-секундами
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum GigaChatExport {
+    List(Vec<GigaChatChat>),
+    Wrapped { chats: Vec<GigaChatChat> },
+}
+
+#[derive(Debug, Deserialize)]
+struct GigaChatChat {
+    #[serde(alias = "chat_id", alias = "uuid")]
+    id: ExportId,
+    #[serde(alias = "name")]
+    title: Option<String>,
+    #[serde(alias = "created", alias = "createdAt")]
+    created_at: Option<ExportTimestamp>,
+    #[serde(default)]
+    messages: Vec<GigaChatMessage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GigaChatMessage {
+    #[serde(alias = "author", alias = "sender")]
+    role: String,
+    #[serde(alias = "text")]
+    content: String,
+    #[serde(alias = "created", alias = "createdAt", alias = "timestamp")]
+    created_at: Option<ExportTimestamp>,
+    model: Option<String>,
+    #[serde(default, alias = "files")]
+    attachments: Vec<GigaChatAttachment>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GigaChatAttachment {
+    #[serde(alias = "file", alias = "file_path")]
+    path: String,
+    #[serde(alias = "filename")]
+    name: Option<String>,
+    #[serde(alias = "mimeType", alias = "mime")]
+    mime_type: Option<String>,
+}
+
+/// Id чата: в разных версиях экспорта строка или число
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+enum ExportId {
+    Number(serde_json::Number),
+    Text(String),
+}
+
+impl std::fmt::Display for ExportId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Number(number) => write!(f, "{}", number),
+            Self::Text(text) => f.write_str(text),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+enum ExportTimestamp {
+    Epoch(f64),
+    Text(String),
+}
+
+impl ExportTimestamp {
+    /// RFC 3339 локального пояса; `None` если время не разобрать
+    fn to_rfc3339(&self) -> Option<String> {
+        match self {
+            Self::Epoch(secs) => epoch_to_rfc3339(*secs),
+            Self::Text(text) => DateTime::parse_from_rfc3339(text)
+                .ok()
+                .map(|dt| dt.with_timezone(&Local).to_rfc3339()),
+        }
+    }
+}
+
+/// Разбирает JSON-экспорт чатов веб-версии GigaChat. Пути вложений — относительно `base_dir`
+pub fn parse_gigachat_export(json: &str, base_dir: &Path) -> Result<Vec<ImportedSession>> {
+    let chats = match serde_json::from_str::<GigaChatExport>(json)? {
+        GigaChatExport::List(chats) => chats,
+        GigaChatExport::Wrapped { chats } => chats,
+    };
+
+    let mut sessions = Vec::new();
+    for chat in chats {
+        // Время сессии: из чата, иначе по первому сообщению со временем
+        let Some(created_at) = chat
+            .created_at
+            .as_ref()
+            .and_then(|t| t.to_rfc3339())
+            .or_else(|| chat.messages.iter().find_map(|m| m.created_at.as_ref()?.to_rfc3339()))
+        else {
+            log::warn!("⚠️ Чат {} без времени пропущен", chat.id);
+            continue;
+        };
+
+        let messages: Vec<ImportedMessage> = chat
+            .messages
+            .into_iter()
+            .filter(|m| !m.content.trim().is_empty())
+            .filter_map(|m| {
+                // Системные и служебные сообщения не импортируются, как и в ChatGPT
+                let role = match m.role.to_lowercase().as_str() {
+                    "user" | "human" => "user",
+                    "assistant" | "ai" | "bot" | "model" | "gigachat" => "assistant",
+                    _ => return None,
+                };
+                Some(ImportedMessage {
+                    role: role.to_string(),
+                    timestamp: m
+                        .created_at
+                        .as_ref()
+                        .and_then(|t| t.to_rfc3339())
+                        .unwrap_or_else(|| created_at.clone()),
+                    attachments: m
+                        .attachments
+                        .iter()
+                        .filter_map(|a| {
+                            NewAttachment::from_file(&base_dir.join(&a.path), a.name.as_deref(), a.mime_type.as_deref())
+                                .map_err(|e| log::warn!("⚠️ Вложение {} не прочитано: {}", a.path, e))
+                                .ok()
+                        })
+                        .collect(),
+                    content: m.content,
+                    model: m.model.unwrap_or_else(|| "GigaChat".to_string()),
+                })
+            })
+            .collect();
+
+        if messages.is_empty() {
+            continue;
+        }
+
+        sessions.push(ImportedSession {
+            session_id: format!("{}:{}", ImportSource::GigaChat.id(), chat.id),
+            title: chat.title,
+            source: ImportSource::GigaChat.id().to_string(),
+            created_at,
+            messages,
+        });
+    }
+
+    Ok(sessions)
+}
+
+/// Находит файл экспорта ChatGPT по id вложения (`file-XXXX-имя.ext`) и читает его.
+/// После id в имени файла идёт разделитель: `file-12` не совпадает с `file-123-…`
+fn load_export_file(base_dir: &Path, id: &str, name: Option<&str>, mime: Option<&str>) -> Option<NewAttachment> {
+    let path: PathBuf = std::fs::read_dir(base_dir)
+        .ok()?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .find(|p| {
+            p.is_file()
+                && p.file_name()
+                    .and_then(|n| n.to_str())
+                    .and_then(|n| n.strip_prefix(id))
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with(['-', '_', '.']))
+        })?;
+
+    match NewAttachment::from_file(&path, name, mime) {
+        Ok(file) => Some(file),
+        Err(e) => {
+            log::warn!("⚠️ Вложение {} не прочитано: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// Переводит unix-время (секунды с дробной частью) в RFC 3339 локального пояса
+fn epoch_to_rfc3339(secs: f64) -> Option<String> {
+    Local
+        .timestamp_millis_opt((secs * 1000.0) as i64)
+        .single()
+        .map(|dt| dt.to_rfc3339())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chatgpt_export_follows_active_branch() {
+        let json = r#"[{
+            "conversation_id": "c1",
+            "title": "Погода",
+            "create_time": 1700000000.5,
+            "current_node": "n3",
+            "mapping": {
+                "n0": {"message": null, "parent": null},
+                "n1": {"message": {"author": {"role": "system"}, "create_time": null,
+                        "content": {"parts": ["Ты помощник"]}}, "parent": "n0"},
+                "n2": {"message": {"author": {"role": "user"}, "create_time": 1700000001.0,
+                        "content": {"parts": ["Привет"]}}, "parent": "n1"},
+                "n2b": {"message": {"author": {"role": "assistant"}, "create_time": 1700000002.0,
+                        "content": {"parts": ["Старая ветка"]}}, "parent": "n2"},
+                "n3": {"message": {"author": {"role": "assistant"}, "create_time": null,
+                        "content": {"parts": ["Здравствуй!"]}, "metadata": {"model_slug": "gpt-4o"}}, "parent": "n2"}
+            }
+        }]"#;
+
+        let sessions = parse_chatgpt_export(json, Path::new(".")).unwrap();
+        assert_eq!(sessions.len(), 1);
+        let session = &sessions[0];
+        assert_eq!(session.session_id, "chatgpt:c1");
+        assert_eq!(session.title.as_deref(), Some("Погода"));
+
+        let messages: Vec<(&str, &str)> = session.messages.iter().map(|m| (m.role.as_str(), m.content.as_str())).collect();
+        assert_eq!(messages, [("user", "Привет"), ("assistant", "Здравствуй!")]);
+        assert_eq!(session.messages[1].model, "gpt-4o");
+        // Сообщение без времени получает время сессии
+        assert_eq!(session.messages[1].timestamp, session.created_at);
+    }
+
+    #[test]
+    fn chatgpt_conversation_without_any_time_is_skipped() {
+        let json = r#"[{
+            "id": "c2",
+            "current_node": "n1",
+            "mapping": {
+                "n1": {"message": {"author": {"role": "user"}, "content": {"parts": ["Привет"]}}, "parent": null}
+            }
+        }]"#;
+        assert!(parse_chatgpt_export(json, Path::new(".")).unwrap().is_empty());
+    }
+
+    #[test]
+    fn gigachat_export_maps_roles_and_skips_system() {
+        let json = r#"{"chats": [{
+            "id": "g1",
+            "name": "Курсы",
+            "messages": [
+                {"role": "system", "content": "Служебное", "created_at": "2024-05-01T10:00:00+03:00"},
+                {"role": "user", "content": "Курс доллара?", "created_at": 1714546800},
+                {"role": "assistant", "text": "92 рубля"},
+                {"role": "user", "content": "   "}
+            ]
+        }]}"#;
+
+        let sessions = parse_gigachat_export(json, Path::new(".")).unwrap();
+        assert_eq!(sessions.len(), 1);
+        let session = &sessions[0];
+        assert_eq!(session.session_id, "gigachat:g1");
+        let roles: Vec<&str> = session.messages.iter().map(|m| m.role.as_str()).collect();
+        assert_eq!(roles, ["user", "assistant"]);
+        assert_eq!(session.messages[1].model, "GigaChat");
+        // Время сессии берётся из первого сообщения со временем
+        assert_eq!(session.created_at, epoch_to_rfc3339(1714546800.0).unwrap());
+        assert_eq!(session.messages[1].timestamp, session.created_at);
+    }
+
+    #[test]
+    fn gigachat_chat_without_any_time_is_skipped() {
+        let json = r#"[{"id": "g2", "messages": [{"role": "user", "content": "Привет"}]}]"#;
+        assert!(parse_gigachat_export(json, Path::new(".")).unwrap().is_empty());
+    }
+
+    #[test]
+    fn unparsable_text_timestamp_falls_back_to_session_time() {
+        let json = r#"[{"id": "g3", "created_at": 1714546800, "messages": [
+            {"role": "user", "content": "Привет", "created_at": "вчера"}
+        ]}]"#;
+        let sessions = parse_gigachat_export(json, Path::new(".")).unwrap();
+        assert_eq!(sessions[0].messages[0].timestamp, sessions[0].created_at);
+    }
+
+    #[test]
+    fn gigachat_chat_ids_may_be_numbers() {
+        let json = r#"[
+            {"id": 12345, "created_at": 1714546800, "messages": [{"role": "user", "content": "Привет"}]},
+            {"id": "g4", "created_at": 1714546800, "messages": [{"role": "user", "content": "Пока"}]}
+        ]"#;
+        let sessions = parse_gigachat_export(json, Path::new(".")).unwrap();
+        let ids: Vec<&str> = sessions.iter().map(|s| s.session_id.as_str()).collect();
+        assert_eq!(ids, ["gigachat:12345", "gigachat:g4"]);
+    }
+
+    #[test]
+    fn export_file_is_found_by_whole_id() {
+        let dir = std::env::temp_dir().join(format!("clippy-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("file-123-отчёт.txt"), "длинный id").unwrap();
+        std::fs::write(dir.join("file-12.png"), "короткий id").unwrap();
+
+        let short = load_export_file(&dir, "file-12", None, None).unwrap();
+        assert_eq!(short.data, "короткий id".as_bytes());
+        let long = load_export_file(&dir, "file-123", None, None).unwrap();
+        assert_eq!(long.data, "длинный id".as_bytes());
+        assert!(load_export_file(&dir, "file-1", None, None).is_none());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn import_source_names() {
+        assert_eq!(ImportSource::parse(" ChatGPT "), Some(ImportSource::ChatGpt));
+        assert_eq!(ImportSource::parse("giga"), Some(ImportSource::GigaChat));
+        assert_eq!(ImportSource::parse("claude"), None);
+    }
+}
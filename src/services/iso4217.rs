@@ -0,0 +1,257 @@
+/// Таблица валют ISO 4217: символ, название и число знаков после запятой
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CurrencyInfo {
+    pub code: &'static str,
+    pub symbol: &'static str,
+    pub name: &'static str,
+    /// Знаков в дробной части суммы (у иены и воны копеек нет)
+    pub decimals: u32,
+}
+
+const fn entry(code: &'static str, symbol: &'static str, name: &'static str) -> CurrencyInfo {
+    CurrencyInfo { code, symbol, name, decimals: 2 }
+}
+
+const fn whole(code: &'static str, symbol: &'static str, name: &'static str) -> CurrencyInfo {
+    CurrencyInfo { code, symbol, name, decimals: 0 }
+}
+
+/// Динары и риалы Персидского залива и Северной Африки делятся на 1000 филсов
+const fn thousandths(code: &'static str, symbol: &'static str, name: &'static str) -> CurrencyInfo {
+    CurrencyInfo { code, symbol, name, decimals: 3 }
+}
+
+/// Все действующие валюты ISO 4217: сначала валюты ЦБ РФ и ближнего зарубежья, затем остальные
+pub const CURRENCIES: &[CurrencyInfo] = &[
+    entry("RUB", "₽", "российский рубль"),
+    entry("USD", "$", "доллар США"),
+    entry("EUR", "€", "евро"),
+    entry("GBP", "£", "фунт стерлингов"),
+    entry("CHF", "₣", "швейцарский франк"),
+    whole("JPY", "¥", "японская иена"),
+    entry("CNY", "¥", "китайский юань"),
+    entry("HKD", "HK$", "гонконгский доллар"),
+    whole("KRW", "₩", "южнокорейская вона"),
+    entry("INR", "₹", "индийская рупия"),
+    entry("SGD", "S$", "сингапурский доллар"),
+    entry("AUD", "A$", "австралийский доллар"),
+    entry("NZD", "NZ$", "новозеландский доллар"),
+    entry("CAD", "C$", "канадский доллар"),
+    entry("BRL", "R$", "бразильский реал"),
+    entry("ZAR", "R", "южноафриканский рэнд"),
+    entry("SEK", "kr", "шведская крона"),
+    entry("NOK", "kr", "норвежская крона"),
+    entry("DKK", "kr", "датская крона"),
+    entry("PLN", "zł", "польский злотый"),
+    entry("CZK", "Kč", "чешская крона"),
+    entry("HUF", "Ft", "венгерский форинт"),
+    entry("RON", "lei", "румынский лей"),
+    entry("BGN", "лв", "болгарский лев"),
+    entry("RSD", "дин", "сербский динар"),
+    entry("TRY", "₺", "турецкая лира"),
+    entry("AED", "د.إ", "дирхам ОАЭ"),
+    entry("QAR", "ر.ق", "катарский риал"),
+    entry("EGP", "E£", "египетский фунт"),
+    entry("THB", "฿", "тайский бат"),
+    whole("VND", "₫", "вьетнамский донг"),
+    entry("IDR", "Rp", "индонезийская рупия"),
+    entry("KZT", "₸", "казахстанский тенге"),
+    entry("BYN", "Br", "белорусский рубль"),
+    entry("UAH", "₴", "украинская гривна"),
+    entry("AMD", "֏", "армянский драм"),
+    entry("AZN", "₼", "азербайджанский манат"),
+    entry("GEL", "₾", "грузинский лари"),
+    entry("KGS", "сом", "киргизский сом"),
+    entry("MDL", "L", "молдавский лей"),
+    entry("TJS", "SM", "таджикский сомони"),
+    entry("TMT", "m", "туркменский манат"),
+    entry("UZS", "сўм", "узбекский сум"),
+    // Остальные действующие валюты по алфавиту
+    entry("AFN", "؋", "афганский афгани"),
+    entry("ALL", "L", "албанский лек"),
+    entry("ANG", "ƒ", "нидерландский антильский гульден"),
+    entry("AOA", "Kz", "ангольская кванза"),
+    entry("ARS", "AR$", "аргентинское песо"),
+    entry("AWG", "ƒ", "арубанский флорин"),
+    entry("BAM", "KM", "конвертируемая марка Боснии и Герцеговины"),
+    entry("BBD", "Bds$", "барбадосский доллар"),
+    entry("BDT", "৳", "бангладешская така"),
+    thousandths("BHD", "BD", "бахрейнский динар"),
+    whole("BIF", "FBu", "бурундийский франк"),
+    entry("BMD", "BD$", "бермудский доллар"),
+    entry("BND", "B$", "брунейский доллар"),
+    entry("BOB", "Bs", "боливийский боливиано"),
+    entry("BSD", "B$", "багамский доллар"),
+    entry("BTN", "Nu", "бутанский нгултрум"),
+    entry("BWP", "P", "ботсванская пула"),
+    entry("BZD", "BZ$", "белизский доллар"),
+    entry("CDF", "FC", "конголезский франк"),
+    whole("CLP", "CLP$", "чилийское песо"),
+    entry("COP", "COL$", "колумбийское песо"),
+    entry("CRC", "₡", "костариканский колон"),
+    entry("CUP", "₱", "кубинское песо"),
+    entry("CVE", "Esc", "эскудо Кабо-Верде"),
+    whole("DJF", "Fdj", "франк Джибути"),
+    entry("DOP", "RD$", "доминиканское песо"),
+    entry("DZD", "DA", "алжирский динар"),
+    entry("ERN", "Nfk", "эритрейская накфа"),
+    entry("ETB", "Br", "эфиопский быр"),
+    entry("FJD", "FJ$", "доллар Фиджи"),
+    entry("FKP", "FK£", "фунт Фолклендских островов"),
+    entry("GHS", "₵", "ганский седи"),
+    entry("GIP", "£", "гибралтарский фунт"),
+    entry("GMD", "D", "гамбийский даласи"),
+    whole("GNF", "FG", "гвинейский франк"),
+    entry("GTQ", "Q", "гватемальский кетсаль"),
+    entry("GYD", "G$", "гайанский доллар"),
+    entry("HNL", "L", "гондурасская лемпира"),
+    entry("HTG", "G", "гаитянский гурд"),
+    entry("ILS", "₪", "израильский шекель"),
+    thousandths("IQD", "ع.د", "иракский динар"),
+    entry("IRR", "﷼", "иранский риал"),
+    whole("ISK", "kr", "исландская крона"),
+    entry("JMD", "J$", "ямайский доллар"),
+    thousandths("JOD", "JD", "иорданский динар"),
+    entry("KES", "KSh", "кенийский шиллинг"),
+    entry("KHR", "៛", "камбоджийский риель"),
+    whole("KMF", "CF", "коморский франк"),
+    entry("KPW", "₩", "северокорейская вона"),
+    thousandths("KWD", "KD", "кувейтский динар"),
+    entry("KYD", "CI$", "доллар Островов Кайман"),
+    entry("LAK", "₭", "лаосский кип"),
+    entry("LBP", "ل.ل", "ливанский фунт"),
+    entry("LKR", "Rs", "шри-ланкийская рупия"),
+    entry("LRD", "L$", "либерийский доллар"),
+    entry("LSL", "L", "лоти Лесото"),
+    thousandths("LYD", "LD", "ливийский динар"),
+    entry("MAD", "DH", "марокканский дирхам"),
+    entry("MGA", "Ar", "малагасийский ариари"),
+    entry("MKD", "ден", "македонский денар"),
+    entry("MMK", "K", "мьянманский кьят"),
+    entry("MNT", "₮", "монгольский тугрик"),
+    entry("MOP", "MOP$", "патака Макао"),
+    entry("MRU", "UM", "мавританская угия"),
+    entry("MUR", "Rs", "маврикийская рупия"),
+    entry("MVR", "Rf", "мальдивская руфия"),
+    entry("MWK", "MK", "малавийская квача"),
+    entry("MXN", "MX$", "мексиканское песо"),
+    entry("MYR", "RM", "малайзийский ринггит"),
+    entry("MZN", "MT", "мозамбикский метикал"),
+    entry("NAD", "N$", "намибийский доллар"),
+    entry("NGN", "₦", "нигерийская найра"),
+    entry("NIO", "C$", "никарагуанская кордоба"),
+    entry("NPR", "Rs", "непальская рупия"),
+    thousandths("OMR", "ر.ع.", "оманский риал"),
+    entry("PAB", "B/.", "панамский бальбоа"),
+    entry("PEN", "S/", "перуанский соль"),
+    entry("PGK", "K", "кина Папуа — Новой Гвинеи"),
+    entry("PHP", "₱", "филиппинское песо"),
+    entry("PKR", "Rs", "пакистанская рупия"),
+    whole("PYG", "₲", "парагвайский гуарани"),
+    whole("RWF", "FRw", "франк Руанды"),
+    entry("SAR", "ر.س", "саудовский риял"),
+    entry("SBD", "SI$", "доллар Соломоновых Островов"),
+    entry("SCR", "SR", "сейшельская рупия"),
+    entry("SDG", "SDG", "суданский фунт"),
+    entry("SHP", "£", "фунт Святой Елены"),
+    entry("SLE", "Le", "леоне Сьерра-Леоне"),
+    entry("SOS", "Sh", "сомалийский шиллинг"),
+    entry("SRD", "Sr$", "суринамский доллар"),
+    entry("SSP", "SS£", "южносуданский фунт"),
+    entry("STN", "Db", "добра Сан-Томе и Принсипи"),
+    entry("SVC", "₡", "сальвадорский колон"),
+    entry("SYP", "£S", "сирийский фунт"),
+    entry("SZL", "E", "лилангени Эсватини"),
+    thousandths("TND", "DT", "тунисский динар"),
+    entry("TOP", "T$", "тонганская паанга"),
+    entry("TTD", "TT$", "доллар Тринидада и Тобаго"),
+    entry("TWD", "NT$", "новый тайваньский доллар"),
+    entry("TZS", "TSh", "танзанийский шиллинг"),
+    whole("UGX", "USh", "угандийский шиллинг"),
+    entry("UYU", "$U", "уругвайское песо"),
+    entry("VES", "Bs.S", "венесуэльский боливар"),
+    whole("VUV", "VT", "вату Вануату"),
+    entry("WST", "WS$", "самоанская тала"),
+    whole("XAF", "FCFA", "франк КФА BEAC"),
+    entry("XCD", "EC$", "восточнокарибский доллар"),
+    entry("XCG", "Cg", "карибский гульден"),
+    whole("XOF", "CFA", "франк КФА BCEAO"),
+    whole("XPF", "F", "франк КФП"),
+    entry("YER", "﷼", "йеменский риал"),
+    entry("ZMW", "ZK", "замбийская квача"),
+    entry("ZWG", "ZiG", "зимбабвийский золотой"),
+];
+
+/// Запись таблицы по коду (без учёта регистра)
+pub fn lookup(code: &str) -> Option<CurrencyInfo> {
+    CURRENCIES.iter().copied().find(|c| c.code.eq_ignore_ascii_case(code))
+}
+
+/// Символ валюты; для неизвестных кодов — сам код
+pub fn symbol(code: &str) -> &str {
+    lookup(code).map_or(code, |c| c.symbol)
+}
+
+/// Знаков после запятой; для неизвестных кодов — 2
+pub fn decimals(code: &str) -> u32 {
+    lookup(code).map_or(2, |c| c.decimals)
+}
+
+/// Сумма, округлённая до минимальной единицы валюты (половина — от нуля)
+pub fn round(amount: f64, code: &str) -> f64 {
+    let factor = 10f64.powi(decimals(code) as i32);
+    // Поправка на двоичное представление: 2.675 хранится как 2.67499999…
+    let scaled = amount * factor;
+    let nudged = scaled + scaled.signum() * scaled.abs() * f64::EPSILON * 4.0;
+    nudged.round() / factor
+}
+
+/// Сумма с разделителями разрядов и символом: "1 234.50 €", "15 000 ¥"
+pub fn format_amount(amount: f64, code: &str) -> String {
+    let decimals = decimals(code) as usize;
+    let text = format!("{:.*}", decimals, round(amount, code).abs());
+    let (integer, fraction) = text.split_once('.').unwrap_or((&text, ""));
+
+    let mut grouped = String::new();
+    for (i, digit) in integer.chars().enumerate() {
+        if i > 0 && (integer.len() - i) % 3 == 0 {
+            grouped.push(' ');
+        }
+        grouped.push(digit);
+    }
+    let sign = if amount < 0.0 && round(amount, code) != 0.0 { "-" } else { "" };
+    if fraction.is_empty() {
+        format!("{}{} {}", sign, grouped, symbol(code))
+    } else {
+        format!("{}{}.{} {}", sign, grouped, fraction, symbol(code))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table_has_every_currency_once() {
+        let mut codes: Vec<&str> = CURRENCIES.iter().map(|c| c.code).collect();
+        assert!(codes.iter().all(|c| c.len() == 3 && c.bytes().all(|b| b.is_ascii_uppercase())));
+        codes.sort_unstable();
+        codes.dedup();
+        assert_eq!(codes.len(), CURRENCIES.len());
+        assert!(CURRENCIES.len() > 150);
+        assert_eq!(lookup("mxn").map(|c| c.name), Some("мексиканское песо"));
+    }
+
+    #[test]
+    fn amounts_are_rounded_to_the_minor_unit() {
+        assert_eq!(round(2.675, "USD"), 2.68);
+        assert_eq!(round(-2.675, "USD"), -2.68);
+        assert_eq!(round(1234.5, "JPY"), 1235.0);
+        assert_eq!(round(1.2345, "KWD"), 1.235);
+        assert_eq!(format_amount(1234.5, "EUR"), "1 234.50 €");
+        assert_eq!(format_amount(15000.0, "JPY"), "15 000 ¥");
+        assert_eq!(format_amount(-0.001, "USD"), "0.00 $");
+        assert_eq!(format_amount(1.5, "KWD"), "1.500 KD");
+        assert_eq!(symbol("XYZ"), "XYZ");
+    }
+}
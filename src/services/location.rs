@@ -0,0 +1,229 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use super::cache::{DiskCache, GEOCODING_TTL};
+use crate::config::Config;
+
+/// Файл с сохранёнными местами в каталоге профиля
+pub const PLACES_FILE: &str = "places.json";
+/// Сколько вариантов геокодинга показывать при поиске
+const SEARCH_RESULTS: usize = 10;
+
+/// Место для погоды: название и координаты
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Place {
+    pub name: String,
+    pub admin1: Option<String>,
+    pub country: Option<String>,
+    pub latitude: f32,
+    pub longitude: f32,
+}
+
+impl Place {
+    /// "Самара, Самарская область, Россия"
+    pub fn display_name(&self) -> String {
+        let mut parts = vec![self.name.as_str()];
+        parts.extend(self.admin1.as_deref().filter(|a| *a != self.name));
+        parts.extend(self.country.as_deref());
+        parts.join(", ")
+    }
+}
+
+/// Избранные места профиля; `default` — индекс места для виджета и агента
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SavedPlaces {
+    pub places: Vec<Place>,
+    pub default: Option<usize>,
+}
+
+impl SavedPlaces {
+    pub fn load(path: &Path) -> Self {
+        match std::fs::read_to_string(path) {
+            Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                log::warn!("⚠️ Не удалось прочитать {}: {}", path.display(), e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn default_place(&self) -> Option<&Place> {
+        self.default.and_then(|i| self.places.get(i))
+    }
+
+    /// Добавляет место (повторно не добавляет); первое место становится основным.
+    /// Возвращает индекс места в списке
+    pub fn add(&mut self, place: Place) -> usize {
+        let index = match self.places.iter().position(|p| *p == place) {
+            Some(index) => index,
+            None => {
+                self.places.push(place);
+                self.places.len() - 1
+            }
+        };
+        self.default.get_or_insert(index);
+        index
+    }
+
+    pub fn set_default(&mut self, index: usize) -> Result<&Place> {
+        let place = self.places.get(index).ok_or_else(|| no_place(index))?;
+        self.default = Some(index);
+        Ok(place)
+    }
+
+    pub fn remove(&mut self, index: usize) -> Result<Place> {
+        if index >= self.places.len() {
+            return Err(no_place(index));
+        }
+        let place = self.places.remove(index);
+        self.default = match self.default {
+            Some(d) if d == index => (!self.places.is_empty()).then_some(0),
+            Some(d) if d > index => Some(d - 1),
+            other => other,
+        };
+        Ok(place)
+    }
+}
+
+fn no_place(index: usize) -> anyhow::Error {
+    anyhow::anyhow!("Места с номером {} нет в списке /places", index + 1)
+}
+
+// Geocoding response for city coordinates
+#[derive(Debug, Deserialize)]
+struct GeocodingResponse {
+    results: Option<Vec<GeocodingResult>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GeocodingResult {
+    latitude: f32,
+    longitude: f32,
+    name: String,
+    admin1: Option<String>,
+    country: Option<String>,
+}
+
+/// Определение места для погоды. Приоритет: основное сохранённое место (`/place`),
+/// координаты из CLIPPY_WEATHER_LAT/LON, город из CLIPPY_WEATHER_CITY,
+/// город системного часового пояса
+pub struct LocationService {
+    http_client: reqwest::Client,
+    cache: DiskCache,
+    places_file: PathBuf,
+    coordinates: Option<(f32, f32)>,
+    city: Option<String>,
+}
+
+impl LocationService {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            http_client: reqwest::Client::new(),
+            cache: DiskCache::from_config(config),
+            places_file: config.data_dir().join(PLACES_FILE),
+            coordinates: config.weather_coordinates,
+            city: config.weather_city.clone(),
+        }
+    }
+
+    /// Все варианты геокодинга для названия (с регионом и страной)
+    pub async fn search(&self, query: &str) -> Result<Vec<Place>> {
+        let key = format!("geocoding:{}", query.trim().to_lowercase());
+        let found = self
+            .cache
+            .get_or_fetch(&key, GEOCODING_TTL, || self.fetch_places(query))
+            .await?;
+        Ok(found.value)
+    }
+
+    async fn fetch_places(&self, query: &str) -> Result<Vec<Place>> {
+        let url = format!(
+            "https://geocoding-api.open-meteo.com/v1/search?name={}&count={}&language=ru&format=json",
+            urlencoding::encode(query.trim()),
+            SEARCH_RESULTS
+        );
+
+        let response = self.http_client.get(&url).send().await?;
+        let geo_response: GeocodingResponse = response.json().await?;
+
+        Ok(geo_response
+            .results
+            .unwrap_or_default()
+            .into_iter()
+            .map(|r| Place {
+                name: r.name,
+                admin1: r.admin1,
+                country: r.country,
+                latitude: r.latitude,
+                longitude: r.longitude,
+            })
+            .collect())
+    }
+
+    /// Место для виджета и ответов агента
+    pub async fn current_place(&self) -> Result<Place> {
+        if let Some(place) = self.saved().default_place() {
+            return Ok(place.clone());
+        }
+
+        if let Some((latitude, longitude)) = self.coordinates {
+            return Ok(Place {
+                name: self
+                    .city
+                    .clone()
+                    .unwrap_or_else(|| format!("{:.2}, {:.2}", latitude, longitude)),
+                admin1: None,
+                country: None,
+                latitude,
+                longitude,
+            });
+        }
+
+        let city = match &self.city {
+            Some(city) => city.clone(),
+            None => timezone_city().unwrap_or_else(|| "Москва".to_string()),
+        };
+        self.search(&city)
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("Город '{}' не найден", city))
+    }
+
+    pub fn saved(&self) -> SavedPlaces {
+        SavedPlaces::load(&self.places_file)
+    }
+
+    pub fn store(&self, places: &SavedPlaces) -> Result<()> {
+        places.save(&self.places_file)
+    }
+}
+
+/// Город из системного часового пояса: TZ или ссылка /etc/localtime
+/// ("Europe/Moscow" → "Moscow", "America/New_York" → "New York")
+fn timezone_city() -> Option<String> {
+    let zone = std::env::var("TZ")
+        .ok()
+        .map(|tz| tz.trim_start_matches(':').to_string())
+        .or_else(|| {
+            std::fs::read_link("/etc/localtime")
+                .ok()
+                .and_then(|target| target.to_str().map(str::to_string))
+        })?;
+
+    let city = zone.rsplit_once("zoneinfo/").map_or(zone.as_str(), |(_, z)| z);
+    let (region, city) = city.rsplit_once('/')?;
+    if region.is_empty() || region == "Etc" {
+        return None;
+    }
+    Some(city.replace('_', " "))
+}
@@ -1,7 +1,35 @@
 pub mod weather;
+pub mod wmo;
+pub mod weather_alerts;
+pub mod location;
+pub mod cache;
+pub mod air_quality;
 pub mod currency;
+pub mod iso4217;
+pub mod crypto_prices;
+pub mod stocks;
+pub mod rate_alerts;
+pub mod rate_store;
 pub mod storage;
+pub mod storage_actor;
+pub mod store;
+pub mod import;
+pub mod crypto;
+pub mod feedback;
+pub mod backup;
+pub mod attachment;
 
-pub use weather::{WeatherService, WeatherInfo};
-pub use currency::{CurrencyService, ExchangeRate};
+pub use weather::{Forecast, WeatherService, WeatherInfo};
+pub use weather_alerts::{WeatherAlert, WeatherAlerts};
+pub use location::{LocationService, Place};
+pub use air_quality::{AirQuality, AirQualityService};
+pub use currency::{CurrencyService, ExchangeRate, RatePoint};
+pub use crypto_prices::{CryptoQuote, CryptoService};
+pub use stocks::{StockQuote, StocksService};
+pub use rate_alerts::{RateAlert, RateAlerts};
 pub use storage::SQLiteStorage;
+pub use storage_actor::StorageHandle;
+pub use store::{ConversationStore, InMemoryStore};
+pub use import::ImportSource;
+pub use feedback::{Feedback, FeedbackAction};
+pub use backup::BackupManager;
@@ -0,0 +1,484 @@
+use anyhow::Result;
+use chrono::{Local, NaiveDate};
+
+use super::currency::{self, CurrencyService, ExchangeRate};
+use super::iso4217;
+use super::stocks::{self, StocksService};
+use super::storage_actor::StorageHandle;
+use crate::config::Config;
+
+/// За сколько дней читать историю курса для изменения за день (с запасом на выходные)
+const CHANGE_HISTORY_DAYS: i64 = 7;
+
+const RULE_EXAMPLES: &str = "/alert USD > 100, /alert EUR/CNY < 7.5, /alert EUR 2%, /alert SBER < 250";
+
+/// Что отслеживает правило
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertTarget {
+    /// Курс валюты к другой валюте
+    Currency,
+    /// Цена акции Мосбиржи, ₽
+    Stock,
+}
+
+impl AlertTarget {
+    pub fn id(&self) -> &'static str {
+        match self {
+            Self::Currency => "currency",
+            Self::Stock => "stock",
+        }
+    }
+
+    pub fn parse(id: &str) -> Option<Self> {
+        match id {
+            "currency" => Some(Self::Currency),
+            "stock" => Some(Self::Stock),
+            _ => None,
+        }
+    }
+}
+
+/// Условие срабатывания правила
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AlertCondition {
+    /// Значение поднялось до порога или выше
+    Above(f64),
+    /// Значение опустилось до порога или ниже
+    Below(f64),
+    /// Изменение за день по модулю не меньше, %
+    Change(f64),
+}
+
+impl AlertCondition {
+    pub fn id(&self) -> &'static str {
+        match self {
+            Self::Above(_) => "above",
+            Self::Below(_) => "below",
+            Self::Change(_) => "change",
+        }
+    }
+
+    pub fn threshold(&self) -> f64 {
+        match self {
+            Self::Above(value) | Self::Below(value) | Self::Change(value) => *value,
+        }
+    }
+
+    pub fn from_parts(id: &str, threshold: f64) -> Option<Self> {
+        match id {
+            "above" => Some(Self::Above(threshold)),
+            "below" => Some(Self::Below(threshold)),
+            "change" => Some(Self::Change(threshold)),
+            _ => None,
+        }
+    }
+}
+
+/// Правило уведомления о курсе: "USD/RUB выше 100", "EUR изменился за день на 2%"
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateRule {
+    pub target: AlertTarget,
+    /// Код валюты или тикер акции
+    pub symbol: String,
+    /// В какой валюте значение (для акций — всегда RUB)
+    pub base: String,
+    pub condition: AlertCondition,
+}
+
+impl RateRule {
+    /// Правило из текста команды: "USD > 100", "USD/RUB ниже 90", "EUR 2%", "SBER < 250".
+    /// Курс валюты без пары считается в `base`
+    pub fn parse(input: &str, base: &str) -> Result<Self> {
+        let mut spaced = String::with_capacity(input.len());
+        for c in input.chars() {
+            if matches!(c, '>' | '<' | '%' | '±') {
+                spaced.extend([' ', c, ' ']);
+            } else {
+                spaced.push(c);
+            }
+        }
+        let mut tokens = spaced.split_whitespace();
+        let Some(pair) = tokens.next() else {
+            return Err(anyhow::anyhow!("Укажи правило, например: {}", RULE_EXAMPLES));
+        };
+
+        let mut direction = None;
+        let mut percent = false;
+        let mut value = None;
+        for token in tokens {
+            match token.to_lowercase().as_str() {
+                ">" | "выше" | "больше" | "above" => direction = Some(true),
+                "<" | "ниже" | "меньше" | "below" => direction = Some(false),
+                "%" => percent = true,
+                "±" | "+-" | "на" => {}
+                other => match other.trim_start_matches('+').replace(',', ".").parse::<f64>() {
+                    Ok(number) if value.is_none() => value = Some(number),
+                    _ => return Err(anyhow::anyhow!("Не понял «{}». Примеры: {}", token, RULE_EXAMPLES)),
+                },
+            }
+        }
+
+        let Some(value) = value.filter(|v| v.is_finite() && *v > 0.0) else {
+            return Err(anyhow::anyhow!("Укажи порог больше нуля. Примеры: {}", RULE_EXAMPLES));
+        };
+        let condition = match (percent, direction) {
+            (true, _) if value >= 100.0 => {
+                return Err(anyhow::anyhow!("Изменение за день должно быть меньше 100%"));
+            }
+            (true, _) => AlertCondition::Change(value),
+            (false, Some(true)) => AlertCondition::Above(value),
+            (false, Some(false)) => AlertCondition::Below(value),
+            (false, None) => {
+                return Err(anyhow::anyhow!("Укажи направление: > или <. Примеры: {}", RULE_EXAMPLES));
+            }
+        };
+
+        let pair = pair.to_uppercase();
+        let (symbol, quote) = match pair.split_once('/') {
+            Some((symbol, quote)) => (symbol.to_string(), Some(quote.to_string())),
+            None => (pair.clone(), None),
+        };
+
+        if iso4217::lookup(&symbol).is_some() {
+            let base = quote.unwrap_or_else(|| base.to_string());
+            if iso4217::lookup(&base).is_none() {
+                return Err(anyhow::anyhow!("Неизвестная валюта {}", base));
+            }
+            if base == symbol {
+                return Err(anyhow::anyhow!("Курс {} к самой себе не меняется", symbol));
+            }
+            return Ok(Self {
+                target: AlertTarget::Currency,
+                symbol,
+                base,
+                condition,
+            });
+        }
+
+        if stocks::stock_name(&symbol).is_none() {
+            let known: Vec<&str> = stocks::KNOWN_STOCKS.iter().map(|(ticker, _)| *ticker).collect();
+            return Err(anyhow::anyhow!(
+                "Неизвестная валюта или тикер {}. Акции: {}",
+                symbol,
+                known.join(", ")
+            ));
+        }
+        if quote.as_deref().is_some_and(|q| q != "RUB") {
+            return Err(anyhow::anyhow!("Акции Мосбиржи торгуются только в рублях"));
+        }
+        Ok(Self {
+            target: AlertTarget::Stock,
+            symbol,
+            base: "RUB".to_string(),
+            condition,
+        })
+    }
+
+    /// "USD/RUB" или "Сбербанк (SBER)"
+    pub fn label(&self) -> String {
+        match self.target {
+            AlertTarget::Currency => format!("{}/{}", self.symbol, self.base),
+            AlertTarget::Stock => match stocks::stock_name(&self.symbol) {
+                Some(name) => format!("{} ({})", name, self.symbol),
+                None => self.symbol.clone(),
+            },
+        }
+    }
+
+    /// Условие для списка правил: "USD/RUB выше 100.00 ₽"
+    pub fn describe(&self) -> String {
+        match self.condition {
+            AlertCondition::Above(value) => format!("{} выше {}", self.label(), self.format_value(value)),
+            AlertCondition::Below(value) => format!("{} ниже {}", self.label(), self.format_value(value)),
+            AlertCondition::Change(percent) => format!("{} изменится за день на {}% и больше", self.label(), percent),
+        }
+    }
+
+    /// Курсы меньше 10 — с большей точностью: "0.6123 ₽", "92.50 ₽"
+    fn format_value(&self, value: f64) -> String {
+        if value < 10.0 {
+            format!("{:.4} {}", value, iso4217::symbol(&self.base))
+        } else {
+            iso4217::format_amount(value, &self.base)
+        }
+    }
+}
+
+/// Правило из БД вместе с состоянием последней проверки
+#[derive(Debug, Clone, PartialEq)]
+pub struct SavedRateRule {
+    pub id: i64,
+    pub rule: RateRule,
+    /// Значение при прошлой проверке: порог срабатывает один раз при пересечении
+    pub last_value: Option<f64>,
+    /// Дата курса, на которой правило сработало в последний раз
+    pub fired_on: Option<NaiveDate>,
+}
+
+/// Сработавшее правило: текст для облачка и состояние, которое сохраняется при показе.
+/// Пока уведомление не показано, правило срабатывает при каждой проверке
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateAlert {
+    pub rule_id: i64,
+    pub text: String,
+    /// Значение и дата курса, на которых правило сработало
+    pub value: f64,
+    pub date: NaiveDate,
+}
+
+impl RateAlert {
+    /// Запоминает срабатывание правила, когда уведомление показано
+    pub async fn mark_shown(&self, storage: &StorageHandle) -> Result<()> {
+        let (id, value, date) = (self.rule_id, self.value, self.date);
+        storage.call(move |s| s.rates_mut().update_rate_rule(id, value, Some(date))).await
+    }
+}
+
+/// Значение, с которым сравнивается правило
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Observation {
+    pub value: f64,
+    /// Изменение за день, %
+    pub change_percent: Option<f64>,
+    /// Дата курса (для акций — сегодня)
+    pub date: NaiveDate,
+}
+
+impl SavedRateRule {
+    /// Текст уведомления, если правило сработало. Порог срабатывает при пересечении
+    /// (и при первой проверке, если он уже пройден), изменение — раз на дату курса
+    pub fn evaluate(&self, observation: &Observation) -> Option<String> {
+        let rule = &self.rule;
+        let now = rule.format_value(observation.value);
+        match rule.condition {
+            AlertCondition::Above(threshold) => {
+                if observation.value < threshold {
+                    return None;
+                }
+                Some(match self.last_value {
+                    None => format!("📈 {} уже выше {}: сейчас {}", rule.label(), rule.format_value(threshold), now),
+                    Some(last) if last < threshold => {
+                        format!("📈 {} поднялся выше {}: сейчас {}", rule.label(), rule.format_value(threshold), now)
+                    }
+                    Some(_) => return None,
+                })
+            }
+            AlertCondition::Below(threshold) => {
+                if observation.value > threshold {
+                    return None;
+                }
+                Some(match self.last_value {
+                    None => format!("📉 {} уже ниже {}: сейчас {}", rule.label(), rule.format_value(threshold), now),
+                    Some(last) if last > threshold => {
+                        format!("📉 {} опустился ниже {}: сейчас {}", rule.label(), rule.format_value(threshold), now)
+                    }
+                    Some(_) => return None,
+                })
+            }
+            AlertCondition::Change(percent) => {
+                let change = observation.change_percent?;
+                if change.abs() < percent || self.fired_on == Some(observation.date) {
+                    return None;
+                }
+                let icon = if change >= 0.0 { "📈" } else { "📉" };
+                Some(format!("{} {} за день {:+.2}%: сейчас {}", icon, rule.label(), change, now))
+            }
+        }
+    }
+}
+
+/// Проверка правил уведомлений о курсах при обновлении курсов валют
+pub struct RateAlerts {
+    stocks: StocksService,
+}
+
+impl RateAlerts {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            stocks: StocksService::from_config(config),
+        }
+    }
+
+    /// Проверяет правила по свежим курсам `rates` (к валюте `base`) и котировкам акций,
+    /// запоминает значения несработавших правил и возвращает сработавшие для облачка.
+    /// Ошибки отдельных правил пишутся в лог и не мешают проверке остальных
+    pub async fn check(
+        &self,
+        storage: &StorageHandle,
+        currency: &CurrencyService,
+        rates: &[ExchangeRate],
+        base: &str,
+    ) -> Vec<RateAlert> {
+        let rules = match storage.read(|s| s.rates().rate_rules()).await {
+            Ok(rules) => rules,
+            Err(e) => {
+                log::warn!("⚠️ Ошибка чтения уведомлений о курсах: {}", e);
+                return Vec::new();
+            }
+        };
+        if rules.is_empty() {
+            return Vec::new();
+        }
+
+        let mut tickers: Vec<String> = Vec::new();
+        for saved in rules.iter().filter(|s| s.rule.target == AlertTarget::Stock) {
+            if !tickers.contains(&saved.rule.symbol) {
+                tickers.push(saved.rule.symbol.clone());
+            }
+        }
+        let quotes = if tickers.is_empty() {
+            Vec::new()
+        } else {
+            match self.stocks.get_quotes_for(&tickers).await {
+                Ok(quotes) if !quotes.stale => quotes.value,
+                // По устаревшим котировкам не уведомляем
+                Ok(_) => Vec::new(),
+                Err(e) => {
+                    log::warn!("⚠️ Не удалось получить котировки для уведомлений: {}", e);
+                    Vec::new()
+                }
+            }
+        };
+
+        let today = Local::now().date_naive();
+        let mut alerts = Vec::new();
+        for saved in rules {
+            let rule = &saved.rule;
+            let observation = match rule.target {
+                AlertTarget::Currency => Self::observe_currency(storage, currency, rule, rates, base, today).await,
+                AlertTarget::Stock => quotes.iter().find(|q| q.ticker == rule.symbol).map(|q| Observation {
+                    value: q.price,
+                    change_percent: q.change_percent,
+                    date: today,
+                }),
+            };
+            let Some(observation) = observation else {
+                continue;
+            };
+
+            // Сработавшее правило сохраняется при показе облачка (RateAlert::mark_shown)
+            if let Some(text) = saved.evaluate(&observation) {
+                log::info!("🔔 Сработало уведомление о курсе: {}", text);
+                alerts.push(RateAlert {
+                    rule_id: saved.id,
+                    text,
+                    value: observation.value,
+                    date: observation.date,
+                });
+                continue;
+            }
+            let (id, value, fired_on) = (saved.id, observation.value, saved.fired_on);
+            if let Err(e) = storage.call(move |s| s.rates_mut().update_rate_rule(id, value, fired_on)).await {
+                log::warn!("⚠️ Ошибка сохранения состояния уведомления «{}»: {}", rule.describe(), e);
+            }
+        }
+        alerts
+    }
+
+    /// Курс пары правила через кросс-курс; изменение за день — по истории курсов ЦБ в БД
+    async fn observe_currency(
+        storage: &StorageHandle,
+        currency: &CurrencyService,
+        rule: &RateRule,
+        rates: &[ExchangeRate],
+        base: &str,
+        today: NaiveDate,
+    ) -> Option<Observation> {
+        let conversion = currency::convert_with(rates, base, 1.0, &rule.symbol, &rule.base).ok()?;
+        let mut observation = Observation {
+            value: conversion.rate,
+            change_percent: None,
+            date: conversion.date.unwrap_or(today),
+        };
+
+        if let AlertCondition::Change(_) = rule.condition {
+            let history = match currency.history(storage, &rule.symbol, &rule.base, CHANGE_HISTORY_DAYS).await {
+                Ok(history) => history,
+                Err(e) => {
+                    log::warn!("⚠️ Ошибка чтения истории курса {}: {}", rule.symbol, e);
+                    return None;
+                }
+            };
+            let [.., previous, last] = history.as_slice() else {
+                return None;
+            };
+            observation.change_percent = Some((last.rate / previous.rate - 1.0) * 100.0);
+            observation.date = last.date;
+        }
+        Some(observation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn saved(rule: &str, last_value: Option<f64>, fired_on: Option<NaiveDate>) -> SavedRateRule {
+        SavedRateRule {
+            id: 1,
+            rule: RateRule::parse(rule, "RUB").unwrap(),
+            last_value,
+            fired_on,
+        }
+    }
+
+    fn observation(value: f64, change_percent: Option<f64>) -> Observation {
+        Observation {
+            value,
+            change_percent,
+            date: NaiveDate::from_ymd_opt(2026, 10, 16).unwrap(),
+        }
+    }
+
+    #[test]
+    fn rules_are_parsed_from_commands() {
+        let rule = RateRule::parse("usd > 100", "RUB").unwrap();
+        assert_eq!(rule.target, AlertTarget::Currency);
+        assert_eq!((rule.symbol.as_str(), rule.base.as_str()), ("USD", "RUB"));
+        assert_eq!(rule.condition, AlertCondition::Above(100.0));
+
+        let pair = RateRule::parse("EUR/CNY ниже 7,5", "RUB").unwrap();
+        assert_eq!((pair.symbol.as_str(), pair.base.as_str()), ("EUR", "CNY"));
+        assert_eq!(pair.condition, AlertCondition::Below(7.5));
+
+        assert_eq!(RateRule::parse("EUR 2%", "USD").unwrap().condition, AlertCondition::Change(2.0));
+        assert_eq!(RateRule::parse("EUR ±2%", "USD").unwrap().base, "USD");
+
+        let stock = RateRule::parse("SBER < 250", "USD").unwrap();
+        assert_eq!(stock.target, AlertTarget::Stock);
+        assert_eq!(stock.base, "RUB");
+        assert_eq!(RateRule::parse("pikk > 100", "RUB").unwrap().symbol, "PIKK");
+    }
+
+    #[test]
+    fn broken_rules_are_rejected() {
+        for rule in ["", "USD 100", "USD > 0", "USD > сто", "USD > 1 2", "EUR 150%", "USD/RUB/EUR > 1", "RUB > 1", "SBER/USD > 1", "BTC > 100000", "USDD > 100"] {
+            assert!(RateRule::parse(rule, "RUB").is_err(), "{}", rule);
+        }
+    }
+
+    #[test]
+    fn thresholds_fire_once_on_crossing() {
+        let first = saved("USD > 100", None, None);
+        assert!(first.evaluate(&observation(101.0, None)).unwrap().contains("уже выше"));
+        assert!(first.evaluate(&observation(99.0, None)).is_none());
+
+        let crossed = saved("USD > 100", Some(99.0), None);
+        assert!(crossed.evaluate(&observation(100.0, None)).unwrap().contains("поднялся выше"));
+        assert!(saved("USD > 100", Some(100.5), None).evaluate(&observation(101.0, None)).is_none());
+
+        let below = saved("USD < 90", Some(91.0), None);
+        assert!(below.evaluate(&observation(89.5, None)).unwrap().contains("опустился ниже"));
+    }
+
+    #[test]
+    fn daily_change_fires_once_per_rate_date() {
+        let rule = saved("EUR 2%", None, None);
+        assert!(rule.evaluate(&observation(95.0, Some(-2.5))).unwrap().contains("-2.50%"));
+        assert!(rule.evaluate(&observation(95.0, Some(1.9))).is_none());
+        assert!(rule.evaluate(&observation(95.0, None)).is_none());
+
+        let fired = saved("EUR 2%", None, NaiveDate::from_ymd_opt(2026, 10, 16));
+        assert!(fired.evaluate(&observation(95.0, Some(3.0))).is_none());
+    }
+}
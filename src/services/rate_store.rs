@@ -0,0 +1,147 @@
+use anyhow::Result;
+use chrono::NaiveDate;
+use std::collections::{BTreeMap, HashMap};
+
+use super::currency::RatePoint;
+use super::rate_alerts::{RateRule, SavedRateRule};
+
+/// Хранилище курсов валют и правил уведомлений о курсах. Живёт рядом с историей
+/// разговоров (в той же БД), но к ней не относится: доступно через
+/// `ConversationStore::rates`
+pub trait RateStore: Send {
+    /// Сохраняет дневные курсы валют (курс на ту же дату перезаписывается)
+    fn save_rates(&mut self, points: &[RatePoint]) -> Result<()>;
+
+    /// История курса валюты начиная с `since`, от старых к новым
+    fn rate_history(&self, currency: &str, since: NaiveDate) -> Result<Vec<RatePoint>>;
+
+    /// По какую дату история курса уже запрошена у ЦБ. Выходные и праздники
+    /// без курса в истории не запрашиваются повторно
+    fn synced_until(&self, currency: &str) -> Result<Option<NaiveDate>>;
+
+    fn set_synced_until(&mut self, currency: &str, date: NaiveDate) -> Result<()>;
+
+    /// Добавляет правило уведомления о курсе, возвращает его id
+    fn add_rate_rule(&mut self, rule: &RateRule) -> Result<i64>;
+
+    /// Правила уведомлений о курсах в порядке добавления
+    fn rate_rules(&self) -> Result<Vec<SavedRateRule>>;
+
+    /// Удаляет правило; `false` если его не было
+    fn remove_rate_rule(&mut self, id: i64) -> Result<bool>;
+
+    /// Запоминает значение последней проверки правила и дату срабатывания
+    fn update_rate_rule(&mut self, id: i64, last_value: f64, fired_on: Option<NaiveDate>) -> Result<()>;
+}
+
+/// Курсы и правила в памяти процесса (для `InMemoryStore`)
+#[derive(Default)]
+pub struct InMemoryRates {
+    /// (валюта, дата) → курс
+    rates: BTreeMap<(String, NaiveDate), RatePoint>,
+    /// валюта → по какую дату запрошена история
+    synced: HashMap<String, NaiveDate>,
+    rules: Vec<SavedRateRule>,
+}
+
+impl RateStore for InMemoryRates {
+    fn save_rates(&mut self, points: &[RatePoint]) -> Result<()> {
+        for point in points {
+            self.rates.insert((point.currency.clone(), point.date), point.clone());
+        }
+        Ok(())
+    }
+
+    fn rate_history(&self, currency: &str, since: NaiveDate) -> Result<Vec<RatePoint>> {
+        Ok(self
+            .rates
+            .values()
+            .filter(|p| p.currency == currency && p.date >= since)
+            .cloned()
+            .collect())
+    }
+
+    fn synced_until(&self, currency: &str) -> Result<Option<NaiveDate>> {
+        Ok(self.synced.get(currency).copied())
+    }
+
+    fn set_synced_until(&mut self, currency: &str, date: NaiveDate) -> Result<()> {
+        self.synced.insert(currency.to_string(), date);
+        Ok(())
+    }
+
+    fn add_rate_rule(&mut self, rule: &RateRule) -> Result<i64> {
+        let id = self.rules.last().map_or(1, |r| r.id + 1);
+        self.rules.push(SavedRateRule {
+            id,
+            rule: rule.clone(),
+            last_value: None,
+            fired_on: None,
+        });
+        Ok(id)
+    }
+
+    fn rate_rules(&self) -> Result<Vec<SavedRateRule>> {
+        Ok(self.rules.clone())
+    }
+
+    fn remove_rate_rule(&mut self, id: i64) -> Result<bool> {
+        let before = self.rules.len();
+        self.rules.retain(|r| r.id != id);
+        Ok(self.rules.len() < before)
+    }
+
+    fn update_rate_rule(&mut self, id: i64, last_value: f64, fired_on: Option<NaiveDate>) -> Result<()> {
+        if let Some(saved) = self.rules.iter_mut().find(|r| r.id == id) {
+            saved.last_value = Some(last_value);
+            saved.fired_on = fired_on;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::currency::RateSource;
+    use crate::services::storage::SQLiteStorage;
+    use crate::services::store::ConversationStore;
+    use uuid::Uuid;
+
+    fn point(day: u32, rate: f64) -> RatePoint {
+        RatePoint {
+            currency: "USD".to_string(),
+            date: NaiveDate::from_ymd_opt(2026, 10, day).unwrap(),
+            rate,
+            source: RateSource::Cbr,
+        }
+    }
+
+    fn roundtrip(rates: &mut dyn RateStore) {
+        let since = NaiveDate::from_ymd_opt(2026, 10, 1).unwrap();
+        rates.save_rates(&[point(9, 81.2345678), point(10, 81.5)]).unwrap();
+        rates.save_rates(&[point(10, 81.7)]).unwrap();
+
+        let history = rates.rate_history("USD", since).unwrap();
+        assert_eq!(history.iter().map(|p| p.rate).collect::<Vec<_>>(), vec![81.2345678, 81.7]);
+        assert!(rates.rate_history("EUR", since).unwrap().is_empty());
+
+        assert_eq!(rates.synced_until("USD").unwrap(), None);
+        let sunday = NaiveDate::from_ymd_opt(2026, 10, 11).unwrap();
+        rates.set_synced_until("USD", sunday).unwrap();
+        assert_eq!(rates.synced_until("USD").unwrap(), Some(sunday));
+    }
+
+    #[test]
+    fn in_memory_rates_keep_precision_and_sync_mark() {
+        roundtrip(&mut InMemoryRates::default());
+    }
+
+    #[test]
+    fn sqlite_rates_keep_precision_and_sync_mark() {
+        let dir = std::env::temp_dir().join(format!("clippy-test-{}", Uuid::new_v4()));
+        let mut storage = SQLiteStorage::new(Some(dir.join("clippy.db"))).unwrap();
+        roundtrip(storage.rates_mut());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
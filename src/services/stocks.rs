@@ -0,0 +1,268 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::cache::{Cached, DiskCache, STOCKS_TTL};
+use super::iso4217;
+use crate::config::Config;
+
+/// ISS Московской биржи по умолчанию; CLIPPY_MOEX_API_URL подменяет его, например, на мок
+pub const DEFAULT_MOEX_API_URL: &str = "https://iss.moex.com/iss";
+/// Режим торгов акциями Т+ (основной режим для акций)
+const BOARD: &str = "TQBR";
+
+/// Известные акции: тикер и название для ответов
+pub const KNOWN_STOCKS: &[(&str, &str)] = &[
+    ("SBER", "Сбербанк"),
+    ("GAZP", "Газпром"),
+    ("LKOH", "Лукойл"),
+    ("ROSN", "Роснефть"),
+    ("GMKN", "Норникель"),
+    ("YDEX", "Яндекс"),
+    ("VTBR", "ВТБ"),
+    ("NVTK", "Новатэк"),
+    ("MGNT", "Магнит"),
+    ("PLZL", "Полюс"),
+    ("TATN", "Татнефть"),
+    ("MTSS", "МТС"),
+    ("AFLT", "Аэрофлот"),
+    ("ALRS", "Алроса"),
+    ("CHMF", "Северсталь"),
+    ("NLMK", "НЛМК"),
+    ("MOEX", "Московская биржа"),
+    ("SNGS", "Сургутнефтегаз"),
+    ("SIBN", "Газпром нефть"),
+    ("OZON", "Озон"),
+    ("PHOR", "ФосАгро"),
+    ("RUAL", "Русал"),
+    ("IRAO", "Интер РАО"),
+    ("PIKK", "ПИК"),
+    ("HYDR", "РусГидро"),
+];
+
+/// Котировка акции на основном режиме торгов
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StockQuote {
+    pub ticker: String,
+    pub name: String,
+    /// Последняя цена сделки, ₽; до начала торгов — цена закрытия прошлого дня
+    pub price: f64,
+    /// Изменение к закрытию прошлого дня, ₽ и %
+    pub change: Option<f64>,
+    pub change_percent: Option<f64>,
+    /// Идут ли сейчас торги
+    pub trading: bool,
+    /// Время последнего обновления на бирже (ЧЧ:ММ:СС)
+    pub update_time: Option<String>,
+}
+
+impl StockQuote {
+    pub fn format_price(&self) -> String {
+        if self.price < 1.0 {
+            format!("{:.4} ₽", self.price)
+        } else {
+            iso4217::format_amount(self.price, "RUB")
+        }
+    }
+
+    pub fn trading_label(&self) -> &'static str {
+        if self.trading {
+            "торги идут"
+        } else {
+            "торги закрыты"
+        }
+    }
+
+    /// Строка для ответа в чате
+    pub fn format(&self) -> String {
+        let mut text = format!("{} ({}): {}", self.name, self.ticker, self.format_price());
+        if let Some(percent) = self.change_percent {
+            let icon = if percent >= 0.0 { "📈" } else { "📉" };
+            text.push_str(&format!(", {} {:+.2}%", icon, percent));
+            if let Some(change) = self.change {
+                text.push_str(&format!(" ({:+.2} ₽)", change));
+            }
+            text.push_str(" за день");
+        }
+        text.push_str(&format!(", {}", self.trading_label()));
+        if let Some(time) = &self.update_time {
+            text.push_str(&format!(" · {}", time.get(..5).unwrap_or(time)));
+        }
+        text
+    }
+}
+
+/// Таблица ISS: названия колонок и строки значений
+#[derive(Debug, Default, Deserialize)]
+struct IssTable {
+    columns: Vec<String>,
+    data: Vec<Vec<serde_json::Value>>,
+}
+
+type IssRow<'a> = HashMap<&'a str, &'a serde_json::Value>;
+
+impl IssTable {
+    /// Строка бумаги `secid` как "колонка → значение" (пустая, если бумаги нет)
+    fn row(&self, secid: &str) -> IssRow<'_> {
+        let secid_column = self.columns.iter().position(|c| c == "SECID");
+        self.data
+            .iter()
+            .find(|row| secid_column.and_then(|i| row.get(i)).and_then(|v| v.as_str()) == Some(secid))
+            .map(|row| self.columns.iter().map(String::as_str).zip(row).collect())
+            .unwrap_or_default()
+    }
+}
+
+fn number(row: &IssRow, column: &str) -> Option<f64> {
+    row.get(column).and_then(|v| v.as_f64())
+}
+
+fn text(row: &IssRow, column: &str) -> Option<String> {
+    row.get(column).and_then(|v| v.as_str()).map(str::to_string)
+}
+
+#[derive(Debug, Deserialize)]
+struct IssResponse {
+    #[serde(default)]
+    securities: IssTable,
+    #[serde(default)]
+    marketdata: IssTable,
+}
+
+/// Котировки акций из ISS Московской биржи (`/engines/stock/markets/shares`)
+pub struct StocksService {
+    http_client: reqwest::Client,
+    cache: DiskCache,
+    endpoint: String,
+    /// Тикеры для виджетов (CLIPPY_STOCKS)
+    tickers: Vec<String>,
+}
+
+impl StocksService {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            http_client: reqwest::Client::new(),
+            cache: DiskCache::from_config(config),
+            endpoint: config.moex_api_url.trim_end_matches('/').to_string(),
+            tickers: config.stock_tickers.clone(),
+        }
+    }
+
+    pub fn tickers(&self) -> &[String] {
+        &self.tickers
+    }
+
+    /// Котировки акций для виджетов
+    pub async fn get_quotes(&self) -> Result<Cached<Vec<StockQuote>>> {
+        self.get_quotes_for(&self.tickers).await
+    }
+
+    /// Котировки в порядке `tickers` (из кэша, если они свежие; без сети — последние известные)
+    pub async fn get_quotes_for(&self, tickers: &[String]) -> Result<Cached<Vec<StockQuote>>> {
+        let key = format!("stocks:{}:{}", self.endpoint, tickers.join(","));
+        self.cache
+            .get_or_fetch(&key, STOCKS_TTL, || self.fetch_quotes(tickers))
+            .await
+    }
+
+    async fn fetch_quotes(&self, tickers: &[String]) -> Result<Vec<StockQuote>> {
+        let url = format!(
+            "{}/engines/stock/markets/shares/boards/{}/securities.json?securities={}&iss.meta=off\
+             &iss.only=securities,marketdata\
+             &securities.columns=SECID,SHORTNAME,PREVPRICE\
+             &marketdata.columns=SECID,LAST,CHANGE,LASTTOPREVPRICE,TRADINGSTATUS,UPDATETIME",
+            self.endpoint,
+            BOARD,
+            tickers.join(",")
+        );
+
+        let response = self.http_client.get(&url).send().await?;
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!("Московская биржа ответила {}", response.status()));
+        }
+        let quotes = parse_iss(&response.json::<IssResponse>().await?, tickers);
+        if quotes.is_empty() {
+            return Err(anyhow::anyhow!("Московская биржа не знает тикеров {}", tickers.join(", ")));
+        }
+        Ok(quotes)
+    }
+}
+
+/// Собирает котировки из таблиц securities (название, закрытие прошлого дня)
+/// и marketdata (последняя сделка, изменение, статус торгов)
+fn parse_iss(response: &IssResponse, tickers: &[String]) -> Vec<StockQuote> {
+    tickers
+        .iter()
+        .filter_map(|ticker| {
+            let security = response.securities.row(ticker);
+            let market = response.marketdata.row(ticker);
+            if security.is_empty() && market.is_empty() {
+                log::warn!("⚠️ Мосбиржа: нет данных по {}", ticker);
+                return None;
+            }
+            let last = number(&market, "LAST");
+            let price = last.or_else(|| number(&security, "PREVPRICE"))?;
+            let name = stock_name(ticker)
+                .map(str::to_string)
+                .or_else(|| text(&security, "SHORTNAME"))
+                .unwrap_or_else(|| ticker.clone());
+            Some(StockQuote {
+                ticker: ticker.clone(),
+                name,
+                price,
+                // Без сделок сегодня изменения нет
+                change: last.and(number(&market, "CHANGE")),
+                change_percent: last.and(number(&market, "LASTTOPREVPRICE")),
+                trading: text(&market, "TRADINGSTATUS").as_deref() == Some("T"),
+                update_time: text(&market, "UPDATETIME"),
+            })
+        })
+        .collect()
+}
+
+/// Название акции из списка известных
+pub fn stock_name(ticker: &str) -> Option<&'static str> {
+    KNOWN_STOCKS
+        .iter()
+        .find(|(known, _)| known.eq_ignore_ascii_case(ticker))
+        .map(|(_, name)| *name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ответ ISS в формате iss.meta=off: SBER торгуется, GAZP без сделок сегодня
+    const ISS_RESPONSE: &str = r#"{
+        "securities": {
+            "columns": ["SECID", "SHORTNAME", "PREVPRICE"],
+            "data": [["SBER", "Сбербанк", 305.1], ["GAZP", "ГАЗПРОМ ао", 130.5], ["XXXX", "Что-то", 1.0]]
+        },
+        "marketdata": {
+            "columns": ["SECID", "LAST", "CHANGE", "LASTTOPREVPRICE", "TRADINGSTATUS", "UPDATETIME"],
+            "data": [["SBER", 310.25, 5.15, 1.69, "T", "15:42:10"], ["GAZP", null, null, null, "N", "09:50:00"]]
+        }
+    }"#;
+
+    #[test]
+    fn iss_tables_are_joined_by_ticker() {
+        let response: IssResponse = serde_json::from_str(ISS_RESPONSE).unwrap();
+        let tickers = ["SBER", "GAZP", "NONE"].map(str::to_string);
+        let quotes = parse_iss(&response, &tickers);
+        assert_eq!(quotes.len(), 2);
+
+        let sber = &quotes[0];
+        assert_eq!((sber.ticker.as_str(), sber.name.as_str()), ("SBER", "Сбербанк"));
+        assert_eq!(sber.price, 310.25);
+        assert_eq!(sber.change_percent, Some(1.69));
+        assert!(sber.trading);
+        assert!(sber.format().ends_with("торги идут · 15:42"));
+
+        // Без сделок — цена закрытия и без изменения за день
+        let gazp = &quotes[1];
+        assert_eq!(gazp.name, "Газпром");
+        assert_eq!(gazp.price, 130.5);
+        assert_eq!(gazp.change, None);
+        assert!(!gazp.trading);
+    }
+}
@@ -1,10 +1,24 @@
 use anyhow::Result;
-use chrono::Local;
-use rusqlite::{Connection, params};
+use chrono::{Local, NaiveDate, Utc};
+use rusqlite::backup::Backup;
+use rusqlite::{Connection, OpenFlags, OptionalExtension, params};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use uuid::Uuid;
 
+use super::attachment::{self, Attachment, NewAttachment};
+use super::crypto::ContentCipher;
+use super::currency::{RatePoint, RateSource};
+use super::feedback::{Feedback, RatedPair};
+use super::rate_alerts::{AlertCondition, AlertTarget, RateRule, SavedRateRule};
+use super::rate_store::RateStore;
+use super::store::ConversationStore;
+
+/// Контрольная строка для проверки пароля при разблокировке
+const KEY_CHECK_PLAINTEXT: &str = "clippy-key-check";
+/// Окончание имени файла вложения, зашифрованного при включении шифрования
+const ENCRYPTED_BLOB_SUFFIX: &str = ".enc";
+
 /// Структура для хранения одного сообщения в БД
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StoredMessage {
@@ -14,22 +28,117 @@ pub struct StoredMessage {
     pub content: String,
     pub model: String,
     pub timestamp: String,
+    /// Постоянный идентификатор сообщения (нет у импортированных)
+    pub uid: Option<String>,
+}
+
+/// Сообщение импортируемой сессии (временная метка в RFC 3339)
+#[derive(Debug, Clone)]
+pub struct ImportedMessage {
+    pub role: String,
+    pub content: String,
+    pub model: String,
+    pub timestamp: String,
+    pub attachments: Vec<NewAttachment>,
+}
+
+/// Сообщение текущей сессии, ожидающее записи
+#[derive(Debug, Clone)]
+pub struct PendingMessage {
+    pub uid: String,
+    pub role: String,
+    pub content: String,
+    pub model: String,
+    pub timestamp: String,
+}
+
+/// Сессия, импортированная из внешнего ассистента
+#[derive(Debug, Clone)]
+pub struct ImportedSession {
+    pub session_id: String,
+    pub title: Option<String>,
+    pub source: String,
+    pub created_at: String,
+    pub messages: Vec<ImportedMessage>,
+}
+
+/// Политика хранения истории; `None` в поле означает отсутствие ограничения
+#[derive(Debug, Clone, Default)]
+pub struct RetentionPolicy {
+    pub max_age_days: Option<i64>,
+    /// Лимит живых сообщений незакреплённых сессий (корзина не в счёт)
+    pub max_messages: Option<usize>,
+    pub max_db_bytes: Option<u64>,
+    /// Через сколько дней корзина очищается окончательно
+    pub trash_days: Option<i64>,
+}
+
+impl RetentionPolicy {
+    pub fn from_config(config: &crate::config::Config) -> Self {
+        Self {
+            max_age_days: config.retention_max_age_days,
+            max_messages: config.retention_max_messages,
+            max_db_bytes: config.retention_max_db_mb.map(|mb| mb * 1024 * 1024),
+            trash_days: config.trash_retention_days,
+        }
+    }
+}
+
+/// Группа сообщений в корзине: одна очистка одной сессии
+#[derive(Debug, Clone)]
+pub struct TrashEntry {
+    /// Метка очистки (значение deleted_at), по ней выполняется восстановление
+    pub batch: String,
+    pub session_id: String,
+    pub count: usize,
+    pub preview: String,
+}
+
+/// Результат фонового обслуживания БД
+#[derive(Debug, Clone)]
+pub struct MaintenanceReport {
+    pub deleted: usize,
+    pub size_before: u64,
+    pub size_after: u64,
+    pub finished_at: String,
+}
+
+/// VACUUM при обслуживании, только если свободных страниц не меньше этой доли файла
+const VACUUM_FREELIST_RATIO: f64 = 0.2;
+
+/// Путь к БД по умолчанию: ~/.config/clippy/clippy.db
+pub fn default_db_path() -> PathBuf {
+    let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+    home.join(".config/clippy/clippy.db")
+}
+
+/// Экранирует `%`, `_` и `\` для LIKE … ESCAPE '\'
+fn escape_like(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
 }
 
 /// Сервис для работы с хранилищем разговоров в SQLite
 pub struct SQLiteStorage {
     conn: Connection,
     session_id: String,
+    cipher: Option<ContentCipher>,
+    /// Каталог крупных вложений (рядом с файлом БД)
+    attachments_dir: PathBuf,
+    db_path: PathBuf,
 }
 
 impl SQLiteStorage {
     /// Создает или открывает базу данных
     pub fn new(db_path: Option<PathBuf>) -> Result<Self> {
         // Используем путь по умолчанию если не указан
-        let db_path = db_path.unwrap_or_else(|| {
-            let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
-            home.join(".config/clippy/clippy.db")
-        });
+        let db_path = db_path.unwrap_or_else(default_db_path);
 
         // Создаем директорию если её нет
         if let Some(parent) = db_path.parent() {
@@ -39,6 +148,10 @@ impl SQLiteStorage {
         let conn = Connection::open(&db_path)?;
         log::info!("📦 SQLite БД открыта: {}", db_path.display());
 
+        // WAL: чтение не блокируется записью, запись не ждёт fsync каждой страницы
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.pragma_update(None, "synchronous", "NORMAL")?;
+
         // Создаем таблицу если её нет
         Self::init_schema(&conn)?;
 
@@ -46,7 +159,370 @@ impl SQLiteStorage {
         let session_id = Uuid::new_v4().to_string();
         log::info!("📍 Session ID: {}", session_id);
 
-        Ok(Self { conn, session_id })
+        conn.execute(
+            "INSERT OR IGNORE INTO sessions (session_id, title, source, created_at)
+             VALUES (?1, NULL, 'clippy', ?2)",
+            params![&session_id, Local::now().to_rfc3339()],
+        )?;
+
+        let attachments_dir = db_path
+            .parent()
+            .map(|dir| dir.join("attachments"))
+            .unwrap_or_else(|| PathBuf::from("attachments"));
+
+        Ok(Self {
+            conn,
+            session_id,
+            cipher: None,
+            attachments_dir,
+            db_path,
+        })
+    }
+
+    /// Открывает второе соединение к той же БД только для чтения. В режиме WAL
+    /// оно не ждёт записи, VACUUM и копирования в основном соединении и видит
+    /// всё, что было зафиксировано до начала запроса
+    pub fn open_reader(&self) -> Result<Self> {
+        let conn = Connection::open_with_flags(
+            &self.db_path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?;
+        conn.busy_timeout(std::time::Duration::from_secs(5))?;
+        Ok(Self {
+            conn,
+            session_id: self.session_id.clone(),
+            cipher: self.cipher.clone(),
+            attachments_dir: self.attachments_dir.clone(),
+            db_path: self.db_path.clone(),
+        })
+    }
+
+    /// Проверяет, включено ли шифрование в этой БД (задан ли пароль ранее)
+    pub fn is_encrypted(&self) -> Result<bool> {
+        Ok(self.get_meta("key_check")?.is_some())
+    }
+
+    /// Разблокирует БД паролем. При первом вызове включает шифрование
+    /// и переводит существующие открытые сообщения в зашифрованный вид
+    pub fn unlock(&mut self, passphrase: &str) -> Result<()> {
+        use base64::Engine;
+        let b64 = base64::engine::general_purpose::STANDARD;
+
+        let salt = match self.get_meta("kdf_salt")? {
+            Some(salt) => b64.decode(salt)?,
+            None => ContentCipher::generate_salt(),
+        };
+        let cipher = ContentCipher::from_passphrase(passphrase, &salt)?;
+
+        match self.get_meta("key_check")? {
+            Some(check) => {
+                if cipher.decrypt(&check).ok().as_deref() != Some(KEY_CHECK_PLAINTEXT) {
+                    return Err(anyhow::anyhow!("Неверный пароль БД"));
+                }
+            }
+            None => {
+                let tx = self.conn.unchecked_transaction()?;
+                tx.execute(
+                    "INSERT OR REPLACE INTO meta (key, value) VALUES ('kdf_salt', ?1)",
+                    params![b64.encode(&salt)],
+                )?;
+                tx.execute(
+                    "INSERT OR REPLACE INTO meta (key, value) VALUES ('key_check', ?1)",
+                    params![cipher.encrypt(KEY_CHECK_PLAINTEXT)?],
+                )?;
+                tx.commit()?;
+                log::info!("🔐 Шифрование БД включено");
+            }
+        }
+
+        // Удалённые и перезаписанные страницы затираются нулями, а не остаются в файле
+        self.conn.pragma_update(None, "secure_delete", "ON")?;
+
+        let rows = Self::encrypt_plaintext_rows(&self.conn, &cipher)?;
+        if rows > 0 {
+            log::info!("🔐 Зашифровано {} ранее открытых записей", rows);
+        }
+        let blobs = self.encrypt_plaintext_blobs(&cipher)?;
+        if blobs > 0 {
+            log::info!("🔐 Зашифровано {} ранее открытых вложений", blobs);
+        }
+        if rows + blobs > 0 {
+            // Открытый текст мог остаться в свободных страницах и в WAL
+            self.conn.execute_batch("VACUUM")?;
+            self.conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))?;
+            log::info!("🧹 Свободные страницы и WAL очищены от открытого текста");
+        }
+
+        self.cipher = Some(cipher);
+        log::info!("🔓 БД разблокирована");
+        Ok(())
+    }
+
+    /// Шифрует на месте все текстовые поля, сохранённые открытым текстом:
+    /// сообщения, названия сессий, комментарии и теги оценок, имена вложений
+    fn encrypt_plaintext_rows(conn: &Connection, cipher: &ContentCipher) -> Result<usize> {
+        const COLUMNS: [(&str, &str); 5] = [
+            ("conversations", "content"),
+            ("sessions", "title"),
+            ("feedback", "comment"),
+            ("feedback", "tags"),
+            ("attachments", "filename"),
+        ];
+
+        let tx = conn.unchecked_transaction()?;
+        let mut migrated = 0;
+        for (table, column) in COLUMNS {
+            let rows: Vec<(i64, String)> = {
+                let mut stmt = tx.prepare(&format!(
+                    "SELECT rowid, {column} FROM {table} WHERE {column} IS NOT NULL AND {column} != ''"
+                ))?;
+                let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+                rows.collect::<rusqlite::Result<_>>()?
+            };
+            for (rowid, text) in rows {
+                if ContentCipher::is_encrypted(&text) {
+                    continue;
+                }
+                tx.execute(
+                    &format!("UPDATE {table} SET {column} = ?1 WHERE rowid = ?2"),
+                    params![cipher.encrypt(&text)?, rowid],
+                )?;
+                migrated += 1;
+            }
+        }
+        tx.commit()?;
+        Ok(migrated)
+    }
+
+    /// Шифрует содержимое вложений, сохранённое открытым. Файлы на диске не
+    /// перезаписываются: зашифрованная копия пишется рядом (`<хеш>.enc`),
+    /// БД переключается на неё одной транзакцией, и только после фиксации
+    /// удаляется открытый файл. Сбой до фиксации оставляет прежний файл
+    /// в силе, сбой после — лишь открытый файл, который уберётся при следующей разблокировке
+    fn encrypt_plaintext_blobs(&self, cipher: &ContentCipher) -> Result<usize> {
+        self.remove_replaced_blob_files()?;
+
+        let blobs: Vec<(String, Option<Vec<u8>>, Option<String>)> = {
+            let mut stmt = self
+                .conn
+                .prepare("SELECT hash, data, file_path FROM attachment_blobs WHERE encrypted = 0")?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+            rows.collect::<rusqlite::Result<_>>()?
+        };
+
+        let mut replaced = Vec::new();
+        let tx = self.conn.unchecked_transaction()?;
+        for (hash, data, file_path) in &blobs {
+            match (data, file_path) {
+                (Some(data), _) => {
+                    tx.execute(
+                        "UPDATE attachment_blobs SET data = ?1, encrypted = 1 WHERE hash = ?2",
+                        params![cipher.encrypt_bytes(data)?, hash],
+                    )?;
+                }
+                (None, Some(file_path)) => {
+                    let encrypted_path = format!("{}{}", file_path, ENCRYPTED_BLOB_SUFFIX);
+                    let data = std::fs::read(self.attachments_dir.join(file_path))?;
+                    std::fs::write(self.attachments_dir.join(&encrypted_path), cipher.encrypt_bytes(&data)?)?;
+                    tx.execute(
+                        "UPDATE attachment_blobs SET file_path = ?1, encrypted = 1 WHERE hash = ?2",
+                        params![&encrypted_path, hash],
+                    )?;
+                    replaced.push(self.attachments_dir.join(file_path));
+                }
+                (None, None) => {}
+            }
+        }
+        tx.commit()?;
+
+        for path in replaced {
+            if let Err(e) = std::fs::remove_file(&path) {
+                log::warn!("⚠️ Не удалось удалить открытый файл вложения {}: {}", path.display(), e);
+            }
+        }
+        Ok(blobs.len())
+    }
+
+    /// Удаляет открытые файлы вложений, уже заменённые зашифрованными копиями
+    /// (остаются, если прошлое шифрование прервалось после фиксации в БД)
+    fn remove_replaced_blob_files(&self) -> Result<()> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT file_path FROM attachment_blobs WHERE encrypted = 1 AND file_path IS NOT NULL")?;
+        let paths = stmt.query_map([], |row| row.get::<_, String>(0))?;
+        for path in paths {
+            let path = path?;
+            let Some(plain) = path.strip_suffix(ENCRYPTED_BLOB_SUFFIX) else {
+                continue;
+            };
+            let plain = self.attachments_dir.join(plain);
+            if plain.is_file() {
+                std::fs::remove_file(&plain)?;
+                log::info!("🧹 Удалён оставшийся открытый файл вложения {}", plain.display());
+            }
+        }
+        Ok(())
+    }
+
+    /// Записывает вложение в рамках уже открытой транзакции. Содержимое
+    /// адресуется хешем: повторный файл добавляет только строку метаданных
+    fn insert_attachment(&self, conn: &Connection, message_uid: &str, file: &NewAttachment) -> Result<Attachment> {
+        let hash = attachment::content_hash(&file.data);
+
+        let exists: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM attachment_blobs WHERE hash = ?1)",
+            params![&hash],
+            |row| row.get(0),
+        )?;
+        if !exists {
+            let payload = match self.cipher {
+                Some(ref cipher) => cipher.encrypt_bytes(&file.data)?,
+                None => file.data.clone(),
+            };
+            let encrypted = self.cipher.is_some();
+
+            if file.data.len() > attachment::INLINE_LIMIT {
+                // Путь относительный: каталог переезжает вместе с профилем и копиями
+                let relative = format!("{}/{}", &hash[..2], hash);
+                let path = self.attachments_dir.join(&relative);
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::fs::write(&path, &payload)?;
+                conn.execute(
+                    "INSERT INTO attachment_blobs (hash, data, file_path, encrypted) VALUES (?1, NULL, ?2, ?3)",
+                    params![&hash, relative, encrypted],
+                )?;
+            } else {
+                conn.execute(
+                    "INSERT INTO attachment_blobs (hash, data, file_path, encrypted) VALUES (?1, ?2, NULL, ?3)",
+                    params![&hash, payload, encrypted],
+                )?;
+            }
+        }
+
+        let created_at = Local::now().to_rfc3339();
+        conn.execute(
+            "INSERT INTO attachments (message_uid, mime_type, filename, size, hash, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![message_uid, &file.mime_type, self.seal(&file.filename)?, file.data.len() as i64, &hash, &created_at],
+        )?;
+
+        Ok(Attachment {
+            id: conn.last_insert_rowid(),
+            message_uid: message_uid.to_string(),
+            mime_type: file.mime_type.clone(),
+            filename: file.filename.clone(),
+            size: file.data.len() as u64,
+            hash,
+            created_at,
+        })
+    }
+
+    fn row_to_attachment(&self, row: &rusqlite::Row) -> Result<Attachment> {
+        Ok(Attachment {
+            id: row.get(0)?,
+            message_uid: row.get(1)?,
+            mime_type: row.get(2)?,
+            filename: self.open_text(&row.get::<_, String>(3)?)?,
+            size: row.get::<_, i64>(4)? as u64,
+            hash: row.get(5)?,
+            created_at: row.get(6)?,
+        })
+    }
+
+    /// Удаляет вложения удалённых сообщений и содержимое, на которое никто не ссылается
+    fn purge_orphan_attachments(&self) -> Result<usize> {
+        let removed = self.conn.execute(
+            "DELETE FROM attachments
+             WHERE message_uid NOT IN (SELECT uid FROM conversations WHERE uid IS NOT NULL)",
+            [],
+        )?;
+
+        let files: Vec<String> = {
+            let mut stmt = self.conn.prepare(
+                "SELECT file_path FROM attachment_blobs
+                 WHERE file_path IS NOT NULL AND hash NOT IN (SELECT hash FROM attachments)",
+            )?;
+            let rows = stmt.query_map([], |row| row.get(0))?;
+            rows.collect::<rusqlite::Result<_>>()?
+        };
+        for file in files {
+            let _ = std::fs::remove_file(self.attachments_dir.join(file));
+        }
+        self.conn.execute(
+            "DELETE FROM attachment_blobs WHERE hash NOT IN (SELECT hash FROM attachments)",
+            [],
+        )?;
+
+        Ok(removed)
+    }
+
+    fn get_meta(&self, key: &str) -> Result<Option<String>> {
+        Self::read_meta(&self.conn, key)
+    }
+
+    fn read_meta(conn: &Connection, key: &str) -> Result<Option<String>> {
+        let mut stmt = conn.prepare("SELECT value FROM meta WHERE key = ?1")?;
+        let mut rows = stmt.query(params![key])?;
+        match rows.next()? {
+            Some(row) => Ok(Some(row.get(0)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Подготавливает содержимое к записи (шифрует, если БД разблокирована)
+    fn seal(&self, content: &str) -> Result<String> {
+        match self.cipher {
+            Some(ref cipher) => cipher.encrypt(content),
+            None => Ok(content.to_string()),
+        }
+    }
+
+    /// Расшифровывает произвольное поле, прочитанное из БД
+    fn open_text(&self, text: &str) -> Result<String> {
+        match self.cipher {
+            Some(ref cipher) => cipher.decrypt(text),
+            None => Ok(text.to_string()),
+        }
+    }
+
+    /// Читает оценку из колонок rating, comment, bookmarked, tags начиная с `offset`
+    fn row_to_feedback(&self, row: &rusqlite::Row, offset: usize) -> Result<Feedback> {
+        let comment: Option<String> = row.get(offset + 1)?;
+        let tags: String = row.get(offset + 3)?;
+        Ok(Feedback {
+            rating: row.get(offset)?,
+            comment: match comment {
+                Some(c) => Some(self.open_text(&c)?),
+                None => None,
+            },
+            bookmarked: row.get::<_, i32>(offset + 2)? != 0,
+            tags: Feedback::parse_tags(&self.open_text(&tags)?),
+        })
+    }
+
+    /// Расшифровывает прочитанное сообщение
+    fn open(&self, mut msg: StoredMessage) -> Result<StoredMessage> {
+        if let Some(ref cipher) = self.cipher {
+            msg.content = cipher.decrypt(&msg.content)?;
+        }
+        Ok(msg)
+    }
+
+    /// Добавляет колонку в существующую таблицу (миграция старых БД)
+    fn add_column_if_missing(conn: &Connection, table: &str, column: &str, definition: &str) -> Result<()> {
+        let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+        let columns = stmt.query_map([], |row| row.get::<_, String>(1))?;
+        for name in columns {
+            if name? == column {
+                return Ok(());
+            }
+        }
+
+        conn.execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition), [])?;
+        log::info!("🔧 Миграция БД: добавлена колонка {}.{}", table, column);
+        Ok(())
     }
 
     /// Инициализирует схему БД
@@ -69,128 +545,1054 @@ impl SQLiteStorage {
             [],
         )?;
 
+        // Метаданные сессий (заголовок и источник — для импортированных чатов)
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS sessions (
+                session_id TEXT PRIMARY KEY,
+                title TEXT,
+                source TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+
+        // Служебные параметры (соль KDF, контрольное значение ключа)
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS meta (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL
+            )",
+            [],
+        )?;
+
+        // Закреплённые сессии не удаляются политиками хранения
+        Self::add_column_if_missing(conn, "sessions", "pinned", "INTEGER NOT NULL DEFAULT 0")?;
+
+        // Мягкое удаление: очищенные сообщения лежат в корзине до автоочистки
+        Self::add_column_if_missing(conn, "conversations", "deleted_at", "TEXT")?;
+
+        // Постоянный uid сообщения: по нему UI ссылается на ответы для оценок
+        Self::add_column_if_missing(conn, "conversations", "uid", "TEXT")?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_uid ON conversations(uid)",
+            [],
+        )?;
+
+        // Оценки, комментарии, закладки и теги к ответам
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS feedback (
+                message_uid TEXT PRIMARY KEY,
+                rating INTEGER NOT NULL DEFAULT 0,
+                comment TEXT,
+                bookmarked INTEGER NOT NULL DEFAULT 0,
+                tags TEXT NOT NULL DEFAULT '',
+                updated_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+
+        // Вложения: метаданные по сообщениям и содержимое, адресуемое хешем
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS attachments (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                message_uid TEXT NOT NULL,
+                mime_type TEXT NOT NULL,
+                filename TEXT NOT NULL,
+                size INTEGER NOT NULL,
+                hash TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_attachments_message ON attachments(message_uid)",
+            [],
+        )?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS attachment_blobs (
+                hash TEXT PRIMARY KEY,
+                data BLOB,
+                file_path TEXT,
+                encrypted INTEGER NOT NULL DEFAULT 0
+            )",
+            [],
+        )?;
+
+        // Дневные курсы валют для графиков (по одному на валюту и дату)
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS exchange_rates (
+                currency TEXT NOT NULL,
+                date TEXT NOT NULL,
+                rate REAL NOT NULL,
+                source TEXT NOT NULL,
+                PRIMARY KEY (currency, date)
+            )",
+            [],
+        )?;
+
+        // По какую дату история курса запрошена у ЦБ (в выходные курса нет, и без отметки
+        // они запрашивались бы при каждом обновлении)
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS rate_sync (
+                currency TEXT PRIMARY KEY,
+                synced_until TEXT NOT NULL
+            )",
+            [],
+        )?;
+
+        // Правила уведомлений о курсах валют и акций и состояние их последней проверки
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS rate_alerts (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                target TEXT NOT NULL,
+                symbol TEXT NOT NULL,
+                base TEXT NOT NULL,
+                condition TEXT NOT NULL,
+                threshold REAL NOT NULL,
+                last_value REAL,
+                fired_on TEXT,
+                created_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+
         log::info!("✓ Схема БД инициализирована");
         Ok(())
     }
 
-    /// Сохраняет сообщение в БД
-    pub fn save_message(&self, role: &str, content: &str, model: &str) -> Result<()> {
-        let timestamp = Local::now().to_rfc3339();
+    fn row_to_message(row: &rusqlite::Row) -> rusqlite::Result<StoredMessage> {
+        Ok(StoredMessage {
+            id: row.get(0)?,
+            session_id: row.get(1)?,
+            role: row.get(2)?,
+            content: row.get(3)?,
+            model: row.get(4)?,
+            timestamp: row.get(5)?,
+            uid: row.get(6)?,
+        })
+    }
+
+    /// Размер файла БД в байтах (по числу страниц SQLite)
+    pub fn db_size_bytes(&self) -> Result<u64> {
+        let page_count: u64 = self.conn.query_row("PRAGMA page_count", [], |row| row.get(0))?;
+        let page_size: u64 = self.conn.query_row("PRAGMA page_size", [], |row| row.get(0))?;
+        Ok(page_count * page_size)
+    }
 
-        self.conn.execute(
-            "INSERT INTO conversations (session_id, role, content, model, timestamp)
-             VALUES (?1, ?2, ?3, ?4, ?5)",
-            params![&self.session_id, role, content, model, &timestamp],
-        )?;
+    /// Объём, занятый данными (без свободных страниц, которые вернёт VACUUM)
+    fn db_used_bytes(&self) -> Result<u64> {
+        let freelist: u64 = self.conn.query_row("PRAGMA freelist_count", [], |row| row.get(0))?;
+        let page_size: u64 = self.conn.query_row("PRAGMA page_size", [], |row| row.get(0))?;
+        Ok(self.db_size_bytes()?.saturating_sub(freelist * page_size))
+    }
 
-        log::debug!("💾 Сохранено сообщение: {} - {}", role, &content[..content.len().min(50)]);
+    /// Доля свободных страниц в файле БД
+    fn freelist_ratio(&self) -> Result<f64> {
+        let page_count: u64 = self.conn.query_row("PRAGMA page_count", [], |row| row.get(0))?;
+        let freelist: u64 = self.conn.query_row("PRAGMA freelist_count", [], |row| row.get(0))?;
+        Ok(if page_count == 0 { 0.0 } else { freelist as f64 / page_count as f64 })
+    }
+}
+
+impl ConversationStore for SQLiteStorage {
+    /// Сохраняет пачку сообщений текущей сессии в одной транзакции
+    fn save_messages(&mut self, batch: &[PendingMessage]) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+
+        for msg in batch {
+            tx.execute(
+                "INSERT INTO conversations (session_id, role, content, model, timestamp, uid)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![&self.session_id, &msg.role, &self.seal(&msg.content)?, &msg.model, &msg.timestamp, &msg.uid],
+            )?;
+
+            let preview: String = msg.content.chars().take(50).collect();
+            log::debug!("💾 Сохранено сообщение: {} - {}", msg.role, preview);
+        }
+
+        tx.commit()?;
         Ok(())
     }
 
+    /// Импортирует внешнюю сессию с исходными временными метками.
+    /// Возвращает количество добавленных сообщений (0 если сессия уже импортирована)
+    fn import_session(&mut self, session: &ImportedSession) -> Result<usize> {
+        let title = match session.title {
+            Some(ref title) => Some(self.seal(title)?),
+            None => None,
+        };
+        let tx = self.conn.unchecked_transaction()?;
+
+        let inserted = tx.execute(
+            "INSERT OR IGNORE INTO sessions (session_id, title, source, created_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![&session.session_id, title, &session.source, &session.created_at],
+        )?;
+
+        if inserted == 0 {
+            log::debug!("⏭️  Сессия {} уже импортирована", session.session_id);
+            return Ok(0);
+        }
+
+        for msg in &session.messages {
+            let uid = Uuid::new_v4().to_string();
+            tx.execute(
+                "INSERT INTO conversations (session_id, role, content, model, timestamp, uid)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![&session.session_id, &msg.role, &self.seal(&msg.content)?, &msg.model, &msg.timestamp, &uid],
+            )?;
+            for file in &msg.attachments {
+                self.insert_attachment(&tx, &uid, file)?;
+            }
+        }
+
+        tx.commit()?;
+        Ok(session.messages.len())
+    }
+
+    /// Ищет сообщения по подстроке во всех сессиях (новые первыми).
+    /// В зашифрованной БД сравнение идёт после расшифровки, а не через LIKE
+    fn search_messages(&self, query: &str, limit: usize) -> Result<Vec<StoredMessage>> {
+        let query = query.trim();
+        let pattern = if self.cipher.is_some() {
+            "%".to_string()
+        } else {
+            format!("%{}%", escape_like(query))
+        };
+        let mut stmt = self.conn.prepare(
+            "SELECT id, session_id, role, content, model, timestamp, uid
+             FROM conversations
+             WHERE content LIKE ?1 ESCAPE '\\' AND deleted_at IS NULL
+             ORDER BY timestamp DESC",
+        )?;
+
+        let messages = stmt.query_map(params![&pattern], Self::row_to_message)?;
+
+        let needle = query.to_lowercase();
+        let mut result = Vec::new();
+        for msg in messages {
+            let msg = self.open(msg?)?;
+            if self.cipher.is_some() && !msg.content.to_lowercase().contains(&needle) {
+                continue;
+            }
+            result.push(msg);
+            if result.len() >= limit {
+                break;
+            }
+        }
+
+        log::info!("🔍 Найдено {} сообщений по запросу '{}'", result.len(), query);
+        Ok(result)
+    }
+
     /// Загружает историю разговора из текущей сессии
-    pub fn load_session_history(&self) -> Result<Vec<StoredMessage>> {
+    fn load_session_history(&self) -> Result<Vec<StoredMessage>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, session_id, role, content, model, timestamp 
+            "SELECT id, session_id, role, content, model, timestamp, uid
              FROM conversations 
-             WHERE session_id = ?1 
+             WHERE session_id = ?1 AND deleted_at IS NULL
              ORDER BY id ASC",
         )?;
 
-        let messages = stmt.query_map(params![&self.session_id], |row| {
-            Ok(StoredMessage {
-                id: row.get(0)?,
-                session_id: row.get(1)?,
-                role: row.get(2)?,
-                content: row.get(3)?,
-                model: row.get(4)?,
-                timestamp: row.get(5)?,
-            })
-        })?;
+        let messages = stmt.query_map(params![&self.session_id], Self::row_to_message)?;
 
         let mut result = Vec::new();
         for msg in messages {
-            result.push(msg?);
+            result.push(self.open(msg?)?);
         }
 
         log::info!("📖 Загружено {} сообщений из сессии", result.len());
         Ok(result)
     }
 
-    /// Загружает последние N сессий
-    pub fn load_recent_sessions(&self, limit: usize) -> Result<Vec<(String, usize)>> {
+    /// Переносит историю текущей сессии в корзину
+    fn clear_session_history(&mut self) -> Result<String> {
+        let batch = Local::now().to_rfc3339();
+        let affected = self.conn.execute(
+            "UPDATE conversations SET deleted_at = ?1 WHERE session_id = ?2 AND deleted_at IS NULL",
+            params![&batch, &self.session_id],
+        )?;
+
+        log::warn!("🗑️  {} сообщений из текущей сессии перенесено в корзину", affected);
+        Ok(batch)
+    }
+
+    /// Содержимое корзины, сгруппированное по очистке и сессии (новые первыми)
+    fn list_trash(&self, limit: usize) -> Result<Vec<TrashEntry>> {
         let mut stmt = self.conn.prepare(
-            "SELECT session_id, COUNT(*) as count 
-             FROM conversations 
-             GROUP BY session_id 
-             ORDER BY MAX(id) DESC 
+            "SELECT deleted_at, session_id, COUNT(*), MIN(id)
+             FROM conversations
+             WHERE deleted_at IS NOT NULL
+             GROUP BY deleted_at, session_id
+             ORDER BY deleted_at DESC
              LIMIT ?1",
         )?;
 
-        let sessions = stmt.query_map(params![limit as i32], |row| {
-            Ok((row.get::<_, String>(0)?, row.get::<_, usize>(1)?))
+        let groups = stmt.query_map(params![limit as i32], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, usize>(2)?,
+                row.get::<_, i64>(3)?,
+            ))
         })?;
 
         let mut result = Vec::new();
-        for session in sessions {
-            result.push(session?);
+        for group in groups {
+            let (deleted_at, session_id, count, first_id) = group?;
+            let first: String = self.conn.query_row(
+                "SELECT content FROM conversations WHERE id = ?1",
+                params![first_id],
+                |row| row.get(0),
+            )?;
+            let first = self.open_text(&first)?;
+
+            result.push(TrashEntry {
+                batch: deleted_at,
+                session_id,
+                count,
+                preview: first.chars().take(60).collect(),
+            });
+        }
+        Ok(result)
+    }
+
+    /// Возвращает из корзины сообщения очистки `batch` (при необходимости — одной сессии)
+    fn restore_deleted(&mut self, batch: &str, session_id: Option<&str>) -> Result<usize> {
+        let restored = match session_id {
+            Some(session_id) => self.conn.execute(
+                "UPDATE conversations SET deleted_at = NULL WHERE deleted_at = ?1 AND session_id = ?2",
+                params![batch, session_id],
+            )?,
+            None => self.conn.execute(
+                "UPDATE conversations SET deleted_at = NULL WHERE deleted_at = ?1",
+                params![batch],
+            )?,
+        };
+
+        log::info!("♻️  Восстановлено {} сообщений из корзины", restored);
+        Ok(restored)
+    }
+
+    /// Сохраняет оценку ответа (пустая оценка удаляет запись)
+    fn set_feedback(&mut self, uid: &str, feedback: &Feedback) -> Result<()> {
+        if feedback.is_empty() {
+            self.conn.execute("DELETE FROM feedback WHERE message_uid = ?1", params![uid])?;
+            return Ok(());
+        }
+
+        let comment = match feedback.comment {
+            Some(ref comment) => Some(self.seal(comment)?),
+            None => None,
+        };
+        let tags = match feedback.tags_string() {
+            tags if tags.is_empty() => tags,
+            tags => self.seal(&tags)?,
+        };
+        self.conn.execute(
+            "INSERT OR REPLACE INTO feedback (message_uid, rating, comment, bookmarked, tags, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                uid,
+                feedback.rating,
+                comment,
+                feedback.bookmarked as i32,
+                tags,
+                Local::now().to_rfc3339()
+            ],
+        )?;
+        log::debug!("⭐ Оценка {} сохранена: {:?}", uid, feedback.rating);
+        Ok(())
+    }
+
+    /// Загружает оценку ответа
+    fn get_feedback(&self, uid: &str) -> Result<Option<Feedback>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT rating, comment, bookmarked, tags FROM feedback WHERE message_uid = ?1",
+        )?;
+        let mut rows = stmt.query(params![uid])?;
+        match rows.next()? {
+            Some(row) => Ok(Some(self.row_to_feedback(row, 0)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Пары «вопрос — оценённый ответ» в хронологическом порядке
+    fn rated_pairs(&self) -> Result<Vec<RatedPair>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT c.content, c.model, c.timestamp, f.rating, f.comment, f.bookmarked, f.tags,
+                    p.content, c.uid, p.uid
+             FROM feedback f
+             JOIN conversations c ON c.uid = f.message_uid
+             LEFT JOIN conversations p ON p.id = (
+                 SELECT q.id FROM conversations q
+                 WHERE q.session_id = c.session_id AND q.id < c.id AND q.role = 'user'
+                 ORDER BY q.id DESC LIMIT 1
+             )
+             WHERE f.rating != 0 AND c.deleted_at IS NULL
+             ORDER BY c.timestamp ASC",
+        )?;
+
+        let mut rows = stmt.query([])?;
+        let mut result = Vec::new();
+        while let Some(row) = rows.next()? {
+            let feedback = self.row_to_feedback(row, 3)?;
+            let prompt: Option<String> = row.get(7)?;
+            let mut attachments = Vec::new();
+            for uid in [row.get::<_, Option<String>>(9)?, row.get::<_, Option<String>>(8)?].into_iter().flatten() {
+                attachments.extend(self.list_attachments(&uid)?);
+            }
+            result.push(RatedPair {
+                prompt: match prompt {
+                    Some(p) => self.open_text(&p)?,
+                    None => String::new(),
+                },
+                response: self.open_text(&row.get::<_, String>(0)?)?,
+                rating: feedback.rating,
+                comment: feedback.comment,
+                bookmarked: feedback.bookmarked,
+                tags: feedback.tags,
+                model: row.get(1)?,
+                timestamp: row.get(2)?,
+                attachments,
+            });
         }
+        Ok(result)
+    }
+
+    /// Ответы с закладкой (новые первыми)
+    fn bookmarks(&self, limit: usize) -> Result<Vec<(StoredMessage, Feedback)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT c.id, c.session_id, c.role, c.content, c.model, c.timestamp, c.uid,
+                    f.rating, f.comment, f.bookmarked, f.tags
+             FROM feedback f
+             JOIN conversations c ON c.uid = f.message_uid
+             WHERE f.bookmarked = 1 AND c.deleted_at IS NULL
+             ORDER BY c.timestamp DESC
+             LIMIT ?1",
+        )?;
 
-        log::info!("📚 Загружено {} сессий", result.len());
+        let mut rows = stmt.query(params![limit as i32])?;
+        let mut result = Vec::new();
+        while let Some(row) = rows.next()? {
+            let msg = self.open(Self::row_to_message(row)?)?;
+            result.push((msg, self.row_to_feedback(row, 7)?));
+        }
         Ok(result)
     }
 
-    /// Очищает историю текущей сессии
-    pub fn clear_session_history(&self) -> Result<()> {
-        let affected = self.conn.execute(
-            "DELETE FROM conversations WHERE session_id = ?1",
+    /// Закрепляет (или открепляет) текущую сессию: закреплённые сессии хранятся вечно
+    fn set_session_pinned(&mut self, pinned: bool) -> Result<()> {
+        self.conn.execute(
+            "UPDATE sessions SET pinned = ?1 WHERE session_id = ?2",
+            params![pinned as i32, &self.session_id],
+        )?;
+        log::info!("📌 Сессия {} {}", self.session_id, if pinned { "закреплена" } else { "откреплена" });
+        Ok(())
+    }
+
+    /// Применяет политику хранения, затем выполняет VACUUM и ANALYZE.
+    /// Сообщения закреплённых сессий не удаляются. Время сравнивается через `datetime()`:
+    /// импортированные сообщения хранятся с другими смещениями (ChatGPT — в UTC)
+    fn run_maintenance(&mut self, policy: &RetentionPolicy) -> Result<MaintenanceReport> {
+        const UNPINNED: &str = "session_id NOT IN (SELECT session_id FROM sessions WHERE pinned = 1)";
+        let size_before = self.db_size_bytes()?;
+        let mut deleted = 0;
+
+        if let Some(days) = policy.trash_days {
+            let cutoff = (Utc::now() - chrono::Duration::days(days)).to_rfc3339();
+            deleted += self.conn.execute(
+                "DELETE FROM conversations WHERE deleted_at IS NOT NULL AND datetime(deleted_at) < datetime(?1)",
+                params![cutoff],
+            )?;
+        }
+
+        if let Some(days) = policy.max_age_days {
+            let cutoff = (Utc::now() - chrono::Duration::days(days)).to_rfc3339();
+            deleted += self.conn.execute(
+                &format!("DELETE FROM conversations WHERE datetime(timestamp) < datetime(?1) AND {}", UNPINNED),
+                params![cutoff],
+            )?;
+        }
+
+        if let Some(max) = policy.max_messages {
+            // Лимит — на живые незакреплённые сообщения: закреплённые и корзина
+            // не в счёт, иначе закреплённые сессии вытесняли бы всю остальную историю
+            let live = format!("deleted_at IS NULL AND {}", UNPINNED);
+            deleted += self.conn.execute(
+                &format!(
+                    "DELETE FROM conversations WHERE id IN (
+                        SELECT id FROM conversations WHERE {live}
+                        ORDER BY datetime(timestamp) ASC, id ASC
+                        LIMIT MAX((SELECT COUNT(*) FROM conversations WHERE {live}) - ?1, 0)
+                    )"
+                ),
+                params![max as i64],
+            )?;
+        }
+
+        if let Some(max_bytes) = policy.max_db_bytes {
+            // Удалённые строки освобождают страницы сразу: занятый объём считается
+            // без свободных страниц, VACUUM выполняется один раз в конце
+            self.purge_orphan_attachments()?;
+            while self.db_used_bytes()? > max_bytes {
+                let batch = self.conn.execute(
+                    &format!(
+                        "DELETE FROM conversations WHERE id IN (
+                            SELECT id FROM conversations WHERE {}
+                            ORDER BY datetime(timestamp) ASC, id ASC LIMIT 100
+                        )",
+                        UNPINNED
+                    ),
+                    [],
+                )?;
+                if batch == 0 {
+                    break;
+                }
+                deleted += batch;
+                self.purge_orphan_attachments()?;
+            }
+        }
+
+        self.purge_orphan_attachments()?;
+        // Оценки удалённых сообщений и сессии, от которых ничего не осталось
+        self.conn.execute(
+            "DELETE FROM feedback WHERE message_uid NOT IN (SELECT uid FROM conversations WHERE uid IS NOT NULL)",
+            [],
+        )?;
+        self.conn.execute(
+            "DELETE FROM sessions
+             WHERE pinned = 0 AND session_id != ?1
+               AND session_id NOT IN (SELECT DISTINCT session_id FROM conversations)",
             params![&self.session_id],
         )?;
 
-        log::warn!("🗑️  Очищено {} сообщений из текущей сессии", affected);
+        if self.freelist_ratio()? >= VACUUM_FREELIST_RATIO {
+            self.conn.execute_batch("VACUUM")?;
+        }
+        self.conn.execute_batch("ANALYZE")?;
+
+        let report = MaintenanceReport {
+            deleted,
+            size_before,
+            size_after: self.db_size_bytes()?,
+            finished_at: Local::now().format("%H:%M").to_string(),
+        };
+        log::info!(
+            "🧹 Обслуживание БД: удалено {}, размер {} → {} КБ",
+            report.deleted,
+            report.size_before / 1024,
+            report.size_after / 1024
+        );
+        Ok(report)
+    }
+
+    fn attach(&mut self, message_uid: &str, file: &NewAttachment) -> Result<Attachment> {
+        let tx = self.conn.unchecked_transaction()?;
+        let attachment = self.insert_attachment(&tx, message_uid, file)?;
+        tx.commit()?;
+        log::info!("📎 Вложение {} ({} байт) → {}", attachment.filename, attachment.size, message_uid);
+        Ok(attachment)
+    }
+
+    fn list_attachments(&self, message_uid: &str) -> Result<Vec<Attachment>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, message_uid, mime_type, filename, size, hash, created_at
+             FROM attachments WHERE message_uid = ?1 ORDER BY id ASC",
+        )?;
+        let mut rows = stmt.query(params![message_uid])?;
+        let mut attachments = Vec::new();
+        while let Some(row) = rows.next()? {
+            attachments.push(self.row_to_attachment(row)?);
+        }
+        Ok(attachments)
+    }
+
+    fn fetch_attachment(&self, id: i64) -> Result<Option<(Attachment, Vec<u8>)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT a.id, a.message_uid, a.mime_type, a.filename, a.size, a.hash, a.created_at,
+                    b.data, b.file_path, b.encrypted
+             FROM attachments a JOIN attachment_blobs b ON b.hash = a.hash
+             WHERE a.id = ?1",
+        )?;
+        let mut rows = stmt.query(params![id])?;
+        let Some(row) = rows.next()? else {
+            return Ok(None);
+        };
+
+        let attachment = self.row_to_attachment(row)?;
+        let payload = match (row.get::<_, Option<Vec<u8>>>(7)?, row.get::<_, Option<String>>(8)?) {
+            (Some(data), _) => data,
+            (None, Some(file_path)) => std::fs::read(self.attachments_dir.join(file_path))?,
+            (None, None) => return Err(anyhow::anyhow!("Содержимое вложения {} потеряно", id)),
+        };
+        let data = match (row.get::<_, bool>(9)?, &self.cipher) {
+            (false, _) => payload,
+            (true, Some(cipher)) => cipher.decrypt_bytes(&payload)?,
+            (true, None) => return Err(anyhow::anyhow!("Вложение зашифровано: БД не разблокирована")),
+        };
+        Ok(Some((attachment, data)))
+    }
+
+    fn rates(&self) -> &dyn RateStore {
+        self
+    }
+
+    fn rates_mut(&mut self) -> &mut dyn RateStore {
+        self
+    }
+
+    fn open_reader(&self) -> Result<Option<Box<dyn ConversationStore>>> {
+        Ok(Some(Box::new(SQLiteStorage::open_reader(self)?)))
+    }
+
+    /// Заменяет содержимое БД копией. Зашифрованную копию можно вернуть
+    /// только с тем же паролем; открытую — зашифруем текущим ключом
+    fn restore_from(&mut self, path: &Path) -> Result<()> {
+        let source = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        let backup_check = Self::read_meta(&source, "key_check").ok().flatten();
+
+        if let Some(ref check) = backup_check {
+            match self.cipher {
+                None => return Err(anyhow::anyhow!("Копия зашифрована: задайте CLIPPY_DB_PASSPHRASE")),
+                Some(ref cipher) if cipher.decrypt(check).ok().as_deref() != Some(KEY_CHECK_PLAINTEXT) => {
+                    return Err(anyhow::anyhow!("Копия зашифрована другим паролем"));
+                }
+                Some(_) => {}
+            }
+        }
+
+        let live_salt = self.get_meta("kdf_salt")?;
+        let live_check = self.get_meta("key_check")?;
+
+        Backup::new(&source, &mut self.conn)?.run_to_completion(256, std::time::Duration::ZERO, None)?;
+
+        // Копия могла быть сделана до последних миграций схемы
+        Self::init_schema(&self.conn)?;
+
+        if let (Some(cipher), None) = (&self.cipher, &backup_check) {
+            for (key, value) in [("kdf_salt", live_salt), ("key_check", live_check)] {
+                if let Some(value) = value {
+                    self.conn.execute(
+                        "INSERT OR REPLACE INTO meta (key, value) VALUES (?1, ?2)",
+                        params![key, value],
+                    )?;
+                }
+            }
+            Self::encrypt_plaintext_rows(&self.conn, cipher)?;
+            self.encrypt_plaintext_blobs(cipher)?;
+        }
+
+        self.conn.execute(
+            "INSERT OR IGNORE INTO sessions (session_id, title, source, created_at)
+             VALUES (?1, NULL, 'clippy', ?2)",
+            params![&self.session_id, Local::now().to_rfc3339()],
+        )?;
+
+        log::info!("♻️ БД восстановлена из {}", path.display());
         Ok(())
     }
+}
 
-    /// Очищает всю историю (осторожно!)
-    pub fn clear_all_history(&self) -> Result<()> {
-        let affected = self.conn.execute("DELETE FROM conversations", [])?;
-        log::warn!("🗑️  ⚠️  Очищено {} сообщений ИЗ ВСЕй ИСТОРИИ", affected);
+impl RateStore for SQLiteStorage {
+    fn save_rates(&mut self, points: &[RatePoint]) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT OR REPLACE INTO exchange_rates (currency, date, rate, source) VALUES (?1, ?2, ?3, ?4)",
+            )?;
+            for point in points {
+                stmt.execute(params![point.currency, point.date, point.rate, point.source.id()])?;
+            }
+        }
+        tx.commit()?;
         Ok(())
     }
 
-    /// Возвращает количество сообщений в БД
-    pub fn message_count(&self) -> Result<usize> {
-        let count: usize = self.conn.query_row(
-            "SELECT COUNT(*) FROM conversations",
-            [],
-            |row| row.get(0),
+    fn rate_history(&self, currency: &str, since: NaiveDate) -> Result<Vec<RatePoint>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT currency, date, rate, source FROM exchange_rates
+             WHERE currency = ?1 AND date >= ?2 ORDER BY date ASC",
         )?;
-        Ok(count)
+        let rows = stmt.query_map(params![currency, since], |row| {
+            Ok(RatePoint {
+                currency: row.get(0)?,
+                date: row.get(1)?,
+                rate: row.get(2)?,
+                source: RateSource::parse(&row.get::<_, String>(3)?).unwrap_or_default(),
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
     }
 
-    /// Возвращает информацию о статистике БД
-    pub fn get_stats(&self) -> Result<String> {
-        let total: usize = self.conn.query_row(
-            "SELECT COUNT(*) FROM conversations",
-            [],
-            |row| row.get(0),
+    fn synced_until(&self, currency: &str) -> Result<Option<NaiveDate>> {
+        Ok(self
+            .conn
+            .query_row(
+                "SELECT synced_until FROM rate_sync WHERE currency = ?1",
+                params![currency],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    fn set_synced_until(&mut self, currency: &str, date: NaiveDate) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO rate_sync (currency, synced_until) VALUES (?1, ?2)",
+            params![currency, date],
         )?;
+        Ok(())
+    }
 
-        let sessions: usize = self.conn.query_row(
-            "SELECT COUNT(DISTINCT session_id) FROM conversations",
-            [],
-            |row| row.get(0),
+    fn add_rate_rule(&mut self, rule: &RateRule) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO rate_alerts (target, symbol, base, condition, threshold, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                rule.target.id(),
+                rule.symbol,
+                rule.base,
+                rule.condition.id(),
+                rule.condition.threshold(),
+                Local::now().to_rfc3339()
+            ],
         )?;
+        Ok(self.conn.last_insert_rowid())
+    }
 
-        let current_session: usize = self.conn.query_row(
-            "SELECT COUNT(*) FROM conversations WHERE session_id = ?1",
-            params![&self.session_id],
-            |row| row.get(0),
+    fn rate_rules(&self) -> Result<Vec<SavedRateRule>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, target, symbol, base, condition, threshold, last_value, fired_on
+             FROM rate_alerts ORDER BY id ASC",
         )?;
+        let rows = stmt.query_map([], |row| {
+            let target: String = row.get(1)?;
+            let condition: String = row.get(4)?;
+            Ok((
+                row.get::<_, i64>(0)?,
+                AlertTarget::parse(&target),
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                AlertCondition::from_parts(&condition, row.get(5)?),
+                row.get::<_, Option<f64>>(6)?,
+                row.get::<_, Option<NaiveDate>>(7)?,
+            ))
+        })?;
+
+        let mut rules = Vec::new();
+        for row in rows {
+            let (id, target, symbol, base, condition, last_value, fired_on) = row?;
+            // Правила из будущих версий с незнакомыми условиями пропускаем
+            let (Some(target), Some(condition)) = (target, condition) else {
+                log::warn!("⚠️ Пропущено правило уведомления #{} с неизвестным условием", id);
+                continue;
+            };
+            rules.push(SavedRateRule {
+                id,
+                rule: RateRule {
+                    target,
+                    symbol,
+                    base,
+                    condition,
+                },
+                last_value,
+                fired_on,
+            });
+        }
+        Ok(rules)
+    }
 
-        Ok(format!(
-            "📊 Статистика БД: {} всего, {} сессий, {} в текущей",
-            total, sessions, current_session
-        ))
+    fn remove_rate_rule(&mut self, id: i64) -> Result<bool> {
+        let removed = self.conn.execute("DELETE FROM rate_alerts WHERE id = ?1", params![id])?;
+        Ok(removed > 0)
+    }
+
+    fn update_rate_rule(&mut self, id: i64, last_value: f64, fired_on: Option<NaiveDate>) -> Result<()> {
+        self.conn.execute(
+            "UPDATE rate_alerts SET last_value = ?1, fired_on = ?2 WHERE id = ?3",
+            params![last_value, fired_on, id],
+        )?;
+        Ok(())
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Временная БД в собственном каталоге (удаляется вместе с ним)
+    pub(crate) struct TempDb {
+        pub dir: PathBuf,
+        pub storage: SQLiteStorage,
+    }
+
+    impl TempDb {
+        pub fn new() -> Self {
+            let dir = std::env::temp_dir().join(format!("clippy-test-{}", Uuid::new_v4()));
+            let storage = SQLiteStorage::new(Some(dir.join("clippy.db"))).unwrap();
+            Self { dir, storage }
+        }
+    }
+
+    impl Drop for TempDb {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    fn message(role: &str, content: &str) -> PendingMessage {
+        PendingMessage {
+            uid: Uuid::new_v4().to_string(),
+            role: role.to_string(),
+            content: content.to_string(),
+            model: "test".to_string(),
+            timestamp: Local::now().to_rfc3339(),
+        }
+    }
+
+    #[test]
+    fn escape_like_escapes_wildcards() {
+        assert_eq!(escape_like("100%_a\\b"), "100\\%\\_a\\\\b");
+        assert_eq!(escape_like("обычный текст"), "обычный текст");
+    }
+
+    #[test]
+    fn search_treats_wildcards_literally() {
+        let mut db = TempDb::new();
+        db.storage
+            .save_messages(&[message("user", "скидка 50% на всё"), message("user", "скидка 500 рублей")])
+            .unwrap();
+
+        let found = db.storage.search_messages("50%", 10).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].content, "скидка 50% на всё");
+        assert!(db.storage.search_messages("_", 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn unlock_encrypts_all_text_and_leaves_no_plaintext_on_disk() {
+        let mut db = TempDb::new();
+        let reply = message("assistant", "SECRETMESSAGE");
+        db.storage.save_messages(&[message("user", "вопрос"), reply.clone()]).unwrap();
+        db.storage
+            .set_feedback(
+                &reply.uid,
+                &Feedback {
+                    rating: 1,
+                    comment: Some("SECRETCOMMENT".to_string()),
+                    bookmarked: true,
+                    tags: vec!["secrettag".to_string()],
+                },
+            )
+            .unwrap();
+        let file = NewAttachment {
+            filename: "secretfile.txt".to_string(),
+            mime_type: "text/plain".to_string(),
+            data: b"SECRETDATA".to_vec(),
+        };
+        db.storage.attach(&reply.uid, &file).unwrap();
+        db.storage
+            .import_session(&ImportedSession {
+                session_id: "chatgpt:1".to_string(),
+                title: Some("SECRETTITLE".to_string()),
+                source: "chatgpt".to_string(),
+                created_at: Local::now().to_rfc3339(),
+                messages: vec![ImportedMessage {
+                    role: "user".to_string(),
+                    content: "импорт".to_string(),
+                    model: "gpt".to_string(),
+                    timestamp: Local::now().to_rfc3339(),
+                    attachments: Vec::new(),
+                }],
+            })
+            .unwrap();
+
+        db.storage.unlock("пароль").unwrap();
+
+        // Прочитанное расшифровывается
+        let feedback = db.storage.get_feedback(&reply.uid).unwrap().unwrap();
+        assert_eq!(feedback.comment.as_deref(), Some("SECRETCOMMENT"));
+        assert_eq!(feedback.tags, ["secrettag"]);
+        let attachments = db.storage.list_attachments(&reply.uid).unwrap();
+        assert_eq!(attachments[0].filename, "secretfile.txt");
+        let history = db.storage.load_session_history().unwrap();
+        assert_eq!(history[1].content, "SECRETMESSAGE");
+
+        // Ни в файле БД, ни в WAL не осталось открытого текста
+        let mut bytes = std::fs::read(db.dir.join("clippy.db")).unwrap();
+        bytes.extend(std::fs::read(db.dir.join("clippy.db-wal")).unwrap_or_default());
+        for secret in ["SECRETMESSAGE", "SECRETCOMMENT", "secrettag", "secretfile", "SECRETDATA", "SECRETTITLE"] {
+            assert!(
+                !bytes.windows(secret.len()).any(|w| w == secret.as_bytes()),
+                "{} остался открытым",
+                secret
+            );
+        }
+    }
+
+    #[test]
+    fn wrong_passphrase_is_rejected() {
+        let mut db = TempDb::new();
+        db.storage.unlock("верный").unwrap();
+        let path = db.dir.join("clippy.db");
+        let mut reopened = SQLiteStorage::new(Some(path)).unwrap();
+        assert!(reopened.unlock("неверный").is_err());
+        assert!(reopened.unlock("верный").is_ok());
+    }
+
+    #[test]
+    fn large_attachments_are_encrypted_into_new_files() {
+        let mut db = TempDb::new();
+        let question = message("user", "вот файл");
+        db.storage.save_messages(std::slice::from_ref(&question)).unwrap();
+        let data = vec![b'x'; attachment::INLINE_LIMIT + 1];
+        let file = NewAttachment {
+            filename: "big.bin".to_string(),
+            mime_type: "application/octet-stream".to_string(),
+            data: data.clone(),
+        };
+        let saved = db.storage.attach(&question.uid, &file).unwrap();
+        let plain = db.storage.attachments_dir.join(&saved.hash[..2]).join(&saved.hash);
+        assert!(plain.is_file());
+
+        db.storage.unlock("пароль").unwrap();
+        assert!(!plain.exists());
+        assert!(plain.with_extension("enc").is_file());
+        assert_eq!(db.storage.fetch_attachment(saved.id).unwrap().unwrap().1, data);
+
+        // Прерванное после фиксации шифрование оставило открытый файл — он удаляется
+        std::fs::write(&plain, &data).unwrap();
+        let mut reopened = SQLiteStorage::new(Some(db.dir.join("clippy.db"))).unwrap();
+        reopened.unlock("пароль").unwrap();
+        assert!(!plain.exists());
+        assert_eq!(reopened.fetch_attachment(saved.id).unwrap().unwrap().1, data);
+    }
+
+    fn old_message(role: &str, content: &str, days_ago: i64) -> PendingMessage {
+        PendingMessage {
+            timestamp: (Local::now() - chrono::Duration::days(days_ago)).to_rfc3339(),
+            ..message(role, content)
+        }
+    }
+
+    #[test]
+    fn maintenance_prunes_feedback_and_empty_sessions() {
+        let mut db = TempDb::new();
+        let imported = ImportedSession {
+            session_id: "chatgpt:old".to_string(),
+            title: None,
+            source: "chatgpt".to_string(),
+            created_at: (Local::now() - chrono::Duration::days(400)).to_rfc3339(),
+            messages: vec![ImportedMessage {
+                role: "user".to_string(),
+                content: "давний вопрос".to_string(),
+                model: "gpt".to_string(),
+                timestamp: (Local::now() - chrono::Duration::days(400)).to_rfc3339(),
+                attachments: Vec::new(),
+            }],
+        };
+        db.storage.import_session(&imported).unwrap();
+        let old = old_message("assistant", "старый ответ", 100);
+        db.storage.save_messages(&[old.clone(), message("user", "свежий вопрос")]).unwrap();
+        db.storage
+            .set_feedback(&old.uid, &Feedback { rating: 1, ..Feedback::default() })
+            .unwrap();
+
+        let policy = RetentionPolicy {
+            max_age_days: Some(30),
+            max_messages: None,
+            max_db_bytes: None,
+            trash_days: None,
+        };
+        let report = db.storage.run_maintenance(&policy).unwrap();
+        assert_eq!(report.deleted, 2);
+        assert!(db.storage.get_feedback(&old.uid).unwrap().is_none());
+
+        let sessions: Vec<String> = {
+            let mut stmt = db.storage.conn.prepare("SELECT session_id FROM sessions").unwrap();
+            let rows = stmt.query_map([], |row| row.get(0)).unwrap();
+            rows.collect::<rusqlite::Result<_>>().unwrap()
+        };
+        // Текущая сессия остаётся, импортированная без сообщений удалена
+        assert_eq!(sessions, [db.storage.session_id.clone()]);
+    }
+
+    #[test]
+    fn message_limit_counts_only_live_unpinned_messages() {
+        let mut db = TempDb::new();
+        db.storage
+            .save_messages(&[old_message("user", "закреплённое 1", 6), old_message("user", "закреплённое 2", 5)])
+            .unwrap();
+        db.storage.set_session_pinned(true).unwrap();
+
+        // Следующий запуск — новая сессия: закреплённые сообщения и корзина лимит не занимают
+        let mut current = SQLiteStorage::new(Some(db.dir.join("clippy.db"))).unwrap();
+        current.save_messages(&[old_message("user", "в корзине", 4)]).unwrap();
+        current.clear_session_history().unwrap();
+        current
+            .save_messages(&[old_message("user", "первое", 3), old_message("user", "второе", 2), old_message("user", "третье", 1)])
+            .unwrap();
+
+        let policy = RetentionPolicy {
+            max_age_days: None,
+            max_messages: Some(2),
+            max_db_bytes: None,
+            trash_days: None,
+        };
+        assert_eq!(current.run_maintenance(&policy).unwrap().deleted, 1);
+        let contents: Vec<String> = current.load_session_history().unwrap().into_iter().map(|m| m.content).collect();
+        assert_eq!(contents, ["второе", "третье"]);
+        assert_eq!(current.list_trash(10).unwrap().len(), 1);
+        assert_eq!(db.storage.load_session_history().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn age_cutoff_compares_instants_across_offsets() {
+        let mut db = TempDb::new();
+        // Текстом давнее сообщение в UTC+14 выглядит моложе границы, а недавнее в UTC−12 — старше
+        let border = Utc::now() - chrono::Duration::days(30);
+        let east = chrono::FixedOffset::east_opt(14 * 3600).unwrap();
+        let west = chrono::FixedOffset::west_opt(12 * 3600).unwrap();
+        db.storage
+            .save_messages(&[
+                PendingMessage {
+                    timestamp: (border - chrono::Duration::hours(1)).with_timezone(&east).to_rfc3339(),
+                    ..message("user", "давнее")
+                },
+                PendingMessage {
+                    timestamp: (border + chrono::Duration::hours(1)).with_timezone(&west).to_rfc3339(),
+                    ..message("user", "недавнее")
+                },
+            ])
+            .unwrap();
+
+        let policy = RetentionPolicy {
+            max_age_days: Some(30),
+            max_messages: None,
+            max_db_bytes: None,
+            trash_days: None,
+        };
+        assert_eq!(db.storage.run_maintenance(&policy).unwrap().deleted, 1);
+        let contents: Vec<String> = db.storage.load_session_history().unwrap().into_iter().map(|m| m.content).collect();
+        assert_eq!(contents, ["недавнее"]);
+    }
+
+    #[test]
+    fn maintenance_shrinks_database_to_size_limit() {
+        let mut db = TempDb::new();
+        let filler = "x".repeat(2000);
+        let batch: Vec<PendingMessage> = (0..500).map(|i| old_message("user", &filler, 500 - i)).collect();
+        db.storage.save_messages(&batch).unwrap();
+        assert!(db.storage.db_size_bytes().unwrap() > 512 * 1024);
+
+        let policy = RetentionPolicy {
+            max_age_days: None,
+            max_messages: None,
+            max_db_bytes: Some(256 * 1024),
+            trash_days: None,
+        };
+        let report = db.storage.run_maintenance(&policy).unwrap();
+        assert!(report.deleted > 0);
+        assert!(report.size_after <= 256 * 1024, "размер после обслуживания {}", report.size_after);
+    }
+}
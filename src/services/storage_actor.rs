@@ -0,0 +1,213 @@
+use anyhow::Result;
+use chrono::Local;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc as std_mpsc;
+use tokio::sync::oneshot;
+use uuid::Uuid;
+
+use super::storage::PendingMessage;
+use super::store::ConversationStore;
+
+type StorageJob = Box<dyn FnOnce(&mut dyn ConversationStore) + Send>;
+type ReadJob = Box<dyn FnOnce(&dyn ConversationStore) + Send>;
+
+enum StorageCommand {
+    /// Запись сообщения без ожидания результата (пишется пачкой)
+    Save(PendingMessage),
+    /// Произвольная операция с ответом через oneshot
+    Run(StorageJob),
+}
+
+/// Асинхронный доступ к хранилищу (`ConversationStore`), работающему в отдельном потоке.
+/// Блокирующие вызовы rusqlite не выполняются в задачах tokio и не держат
+/// мьютекс агента; подряд идущие записи объединяются в одну транзакцию.
+/// Если хранилище умеет открывать соединение только для чтения, чтение идёт
+/// через него в своём потоке и не ждёт записи, обслуживания и копирования
+#[derive(Clone)]
+pub struct StorageHandle {
+    sender: std_mpsc::Sender<StorageCommand>,
+    reader: Option<std_mpsc::Sender<ReadJob>>,
+    /// Сообщения в очереди, ещё не записанные в БД
+    unsaved: Arc<AtomicUsize>,
+}
+
+impl StorageHandle {
+    /// Переносит хранилище в выделенный поток и возвращает handle к нему
+    pub fn spawn(mut storage: Box<dyn ConversationStore>) -> Self {
+        let (sender, receiver) = std_mpsc::channel::<StorageCommand>();
+        let unsaved = Arc::new(AtomicUsize::new(0));
+        let reader = match storage.open_reader() {
+            Ok(Some(reader)) => Self::spawn_reader(reader),
+            Ok(None) => None,
+            Err(e) => {
+                log::warn!("⚠️ Соединение для чтения не открыто, чтение пойдёт через поток записи: {}", e);
+                None
+            }
+        };
+        let pending = unsaved.clone();
+
+        std::thread::Builder::new()
+            .name("clippy-storage".to_string())
+            .spawn(move || {
+                log::info!("🧵 Поток хранилища запущен");
+                while let Ok(command) = receiver.recv() {
+                    let mut batch = Vec::new();
+                    let mut next = Some(command);
+
+                    // Забираем всё, что успело накопиться, сохраняя порядок команд
+                    while let Some(command) = next.take() {
+                        match command {
+                            StorageCommand::Save(msg) => batch.push(msg),
+                            StorageCommand::Run(job) => {
+                                Self::flush(storage.as_mut(), &mut batch, &pending);
+                                job(storage.as_mut());
+                            }
+                        }
+                        next = receiver.try_recv().ok();
+                    }
+
+                    Self::flush(storage.as_mut(), &mut batch, &pending);
+                }
+                log::info!("🧵 Поток хранилища остановлен");
+            })
+            .expect("Не удалось запустить поток хранилища");
+
+        Self { sender, reader, unsaved }
+    }
+
+    fn spawn_reader(storage: Box<dyn ConversationStore>) -> Option<std_mpsc::Sender<ReadJob>> {
+        let (sender, receiver) = std_mpsc::channel::<ReadJob>();
+        let spawned = std::thread::Builder::new()
+            .name("clippy-storage-reader".to_string())
+            .spawn(move || {
+                while let Ok(job) = receiver.recv() {
+                    job(storage.as_ref());
+                }
+            });
+        match spawned {
+            Ok(_) => Some(sender),
+            Err(e) => {
+                log::warn!("⚠️ Поток чтения хранилища не запущен: {}", e);
+                None
+            }
+        }
+    }
+
+    fn flush(storage: &mut dyn ConversationStore, batch: &mut Vec<PendingMessage>, unsaved: &AtomicUsize) {
+        if batch.is_empty() {
+            return;
+        }
+        if let Err(e) = storage.save_messages(batch) {
+            log::error!("Ошибка сохранения {} сообщений в БД: {}", batch.len(), e);
+        }
+        unsaved.fetch_sub(batch.len(), Ordering::SeqCst);
+        batch.clear();
+    }
+
+    /// Ставит сообщение в очередь на запись; время фиксируется в момент вызова.
+    /// Возвращает uid, под которым сообщение будет сохранено
+    pub fn save_message(&self, role: &str, content: &str, model: &str) -> String {
+        let uid = Uuid::new_v4().to_string();
+        let msg = PendingMessage {
+            uid: uid.clone(),
+            role: role.to_string(),
+            content: content.to_string(),
+            model: model.to_string(),
+            timestamp: Local::now().to_rfc3339(),
+        };
+        self.unsaved.fetch_add(1, Ordering::SeqCst);
+        if self.sender.send(StorageCommand::Save(msg)).is_err() {
+            self.unsaved.fetch_sub(1, Ordering::SeqCst);
+            log::error!("Поток хранилища недоступен, сообщение не сохранено");
+        }
+        uid
+    }
+
+    /// Выполняет операцию в потоке хранилища и ждёт результат
+    pub async fn call<R, F>(&self, f: F) -> Result<R>
+    where
+        R: Send + 'static,
+        F: FnOnce(&mut dyn ConversationStore) -> Result<R> + Send + 'static,
+    {
+        let (tx, rx) = oneshot::channel();
+        let job: StorageJob = Box::new(move |storage| {
+            let _ = tx.send(f(storage));
+        });
+
+        self.sender
+            .send(StorageCommand::Run(job))
+            .map_err(|_| anyhow::anyhow!("Поток хранилища недоступен"))?;
+        rx.await
+            .map_err(|_| anyhow::anyhow!("Поток хранилища не вернул ответ"))?
+    }
+
+    /// Выполняет чтение через соединение только для чтения и ждёт результат.
+    /// Видит всё, что записано завершившимися `call`; пока в очереди есть
+    /// несохранённые сообщения, чтение идёт через поток записи после них
+    pub async fn read<R, F>(&self, f: F) -> Result<R>
+    where
+        R: Send + 'static,
+        F: FnOnce(&dyn ConversationStore) -> Result<R> + Send + 'static,
+    {
+        let reader = match self.reader {
+            Some(ref reader) if self.unsaved.load(Ordering::SeqCst) == 0 => reader,
+            _ => return self.call(move |storage| f(storage)).await,
+        };
+
+        let (tx, rx) = oneshot::channel();
+        let job: ReadJob = Box::new(move |storage| {
+            let _ = tx.send(f(storage));
+        });
+
+        reader
+            .send(job)
+            .map_err(|_| anyhow::anyhow!("Поток чтения хранилища недоступен"))?;
+        rx.await
+            .map_err(|_| anyhow::anyhow!("Поток чтения хранилища не вернул ответ"))?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::storage::SQLiteStorage;
+
+    #[tokio::test]
+    async fn reads_see_queued_messages_and_do_not_wait_for_writer() {
+        let dir = std::env::temp_dir().join(format!("clippy-test-{}", Uuid::new_v4()));
+        let mut storage = SQLiteStorage::new(Some(dir.join("clippy.db"))).unwrap();
+        storage.unlock("пароль").unwrap();
+        let handle = StorageHandle::spawn(Box::new(storage));
+        assert!(handle.reader.is_some());
+
+        // Только что поставленное в очередь сообщение уже видно чтению
+        handle.save_message("user", "секретный вопрос", "test");
+        let history = handle.read(|s| s.load_session_history()).await.unwrap();
+        assert_eq!(history.len(), 1);
+
+        // Поток записи занят, пока тест его не отпустит — чтение через своё соединение
+        // возвращается раньше; если бы оно ждало запись, тест бы не завершился
+        let (started_tx, started_rx) = oneshot::channel();
+        let (release_tx, release_rx) = std_mpsc::channel::<()>();
+        let writer = handle.clone();
+        let busy = tokio::spawn(async move {
+            writer
+                .call(move |_| {
+                    let _ = started_tx.send(());
+                    let _ = release_rx.recv();
+                    Ok(())
+                })
+                .await
+        });
+        started_rx.await.unwrap();
+
+        let found = handle.read(|s| s.search_messages("секретный", 5)).await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].content, "секретный вопрос");
+
+        release_tx.send(()).unwrap();
+        busy.await.unwrap().unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,587 @@
+use anyhow::Result;
+use chrono::{DateTime, Local, Utc};
+use std::collections::HashMap;
+use std::path::Path;
+use uuid::Uuid;
+
+use super::attachment::{self, Attachment, NewAttachment};
+use super::feedback::{Feedback, FeedbackAction, RatedPair};
+use super::rate_store::{InMemoryRates, RateStore};
+use super::storage::{ImportedSession, MaintenanceReport, PendingMessage, RetentionPolicy, StoredMessage, TrashEntry};
+
+/// Хранилище истории разговоров. Реализации: `SQLiteStorage` (файл на диске)
+/// и `InMemoryStore` (тесты, скрипты, сессии без следов на диске)
+pub trait ConversationStore: Send {
+    /// Сохраняет пачку сообщений текущей сессии
+    fn save_messages(&mut self, batch: &[PendingMessage]) -> Result<()>;
+
+    /// Импортирует внешнюю сессию; 0 если она уже есть
+    fn import_session(&mut self, session: &ImportedSession) -> Result<usize>;
+
+    /// Поиск по подстроке во всех сессиях (новые первыми)
+    fn search_messages(&self, query: &str, limit: usize) -> Result<Vec<StoredMessage>>;
+
+    /// История текущей сессии
+    fn load_session_history(&self) -> Result<Vec<StoredMessage>>;
+
+    /// Переносит текущую сессию в корзину; возвращает метку очистки для отмены
+    fn clear_session_history(&mut self) -> Result<String>;
+
+    /// Содержимое корзины (новые очистки первыми)
+    fn list_trash(&self, limit: usize) -> Result<Vec<TrashEntry>>;
+
+    /// Восстанавливает сообщения очистки `batch`, при необходимости только одной сессии
+    fn restore_deleted(&mut self, batch: &str, session_id: Option<&str>) -> Result<usize>;
+
+    /// Сохраняет оценку ответа по uid (пустая оценка удаляет запись)
+    fn set_feedback(&mut self, uid: &str, feedback: &Feedback) -> Result<()>;
+
+    fn get_feedback(&self, uid: &str) -> Result<Option<Feedback>>;
+
+    /// Применяет изменение к сохранённой оценке и возвращает результат
+    fn update_feedback(&mut self, uid: &str, action: &FeedbackAction) -> Result<Feedback> {
+        let mut feedback = self.get_feedback(uid)?.unwrap_or_default();
+        action.apply(&mut feedback);
+        self.set_feedback(uid, &feedback)?;
+        Ok(feedback)
+    }
+
+    /// Оценённые ответы вместе с предшествующим вопросом пользователя
+    fn rated_pairs(&self) -> Result<Vec<RatedPair>>;
+
+    /// Ответы с закладкой (новые первыми)
+    fn bookmarks(&self, limit: usize) -> Result<Vec<(StoredMessage, Feedback)>>;
+
+    /// Закреплённые сессии не удаляются политиками хранения
+    fn set_session_pinned(&mut self, pinned: bool) -> Result<()>;
+
+    /// Применяет политику хранения и оптимизирует хранилище
+    fn run_maintenance(&mut self, policy: &RetentionPolicy) -> Result<MaintenanceReport>;
+
+    /// Прикрепляет файл к сообщению; одинаковое содержимое хранится один раз
+    fn attach(&mut self, message_uid: &str, file: &NewAttachment) -> Result<Attachment>;
+
+    /// Вложения сообщения в порядке добавления
+    fn list_attachments(&self, message_uid: &str) -> Result<Vec<Attachment>>;
+
+    /// Метаданные и содержимое вложения
+    fn fetch_attachment(&self, id: i64) -> Result<Option<(Attachment, Vec<u8>)>>;
+
+    /// Курсы валют и правила уведомлений о них
+    fn rates(&self) -> &dyn RateStore;
+
+    fn rates_mut(&mut self) -> &mut dyn RateStore;
+
+    /// Отдельное соединение только для чтения, если хранилище его поддерживает.
+    /// Через него `StorageHandle::read` обслуживает чтение параллельно с записью
+    fn open_reader(&self) -> Result<Option<Box<dyn ConversationStore>>> {
+        Ok(None)
+    }
+
+    /// Заменяет содержимое хранилища копией из файла
+    fn restore_from(&mut self, path: &Path) -> Result<()>;
+}
+
+/// Хранилище в памяти процесса: ничего не пишет на диск и исчезает вместе с ним
+pub struct InMemoryStore {
+    session_id: String,
+    messages: Vec<StoredMessage>,
+    /// session_id → закреплена ли сессия
+    sessions: HashMap<String, bool>,
+    /// id сообщения → метка очистки (корзина)
+    trash: HashMap<i32, String>,
+    /// uid сообщения → оценка
+    feedback: HashMap<String, Feedback>,
+    attachments: Vec<Attachment>,
+    /// хеш → содержимое вложения
+    blobs: HashMap<String, Vec<u8>>,
+    rates: InMemoryRates,
+    next_id: i32,
+}
+
+impl InMemoryStore {
+    pub fn new() -> Self {
+        let session_id = Uuid::new_v4().to_string();
+        log::info!("📦 Хранилище в памяти, Session ID: {}", session_id);
+
+        let mut sessions = HashMap::new();
+        sessions.insert(session_id.clone(), false);
+
+        Self {
+            session_id,
+            messages: Vec::new(),
+            sessions,
+            trash: HashMap::new(),
+            feedback: HashMap::new(),
+            attachments: Vec::new(),
+            blobs: HashMap::new(),
+            rates: InMemoryRates::default(),
+            next_id: 1,
+        }
+    }
+
+    fn push(&mut self, uid: Option<&str>, session_id: &str, role: &str, content: &str, model: &str, timestamp: &str) {
+        self.messages.push(StoredMessage {
+            id: self.next_id,
+            session_id: session_id.to_string(),
+            role: role.to_string(),
+            content: content.to_string(),
+            model: model.to_string(),
+            timestamp: timestamp.to_string(),
+            uid: uid.map(str::to_string),
+        });
+        self.next_id += 1;
+    }
+
+    /// Сообщения вне корзины
+    fn live(&self) -> impl Iterator<Item = &StoredMessage> {
+        self.messages.iter().filter(|m| !self.trash.contains_key(&m.id))
+    }
+
+    /// Переносит в корзину сообщения, подходящие под фильтр
+    fn trash_where(&mut self, filter: impl Fn(&StoredMessage) -> bool) -> String {
+        let batch = Local::now().to_rfc3339();
+        let ids: Vec<i32> = self.live().filter(|m| filter(m)).map(|m| m.id).collect();
+        for id in ids {
+            self.trash.insert(id, batch.clone());
+        }
+        batch
+    }
+
+    fn is_pinned(&self, session_id: &str) -> bool {
+        self.sessions.get(session_id).copied().unwrap_or(false)
+    }
+
+    fn size_bytes(&self) -> u64 {
+        self.messages.iter().map(|m| m.content.len() as u64).sum()
+    }
+
+    /// Удаляет до `count` самых старых незакреплённых сообщений; `with_trash` — и из корзины
+    fn remove_oldest(&mut self, count: usize, with_trash: bool) -> usize {
+        let mut candidates: Vec<(Option<DateTime<Utc>>, i32)> = self
+            .messages
+            .iter()
+            .filter(|m| !self.is_pinned(&m.session_id) && (with_trash || !self.trash.contains_key(&m.id)))
+            .map(|m| (instant(&m.timestamp), m.id))
+            .collect();
+        candidates.sort();
+
+        let doomed: Vec<i32> = candidates.into_iter().take(count).map(|(_, id)| id).collect();
+        self.messages.retain(|m| !doomed.contains(&m.id));
+        self.trash.retain(|id, _| !doomed.contains(id));
+        doomed.len()
+    }
+}
+
+/// Момент времени метки RFC 3339: импортированные сообщения хранятся с разными смещениями
+fn instant(timestamp: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(timestamp).ok().map(|t| t.with_timezone(&Utc))
+}
+
+impl Default for InMemoryStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConversationStore for InMemoryStore {
+    fn save_messages(&mut self, batch: &[PendingMessage]) -> Result<()> {
+        let session_id = self.session_id.clone();
+        for msg in batch {
+            self.push(Some(&msg.uid), &session_id, &msg.role, &msg.content, &msg.model, &msg.timestamp);
+        }
+        Ok(())
+    }
+
+    fn import_session(&mut self, session: &ImportedSession) -> Result<usize> {
+        if self.sessions.contains_key(&session.session_id) {
+            return Ok(0);
+        }
+
+        self.sessions.insert(session.session_id.clone(), false);
+        for msg in &session.messages {
+            let uid = Uuid::new_v4().to_string();
+            self.push(Some(&uid), &session.session_id, &msg.role, &msg.content, &msg.model, &msg.timestamp);
+            for file in &msg.attachments {
+                self.attach(&uid, file)?;
+            }
+        }
+        Ok(session.messages.len())
+    }
+
+    fn search_messages(&self, query: &str, limit: usize) -> Result<Vec<StoredMessage>> {
+        let needle = query.trim().to_lowercase();
+        let mut found: Vec<StoredMessage> = self
+            .live()
+            .filter(|m| m.content.to_lowercase().contains(&needle))
+            .cloned()
+            .collect();
+        found.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+        found.truncate(limit);
+        Ok(found)
+    }
+
+    fn load_session_history(&self) -> Result<Vec<StoredMessage>> {
+        Ok(self
+            .live()
+            .filter(|m| m.session_id == self.session_id)
+            .cloned()
+            .collect())
+    }
+
+    fn clear_session_history(&mut self) -> Result<String> {
+        let session_id = self.session_id.clone();
+        Ok(self.trash_where(|m| m.session_id == session_id))
+    }
+
+    fn list_trash(&self, limit: usize) -> Result<Vec<TrashEntry>> {
+        let mut entries: Vec<TrashEntry> = Vec::new();
+        for m in &self.messages {
+            let Some(batch) = self.trash.get(&m.id) else {
+                continue;
+            };
+            match entries.iter_mut().find(|e| &e.batch == batch && e.session_id == m.session_id) {
+                Some(entry) => entry.count += 1,
+                None => entries.push(TrashEntry {
+                    batch: batch.clone(),
+                    session_id: m.session_id.clone(),
+                    count: 1,
+                    preview: m.content.chars().take(60).collect(),
+                }),
+            }
+        }
+
+        entries.sort_by(|a, b| b.batch.cmp(&a.batch));
+        entries.truncate(limit);
+        Ok(entries)
+    }
+
+    fn restore_deleted(&mut self, batch: &str, session_id: Option<&str>) -> Result<usize> {
+        let ids: Vec<i32> = self
+            .messages
+            .iter()
+            .filter(|m| self.trash.get(&m.id).map(String::as_str) == Some(batch))
+            .filter(|m| session_id.is_none_or(|s| m.session_id == s))
+            .map(|m| m.id)
+            .collect();
+
+        for id in &ids {
+            self.trash.remove(id);
+        }
+        Ok(ids.len())
+    }
+
+    fn set_feedback(&mut self, uid: &str, feedback: &Feedback) -> Result<()> {
+        if feedback.is_empty() {
+            self.feedback.remove(uid);
+        } else {
+            self.feedback.insert(uid.to_string(), feedback.clone());
+        }
+        Ok(())
+    }
+
+    fn get_feedback(&self, uid: &str) -> Result<Option<Feedback>> {
+        Ok(self.feedback.get(uid).cloned())
+    }
+
+    fn rated_pairs(&self) -> Result<Vec<RatedPair>> {
+        let mut pairs = Vec::new();
+        let live: Vec<&StoredMessage> = self.live().collect();
+
+        for (i, msg) in live.iter().enumerate() {
+            let Some(feedback) = msg.uid.as_ref().and_then(|uid| self.feedback.get(uid)) else {
+                continue;
+            };
+            if feedback.rating == 0 {
+                continue;
+            }
+
+            let prompt_msg = live[..i]
+                .iter()
+                .rev()
+                .find(|m| m.session_id == msg.session_id && m.role == "user");
+            let prompt = prompt_msg.map(|m| m.content.clone()).unwrap_or_default();
+
+            let mut attachments = Vec::new();
+            for uid in [prompt_msg.and_then(|m| m.uid.as_ref()), msg.uid.as_ref()].into_iter().flatten() {
+                attachments.extend(self.list_attachments(uid)?);
+            }
+
+            pairs.push(RatedPair {
+                prompt,
+                response: msg.content.clone(),
+                rating: feedback.rating,
+                comment: feedback.comment.clone(),
+                bookmarked: feedback.bookmarked,
+                tags: feedback.tags.clone(),
+                model: msg.model.clone(),
+                timestamp: msg.timestamp.clone(),
+                attachments,
+            });
+        }
+        Ok(pairs)
+    }
+
+    fn bookmarks(&self, limit: usize) -> Result<Vec<(StoredMessage, Feedback)>> {
+        let mut found: Vec<(StoredMessage, Feedback)> = self
+            .live()
+            .filter_map(|m| {
+                let feedback = self.feedback.get(m.uid.as_ref()?)?;
+                feedback.bookmarked.then(|| (m.clone(), feedback.clone()))
+            })
+            .collect();
+        found.sort_by(|a, b| b.0.timestamp.cmp(&a.0.timestamp));
+        found.truncate(limit);
+        Ok(found)
+    }
+
+    fn set_session_pinned(&mut self, pinned: bool) -> Result<()> {
+        self.sessions.insert(self.session_id.clone(), pinned);
+        Ok(())
+    }
+
+    fn run_maintenance(&mut self, policy: &RetentionPolicy) -> Result<MaintenanceReport> {
+        let size_before = self.size_bytes();
+        let total_before = self.messages.len();
+
+        if let Some(days) = policy.trash_days {
+            let cutoff = Utc::now() - chrono::Duration::days(days);
+            let expired: Vec<i32> = self
+                .trash
+                .iter()
+                .filter(|(_, b)| instant(b).is_some_and(|t| t < cutoff))
+                .map(|(id, _)| *id)
+                .collect();
+            self.messages.retain(|m| !expired.contains(&m.id));
+            self.trash.retain(|id, _| !expired.contains(id));
+        }
+
+        if let Some(days) = policy.max_age_days {
+            let cutoff = Utc::now() - chrono::Duration::days(days);
+            let pinned: Vec<String> = self.sessions.iter().filter(|(_, p)| **p).map(|(id, _)| id.clone()).collect();
+            self.messages.retain(|m| {
+                instant(&m.timestamp).is_none_or(|t| t >= cutoff) || pinned.contains(&m.session_id)
+            });
+        }
+
+        if let Some(max) = policy.max_messages {
+            // Лимит — на живые незакреплённые сообщения, как в SQLiteStorage
+            let live = self.live().filter(|m| !self.is_pinned(&m.session_id)).count();
+            self.remove_oldest(live.saturating_sub(max), false);
+        }
+
+        if let Some(max_bytes) = policy.max_db_bytes {
+            while self.size_bytes() > max_bytes {
+                if self.remove_oldest(100, true) == 0 {
+                    break;
+                }
+            }
+        }
+
+        // Вложения удалённых сообщений и содержимое без ссылок
+        let uids: std::collections::HashSet<&String> = self.messages.iter().filter_map(|m| m.uid.as_ref()).collect();
+        self.attachments.retain(|a| uids.contains(&a.message_uid));
+        let hashes: std::collections::HashSet<&String> = self.attachments.iter().map(|a| &a.hash).collect();
+        self.blobs.retain(|hash, _| hashes.contains(hash));
+
+        // Оценки удалённых сообщений и сессии, от которых ничего не осталось
+        self.feedback.retain(|uid, _| uids.contains(uid));
+        let current = &self.session_id;
+        let sessions: std::collections::HashSet<&String> = self.messages.iter().map(|m| &m.session_id).collect();
+        self.sessions
+            .retain(|id, pinned| *pinned || id == current || sessions.contains(id));
+
+        Ok(MaintenanceReport {
+            deleted: total_before - self.messages.len(),
+            size_before,
+            size_after: self.size_bytes(),
+            finished_at: Local::now().format("%H:%M").to_string(),
+        })
+    }
+
+    fn attach(&mut self, message_uid: &str, file: &NewAttachment) -> Result<Attachment> {
+        let hash = attachment::content_hash(&file.data);
+        self.blobs.entry(hash.clone()).or_insert_with(|| file.data.clone());
+
+        let attachment = Attachment {
+            id: self.attachments.last().map(|a| a.id + 1).unwrap_or(1),
+            message_uid: message_uid.to_string(),
+            mime_type: file.mime_type.clone(),
+            filename: file.filename.clone(),
+            size: file.data.len() as u64,
+            hash,
+            created_at: Local::now().to_rfc3339(),
+        };
+        self.attachments.push(attachment.clone());
+        Ok(attachment)
+    }
+
+    fn list_attachments(&self, message_uid: &str) -> Result<Vec<Attachment>> {
+        Ok(self
+            .attachments
+            .iter()
+            .filter(|a| a.message_uid == message_uid)
+            .cloned()
+            .collect())
+    }
+
+    fn fetch_attachment(&self, id: i64) -> Result<Option<(Attachment, Vec<u8>)>> {
+        Ok(self
+            .attachments
+            .iter()
+            .find(|a| a.id == id)
+            .and_then(|a| Some((a.clone(), self.blobs.get(&a.hash)?.clone()))))
+    }
+
+    fn rates(&self) -> &dyn RateStore {
+        &self.rates
+    }
+
+    fn rates_mut(&mut self) -> &mut dyn RateStore {
+        &mut self.rates
+    }
+
+    fn restore_from(&mut self, _path: &Path) -> Result<()> {
+        Err(anyhow::anyhow!("Хранилище в памяти не поддерживает резервные копии"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::feedback::FeedbackAction;
+
+    fn message(role: &str, content: &str, days_ago: i64) -> PendingMessage {
+        PendingMessage {
+            uid: Uuid::new_v4().to_string(),
+            role: role.to_string(),
+            content: content.to_string(),
+            model: "test".to_string(),
+            timestamp: (Local::now() - chrono::Duration::days(days_ago)).to_rfc3339(),
+        }
+    }
+
+    fn file(data: &[u8]) -> NewAttachment {
+        NewAttachment {
+            filename: "note.txt".to_string(),
+            mime_type: "text/plain".to_string(),
+            data: data.to_vec(),
+        }
+    }
+
+    #[test]
+    fn cleared_session_goes_to_trash_and_comes_back() {
+        let mut store = InMemoryStore::new();
+        store
+            .save_messages(&[message("user", "Привет, Скрепыш", 0), message("assistant", "Здравствуй!", 0)])
+            .unwrap();
+        assert_eq!(store.search_messages("скрепыш", 5).unwrap().len(), 1);
+
+        let batch = store.clear_session_history().unwrap();
+        assert!(store.load_session_history().unwrap().is_empty());
+        assert!(store.search_messages("скрепыш", 5).unwrap().is_empty());
+
+        let trash = store.list_trash(10).unwrap();
+        assert_eq!(trash.len(), 1);
+        assert_eq!(trash[0].count, 2);
+
+        assert_eq!(store.restore_deleted(&batch, None).unwrap(), 2);
+        assert_eq!(store.load_session_history().unwrap().len(), 2);
+        assert!(store.list_trash(10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn feedback_feeds_bookmarks_and_rated_pairs() {
+        let mut store = InMemoryStore::new();
+        let question = message("user", "Сколько будет 2+2?", 0);
+        let answer = message("assistant", "4", 0);
+        store.save_messages(&[question, answer.clone()]).unwrap();
+
+        store.update_feedback(&answer.uid, &FeedbackAction::Rate(1)).unwrap();
+        store.update_feedback(&answer.uid, &FeedbackAction::ToggleBookmark).unwrap();
+
+        let pairs = store.rated_pairs().unwrap();
+        assert_eq!(pairs.len(), 1);
+        assert_eq!(pairs[0].prompt, "Сколько будет 2+2?");
+        assert_eq!(pairs[0].rating, 1);
+        assert_eq!(store.bookmarks(10).unwrap().len(), 1);
+
+        // Пустая оценка удаляет запись
+        store.set_feedback(&answer.uid, &Feedback::default()).unwrap();
+        assert!(store.get_feedback(&answer.uid).unwrap().is_none());
+        assert!(store.bookmarks(10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn maintenance_keeps_pinned_sessions_and_drops_orphans() {
+        let mut store = InMemoryStore::new();
+        let old = message("user", "старый вопрос", 100);
+        let fresh = message("user", "свежий вопрос", 0);
+        store.save_messages(&[old.clone(), fresh.clone()]).unwrap();
+        store.attach(&old.uid, &file(b"same")).unwrap();
+        let kept = store.attach(&fresh.uid, &file(b"same")).unwrap();
+        store.update_feedback(&old.uid, &FeedbackAction::Rate(-1)).unwrap();
+        assert_eq!(store.blobs.len(), 1);
+
+        let policy = RetentionPolicy {
+            max_age_days: Some(30),
+            ..Default::default()
+        };
+
+        store.set_session_pinned(true).unwrap();
+        assert_eq!(store.run_maintenance(&policy).unwrap().deleted, 0);
+
+        store.set_session_pinned(false).unwrap();
+        let report = store.run_maintenance(&policy).unwrap();
+        assert_eq!(report.deleted, 1);
+        assert!(store.get_feedback(&old.uid).unwrap().is_none());
+        assert!(store.list_attachments(&old.uid).unwrap().is_empty());
+
+        // Общее содержимое остаётся, пока на него ссылается живое вложение
+        let (_, data) = store.fetch_attachment(kept.id).unwrap().unwrap();
+        assert_eq!(data, b"same");
+    }
+
+    #[test]
+    fn maintenance_trims_to_message_limit_oldest_first() {
+        let mut store = InMemoryStore::new();
+        store
+            .save_messages(&[message("user", "первое", 3), message("user", "второе", 2), message("user", "третье", 1)])
+            .unwrap();
+
+        let policy = RetentionPolicy {
+            max_messages: Some(2),
+            ..Default::default()
+        };
+        store.run_maintenance(&policy).unwrap();
+
+        let contents: Vec<String> = store.load_session_history().unwrap().into_iter().map(|m| m.content).collect();
+        assert_eq!(contents, ["второе", "третье"]);
+    }
+
+    #[test]
+    fn message_limit_skips_pinned_and_trashed_messages() {
+        let mut store = InMemoryStore::new();
+        store
+            .save_messages(&[message("user", "закреплённое 1", 6), message("user", "закреплённое 2", 5)])
+            .unwrap();
+        store.set_session_pinned(true).unwrap();
+
+        // Новая сессия с сообщением в корзине и тремя живыми
+        store.session_id = Uuid::new_v4().to_string();
+        store.save_messages(&[message("user", "в корзине", 4)]).unwrap();
+        store.clear_session_history().unwrap();
+        store
+            .save_messages(&[message("user", "первое", 3), message("user", "второе", 2), message("user", "третье", 1)])
+            .unwrap();
+
+        let policy = RetentionPolicy {
+            max_messages: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(store.run_maintenance(&policy).unwrap().deleted, 1);
+        let contents: Vec<String> = store.load_session_history().unwrap().into_iter().map(|m| m.content).collect();
+        assert_eq!(contents, ["второе", "третье"]);
+        assert_eq!(store.list_trash(10).unwrap().len(), 1);
+    }
+}
@@ -1,12 +1,234 @@
 use anyhow::Result;
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
 use serde::{Deserialize, Serialize};
 
+use super::cache::{Cached, DiskCache, WEATHER_TTL};
+use super::location::Place;
+use crate::config::Config;
+use super::wmo;
+
+/// Сколько часов почасового прогноза хранить (от текущего часа)
+const HOURLY_HOURS: usize = 24;
+/// Запас часов в кэше: без сети прогноз продолжает сдвигаться ещё полдня
+const CACHED_HOURS: usize = 12;
+/// Дней в дневном прогнозе
+const FORECAST_DAYS: usize = 7;
+
+/// Единица температуры (CLIPPY_TEMPERATURE_UNIT: celsius | fahrenheit)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum TemperatureUnit {
+    #[default]
+    Celsius,
+    Fahrenheit,
+}
+
+/// Единица скорости ветра (CLIPPY_WIND_UNIT: ms | kmh)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum WindUnit {
+    #[default]
+    MetersPerSecond,
+    KilometersPerHour,
+}
+
+/// Единица давления (CLIPPY_PRESSURE_UNIT: hpa | mmhg)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum PressureUnit {
+    #[default]
+    Hectopascal,
+    MillimetersOfMercury,
+}
+
+impl TemperatureUnit {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "c" | "celsius" | "с" => Some(Self::Celsius),
+            "f" | "fahrenheit" => Some(Self::Fahrenheit),
+            _ => None,
+        }
+    }
+}
+
+impl WindUnit {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "ms" | "m/s" | "м/с" => Some(Self::MetersPerSecond),
+            "kmh" | "km/h" | "км/ч" => Some(Self::KilometersPerHour),
+            _ => None,
+        }
+    }
+}
+
+impl PressureUnit {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "hpa" | "гпа" => Some(Self::Hectopascal),
+            "mmhg" | "мм" => Some(Self::MillimetersOfMercury),
+            _ => None,
+        }
+    }
+}
+
+/// Единицы отображения погоды. Температура и ветер запрашиваются у Open-Meteo
+/// сразу в нужных единицах, давление всегда приходит в гПа и пересчитывается при выводе
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct WeatherUnits {
+    pub temperature: TemperatureUnit,
+    pub wind: WindUnit,
+    pub pressure: PressureUnit,
+}
+
+impl WeatherUnits {
+    /// Параметры единиц для запроса к Open-Meteo
+    fn query(&self) -> String {
+        let temperature = match self.temperature {
+            TemperatureUnit::Celsius => "celsius",
+            TemperatureUnit::Fahrenheit => "fahrenheit",
+        };
+        let wind = match self.wind {
+            WindUnit::MetersPerSecond => "ms",
+            WindUnit::KilometersPerHour => "kmh",
+        };
+        format!("temperature_unit={}&wind_speed_unit={}", temperature, wind)
+    }
+
+    /// Температура в °C (для порогов, не зависящих от единиц)
+    pub fn celsius(&self, value: f32) -> f32 {
+        match self.temperature {
+            TemperatureUnit::Celsius => value,
+            TemperatureUnit::Fahrenheit => (value - 32.0) * 5.0 / 9.0,
+        }
+    }
+
+    /// Скорость ветра в м/с
+    pub fn meters_per_second(&self, value: f32) -> f32 {
+        match self.wind {
+            WindUnit::MetersPerSecond => value,
+            WindUnit::KilometersPerHour => value / 3.6,
+        }
+    }
+
+    /// "+12°C"
+    pub fn temperature(&self, value: f32) -> String {
+        let unit = match self.temperature {
+            TemperatureUnit::Celsius => "°C",
+            TemperatureUnit::Fahrenheit => "°F",
+        };
+        format!("{:+.0}{}", value, unit)
+    }
+
+    /// "4 м/с"
+    pub fn wind(&self, value: f32) -> String {
+        match self.wind {
+            WindUnit::MetersPerSecond => format!("{:.0} м/с", value),
+            WindUnit::KilometersPerHour => format!("{:.0} км/ч", value),
+        }
+    }
+
+    /// Давление из гПа в выбранных единицах: "1013 гПа" или "760 мм рт. ст."
+    pub fn pressure(&self, hpa: f32) -> String {
+        match self.pressure {
+            PressureUnit::Hectopascal => format!("{:.0} гПа", hpa),
+            PressureUnit::MillimetersOfMercury => format!("{:.0} мм рт. ст.", hpa * 0.750_062),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WeatherInfo {
     pub city: String,
-    pub temperature: i32,
+    pub temperature: f32,
+    /// Ощущается как
+    pub apparent_temperature: f32,
     pub description: String,
+    pub weather_code: i32,
+    pub is_day: bool,
     pub humidity: i32,
+    pub wind_speed: f32,
+    /// Откуда дует ветер, градусы (0 — север)
+    pub wind_direction: i32,
+    /// Давление на уровне моря, гПа
+    pub pressure_hpa: f32,
+    /// Облачность, %
+    pub cloud_cover: i32,
+    pub uv_index: f32,
+    /// Осадки за последний час, мм
+    pub precipitation: f32,
+    pub units: WeatherUnits,
+}
+
+impl WeatherInfo {
+    pub fn icon(&self) -> &'static str {
+        wmo::icon(self.weather_code, self.is_day)
+    }
+}
+
+/// Прогноз на час
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HourlyForecast {
+    pub time: NaiveDateTime,
+    pub temperature: f32,
+    /// Вероятность осадков, %
+    pub precipitation_probability: i32,
+    pub weather_code: i32,
+    pub is_day: bool,
+    pub wind_speed: f32,
+}
+
+/// Прогноз на день
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DailyForecast {
+    pub date: NaiveDate,
+    pub temperature_min: f32,
+    pub temperature_max: f32,
+    /// Максимальная за день вероятность осадков, %
+    pub precipitation_probability: i32,
+    /// Сумма осадков, мм
+    pub precipitation_sum: f32,
+    pub weather_code: i32,
+    pub wind_speed_max: f32,
+    pub uv_index_max: f32,
+    pub sunrise: Option<NaiveTime>,
+    pub sunset: Option<NaiveTime>,
+}
+
+/// Текущая погода с почасовым (24 ч) и дневным (7 дней) прогнозом
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Forecast {
+    pub current: WeatherInfo,
+    pub hourly: Vec<HourlyForecast>,
+    pub daily: Vec<DailyForecast>,
+    pub units: WeatherUnits,
+    /// Смещение часового пояса города от UTC: время в прогнозе местное для города.
+    /// `None` — прогноз из кэша старой версии
+    #[serde(default)]
+    pub utc_offset_seconds: Option<i32>,
+}
+
+impl Forecast {
+    /// Текущее время в городе прогноза
+    pub fn local_now(&self) -> NaiveDateTime {
+        match self.utc_offset_seconds {
+            Some(offset) => city_now(offset),
+            None => chrono::Local::now().naive_local(),
+        }
+    }
+
+    /// Сегодняшняя дата в городе прогноза
+    pub fn today(&self) -> NaiveDate {
+        self.local_now().date()
+    }
+
+    /// Прогноз на дату, если она входит в 7 дней
+    pub fn day(&self, date: NaiveDate) -> Option<&DailyForecast> {
+        self.daily.iter().find(|d| d.date == date)
+    }
+}
+
+impl DailyForecast {
+    /// Ожидаются ли осадки: код WMO с дождём/снегом или вероятность от 50%
+    pub fn is_wet(&self) -> bool {
+        wmo::is_precipitation(self.weather_code) || self.precipitation_probability >= 50
+    }
 }
 
 // Response structures for Open-Meteo API
@@ -15,112 +237,273 @@ struct OpenMeteoResponse {
     current: CurrentWeather,
 }
 
+/// Переменные блока `current`, которые запрашиваются у Open-Meteo
+const CURRENT_FIELDS: &str = "temperature_2m,apparent_temperature,relative_humidity_2m,weather_code,is_day,\
+    wind_speed_10m,wind_direction_10m,pressure_msl,cloud_cover,uv_index,precipitation";
+
 #[derive(Debug, Deserialize)]
-struct CurrentWeather {
-    temperature_2m: f32,
-    relative_humidity_2m: i32,
-    weather_code: i32,
+struct OpenMeteoForecastResponse {
+    /// Смещение часового пояса города (timezone=auto)
+    utc_offset_seconds: i32,
+    current: CurrentWeather,
+    hourly: HourlyBlock,
+    daily: DailyBlock,
 }
 
-// Geocoding response for city coordinates
+/// Open-Meteo отдаёт прогноз столбцами; значения могут отсутствовать (null)
 #[derive(Debug, Deserialize)]
-struct GeocodingResponse {
-    results: Option<Vec<GeocodingResult>>,
+struct HourlyBlock {
+    time: Vec<String>,
+    temperature_2m: Vec<Option<f32>>,
+    precipitation_probability: Vec<Option<i32>>,
+    weather_code: Vec<Option<i32>>,
+    is_day: Vec<Option<i32>>,
+    wind_speed_10m: Vec<Option<f32>>,
 }
 
 #[derive(Debug, Deserialize)]
-struct GeocodingResult {
-    latitude: f32,
-    longitude: f32,
-    name: String,
-    admin1: Option<String>,
-    country: Option<String>,
+struct DailyBlock {
+    time: Vec<String>,
+    weather_code: Vec<Option<i32>>,
+    temperature_2m_max: Vec<Option<f32>>,
+    temperature_2m_min: Vec<Option<f32>>,
+    precipitation_probability_max: Vec<Option<i32>>,
+    precipitation_sum: Vec<Option<f32>>,
+    wind_speed_10m_max: Vec<Option<f32>>,
+    uv_index_max: Vec<Option<f32>>,
+    sunrise: Vec<Option<String>>,
+    sunset: Vec<Option<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CurrentWeather {
+    temperature_2m: f32,
+    apparent_temperature: Option<f32>,
+    relative_humidity_2m: i32,
+    weather_code: i32,
+    is_day: Option<i32>,
+    wind_speed_10m: Option<f32>,
+    wind_direction_10m: Option<i32>,
+    pressure_msl: Option<f32>,
+    cloud_cover: Option<i32>,
+    uv_index: Option<f32>,
+    precipitation: Option<f32>,
 }
 
 /// Сервис для получения информации о погоде через Open-Meteo API
 pub struct WeatherService {
     http_client: reqwest::Client,
+    cache: DiskCache,
+    units: WeatherUnits,
 }
 
 impl WeatherService {
-    pub fn new() -> Self {
+    pub fn from_config(config: &Config) -> Self {
         Self {
             http_client: reqwest::Client::new(),
+            cache: DiskCache::from_config(config),
+            units: config.weather_units,
         }
     }
 
+    /// Ключ кэша: место и единицы (в других единицах данные другие)
+    fn cache_key(&self, kind: &str, place: &Place) -> String {
+        format!("{}:{:.3},{:.3}:{:?}", kind, place.latitude, place.longitude, self.units)
+    }
+
     /// Преобразует WMO код погоды в описание
-    fn weather_code_to_description(&self, code: i32) -> String {
-        match code {
-            0 => "Ясно".to_string(),
-            1 | 2 => "Облачно".to_string(),
-            3 => "Пасмурно".to_string(),
-            45 | 48 => "Туман".to_string(),
-            51 | 53 | 55 => "Морось".to_string(),
-            61 | 63 | 65 => "Дождь".to_string(),
-            71 | 73 | 75 => "Снег".to_string(),
-            77 => "Снег".to_string(),
-            80 | 82 | 81 => "Ливень".to_string(),
-            85 | 86 => "Снегопад".to_string(),
-            95 | 96 | 99 => "Гроза".to_string(),
-            _ => "Неизвестно".to_string(),
-        }
+    pub fn weather_code_to_description(code: i32) -> String {
+        wmo::description(code).to_string()
     }
 
-    /// Получает координаты города через Geocoding API
-    async fn get_city_coordinates(&self, city: &str) -> Result<(f32, f32, String)> {
+    /// Текущая погода для места (из кэша, если она свежая)
+    pub async fn get_weather(&self, place: &Place) -> Result<Cached<WeatherInfo>> {
+        let key = self.cache_key("weather", place);
+        self.cache
+            .get_or_fetch(&key, WEATHER_TTL, || self.fetch_weather(place))
+            .await
+    }
+
+    /// Получает информацию о погоде для места через Open-Meteo API
+    async fn fetch_weather(&self, place: &Place) -> Result<WeatherInfo> {
+        // Запрашиваем данные погоды
         let url = format!(
-            "https://geocoding-api.open-meteo.com/v1/search?name={}&count=1&language=ru&format=json",
-            urlencoding::encode(city)
+            "https://api.open-meteo.com/v1/forecast?latitude={}&longitude={}&current={}&{}&timezone=auto",
+            place.latitude,
+            place.longitude,
+            CURRENT_FIELDS,
+            self.units.query()
         );
 
         let response = self.http_client.get(&url).send().await?;
-        let geo_response: GeocodingResponse = response.json().await?;
-
-        if let Some(mut results) = geo_response.results {
-            if !results.is_empty() {
-                let result = results.remove(0);
-                Ok((result.latitude, result.longitude, result.name))
-            } else {
-                Err(anyhow::anyhow!("Город '{}' не найден", city))
-            }
-        } else {
-            Err(anyhow::anyhow!("Ошибка при поиске города '{}'", city))
+        let weather_response: OpenMeteoResponse = response.json().await?;
+
+        Ok(self.current_info(place.name.clone(), weather_response.current))
+    }
+
+    fn current_info(&self, city: String, current: CurrentWeather) -> WeatherInfo {
+        WeatherInfo {
+            city,
+            temperature: current.temperature_2m,
+            apparent_temperature: current.apparent_temperature.unwrap_or(current.temperature_2m),
+            description: Self::weather_code_to_description(current.weather_code),
+            weather_code: current.weather_code,
+            is_day: current.is_day.unwrap_or(1) == 1,
+            humidity: current.relative_humidity_2m,
+            wind_speed: current.wind_speed_10m.unwrap_or(0.0),
+            wind_direction: current.wind_direction_10m.unwrap_or(0),
+            pressure_hpa: current.pressure_msl.unwrap_or(0.0),
+            cloud_cover: current.cloud_cover.unwrap_or(0),
+            uv_index: current.uv_index.unwrap_or(0.0),
+            precipitation: current.precipitation.unwrap_or(0.0),
+            units: self.units,
         }
     }
 
-    /// Получает информацию о погоде для города через Open-Meteo API
-    pub async fn get_weather(&self, city: &str) -> Result<WeatherInfo> {
-        // Получаем координаты города
-        let (latitude, longitude, city_name) = self.get_city_coordinates(city).await?;
+    /// Текущая погода и прогноз (из кэша, если они свежие)
+    pub async fn get_forecast(&self, place: &Place) -> Result<Cached<Forecast>> {
+        let key = self.cache_key("forecast", place);
+        let mut forecast = self
+            .cache
+            .get_or_fetch(&key, WEATHER_TTL, || self.fetch_forecast(place))
+            .await?;
 
-        // Запрашиваем данные погоды
+        // Почасовой прогноз из кэша начинаем с текущего часа в городе
+        let now = forecast.value.local_now();
+        forecast.value.hourly.retain(|h| h.time + chrono::Duration::hours(1) > now);
+        forecast.value.hourly.truncate(HOURLY_HOURS);
+        Ok(forecast)
+    }
+
+    /// Получает текущую погоду и прогноз: почасовой на сутки и дневной на неделю
+    async fn fetch_forecast(&self, place: &Place) -> Result<Forecast> {
         let url = format!(
-            "https://api.open-meteo.com/v1/forecast?latitude={}&longitude={}&current=temperature_2m,relative_humidity_2m,weather_code&temperature_unit=celsius&timezone=auto",
-            latitude, longitude
+            "https://api.open-meteo.com/v1/forecast?latitude={}&longitude={}\
+             &current={}\
+             &hourly=temperature_2m,precipitation_probability,weather_code,is_day,wind_speed_10m\
+             &daily=weather_code,temperature_2m_max,temperature_2m_min,precipitation_probability_max,\
+             precipitation_sum,wind_speed_10m_max,uv_index_max,sunrise,sunset\
+             &forecast_days={}&{}&timezone=auto",
+            place.latitude,
+            place.longitude,
+            CURRENT_FIELDS,
+            FORECAST_DAYS,
+            self.units.query()
         );
 
         let response = self.http_client.get(&url).send().await?;
-        let weather_response: OpenMeteoResponse = response.json().await?;
+        let data: OpenMeteoForecastResponse = response.json().await?;
 
-        let current = weather_response.current;
-        let description = self.weather_code_to_description(current.weather_code);
+        let current = self.current_info(place.name.clone(), data.current);
 
-        Ok(WeatherInfo {
-            city: city_name,
-            temperature: current.temperature_2m as i32,
-            description,
-            humidity: current.relative_humidity_2m,
+        // Время в ответе местное для города (timezone=auto); начинаем с текущего часа
+        let now = city_now(data.utc_offset_seconds);
+        let hourly = parse_hourly(&data.hourly)
+            .into_iter()
+            .skip_while(|h| h.time + chrono::Duration::hours(1) <= now)
+            .take(HOURLY_HOURS + CACHED_HOURS)
+            .collect();
+
+        Ok(Forecast {
+            current,
+            hourly,
+            daily: parse_daily(&data.daily),
+            units: self.units,
+            utc_offset_seconds: Some(data.utc_offset_seconds),
         })
     }
 
     /// Форматирует информацию о погоде в читаемый текст
-    pub async fn format_weather_info(&self, city: &str) -> Result<String> {
-        let weather = self.get_weather(city).await?;
-        let result = format!(
-            "🌍 Погода в городе {}:\n• 🌡️ Температура: {}°C\n• ☁️ Условия: {}\n• 💧 Влажность: {}%",
-            weather.city, weather.temperature, weather.description, weather.humidity
+    pub async fn format_weather_info(&self, place: &Place) -> Result<String> {
+        let weather = self.get_weather(place).await?;
+        let mut text = Self::format_weather(&weather.value);
+        if let Some(note) = weather.stale_note() {
+            text.push_str(&format!("\n{}", note));
+        }
+        Ok(text)
+    }
+
+    /// Текст о текущей погоде для ответа в чате
+    pub fn format_weather(weather: &WeatherInfo) -> String {
+        let units = &weather.units;
+        let mut text = format!(
+            "🌍 Погода в городе {}:\n• 🌡️ Температура: {} (ощущается как {})\n• {} Условия: {}, облачность {}%\n\
+             • 💧 Влажность: {}%\n• 💨 Ветер: {} {}\n• 🧭 Давление: {}",
+            weather.city,
+            units.temperature(weather.temperature),
+            units.temperature(weather.apparent_temperature),
+            weather.icon(),
+            weather.description,
+            weather.cloud_cover,
+            weather.humidity,
+            wind_direction_name(weather.wind_direction),
+            units.wind(weather.wind_speed),
+            units.pressure(weather.pressure_hpa)
         );
-        Ok(result)
+        if weather.precipitation > 0.0 {
+            text.push_str(&format!("\n• ☔ Осадки: {:.1} мм/ч", weather.precipitation));
+        }
+        if weather.is_day {
+            text.push_str(&format!("\n• 🔆 УФ-индекс: {:.0}", weather.uv_index));
+        }
+        text
     }
 }
+
+/// Направление ветра по румбам: откуда дует
+pub fn wind_direction_name(degrees: i32) -> &'static str {
+    const NAMES: [&str; 8] = ["С", "СВ", "В", "ЮВ", "Ю", "ЮЗ", "З", "СЗ"];
+    NAMES[((degrees.rem_euclid(360) as f32 + 22.5) / 45.0) as usize % 8]
+}
+
+/// Текущее время в часовом поясе со смещением `offset_seconds` от UTC
+fn city_now(offset_seconds: i32) -> NaiveDateTime {
+    chrono::Utc::now().naive_utc() + chrono::Duration::seconds(offset_seconds.into())
+}
+
+fn parse_hourly(block: &HourlyBlock) -> Vec<HourlyForecast> {
+    block
+        .time
+        .iter()
+        .enumerate()
+        .filter_map(|(i, time)| {
+            Some(HourlyForecast {
+                time: NaiveDateTime::parse_from_str(time, "%Y-%m-%dT%H:%M").ok()?,
+                temperature: (*block.temperature_2m.get(i)?)?,
+                precipitation_probability: block.precipitation_probability.get(i).copied().flatten().unwrap_or(0),
+                weather_code: block.weather_code.get(i).copied().flatten().unwrap_or(0),
+                is_day: block.is_day.get(i).copied().flatten().unwrap_or(1) == 1,
+                wind_speed: block.wind_speed_10m.get(i).copied().flatten().unwrap_or(0.0),
+            })
+        })
+        .collect()
+}
+
+fn parse_daily(block: &DailyBlock) -> Vec<DailyForecast> {
+    let time_of = |value: Option<&Option<String>>| {
+        value
+            .and_then(|v| v.as_deref())
+            .and_then(|v| NaiveDateTime::parse_from_str(v, "%Y-%m-%dT%H:%M").ok())
+            .map(|dt| dt.time())
+    };
+
+    block
+        .time
+        .iter()
+        .enumerate()
+        .filter_map(|(i, date)| {
+            Some(DailyForecast {
+                date: NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?,
+                temperature_min: (*block.temperature_2m_min.get(i)?)?,
+                temperature_max: (*block.temperature_2m_max.get(i)?)?,
+                precipitation_probability: block.precipitation_probability_max.get(i).copied().flatten().unwrap_or(0),
+                precipitation_sum: block.precipitation_sum.get(i).copied().flatten().unwrap_or(0.0),
+                weather_code: block.weather_code.get(i).copied().flatten().unwrap_or(0),
+                wind_speed_max: block.wind_speed_10m_max.get(i).copied().flatten().unwrap_or(0.0),
+                uv_index_max: block.uv_index_max.get(i).copied().flatten().unwrap_or(0.0),
+                sunrise: time_of(block.sunrise.get(i)),
+                sunset: time_of(block.sunset.get(i)),
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,276 @@
+use chrono::{Duration, Local, NaiveDate, Timelike};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::cache::DiskCache;
+use super::weather::{Forecast, HourlyForecast};
+use super::wmo;
+use crate::config::Config;
+
+/// Насколько вперёд предупреждать об осадках (часы)
+const PRECIPITATION_LOOKAHEAD_HOURS: usize = 6;
+/// Насколько вперёд смотреть на заморозки, жару, грозы и ветер (часы)
+const ALERT_LOOKAHEAD_HOURS: usize = 24;
+/// Ключ списка показанных предупреждений в кэше
+const SHOWN_KEY: &str = "weather-alerts:shown";
+
+/// Пороги и тихие часы предупреждений о погоде. Пороги всегда в °C и м/с,
+/// независимо от единиц отображения
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AlertSettings {
+    /// CLIPPY_WEATHER_ALERTS=false выключает предупреждения
+    pub enabled: bool,
+    /// Заморозки: температура не выше, °C
+    pub frost_c: f32,
+    /// Жара: температура не ниже, °C
+    pub heat_c: f32,
+    /// Вероятность осадков, начиная с которой предупреждать, %
+    pub precipitation_probability: i32,
+    /// Сильный ветер, м/с
+    pub wind_ms: f32,
+    /// Тихие часы (начало, конец): "23-8" — с 23:00 до 08:00
+    pub quiet_hours: Option<(u32, u32)>,
+    /// Озвучивать предупреждения
+    pub speak: bool,
+}
+
+impl Default for AlertSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            frost_c: 0.0,
+            heat_c: 30.0,
+            precipitation_probability: 60,
+            wind_ms: 15.0,
+            quiet_hours: Some((23, 8)),
+            speak: false,
+        }
+    }
+}
+
+impl AlertSettings {
+    /// Тихие часы из строки "23-8"; "off" или пустая строка — без тихих часов
+    pub fn parse_quiet_hours(value: &str) -> Option<Option<(u32, u32)>> {
+        let value = value.trim();
+        if value.is_empty() || value.eq_ignore_ascii_case("off") {
+            return Some(None);
+        }
+        let (start, end) = value.split_once('-')?;
+        let start = start.trim().parse::<u32>().ok().filter(|h| *h < 24)?;
+        let end = end.trim().parse::<u32>().ok().filter(|h| *h <= 24)?;
+        Some(Some((start, end % 24)))
+    }
+
+    /// Попадает ли час в тихие часы (интервал может переходить через полночь)
+    pub fn is_quiet(&self, hour: u32) -> bool {
+        match self.quiet_hours {
+            Some((start, end)) if start <= end => hour >= start && hour < end,
+            Some((start, end)) => hour >= start || hour < end,
+            None => false,
+        }
+    }
+
+    /// Тихие ли часы сейчас (по часам компьютера — это время пользователя, а не города прогноза)
+    pub fn is_quiet_now(&self) -> bool {
+        self.is_quiet(Local::now().hour())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertKind {
+    Precipitation,
+    Frost,
+    Heat,
+    Storm,
+    Wind,
+}
+
+impl AlertKind {
+    fn key(&self) -> &'static str {
+        match self {
+            Self::Precipitation => "precipitation",
+            Self::Frost => "frost",
+            Self::Heat => "heat",
+            Self::Storm => "storm",
+            Self::Wind => "wind",
+        }
+    }
+}
+
+/// Предупреждение для облачка рядом со Скрепышом
+#[derive(Debug, Clone, PartialEq)]
+pub struct WeatherAlert {
+    pub kind: AlertKind,
+    /// День события: одно предупреждение каждого вида на день
+    pub date: NaiveDate,
+    pub text: String,
+}
+
+impl WeatherAlert {
+    pub fn key(&self) -> String {
+        format!("{}:{}", self.kind.key(), self.date)
+    }
+}
+
+/// Предупреждения, которые следуют из прогноза (без учёта уже показанных)
+pub fn detect(forecast: &Forecast, settings: &AlertSettings) -> Vec<WeatherAlert> {
+    let units = forecast.units;
+    let today = forecast.today();
+    let upcoming = &forecast.hourly[..forecast.hourly.len().min(ALERT_LOOKAHEAD_HOURS)];
+    let mut alerts = Vec::new();
+
+    // Гроза важнее обычных осадков
+    if let Some(hour) = upcoming.iter().find(|h| matches!(h.weather_code, 95..=99)) {
+        alerts.push(WeatherAlert {
+            kind: AlertKind::Storm,
+            date: hour.time.date(),
+            text: format!("⛈ {}: {}. Лучше переждать под крышей", when(hour, today), wmo::description(hour.weather_code).to_lowercase()),
+        });
+    } else if !wmo::is_precipitation(forecast.current.weather_code) {
+        // Если осадки уже идут, предупреждать поздно
+        let soon = upcoming.iter().take(PRECIPITATION_LOOKAHEAD_HOURS).find(|h| {
+            wmo::is_precipitation(h.weather_code) && h.precipitation_probability >= settings.precipitation_probability
+        });
+        if let Some(hour) = soon {
+            alerts.push(WeatherAlert {
+                kind: AlertKind::Precipitation,
+                date: hour.time.date(),
+                text: format!(
+                    "☔ {}: {} (вероятность {}%). Не забудь зонт!",
+                    when(hour, today),
+                    wmo::description(hour.weather_code).to_lowercase(),
+                    hour.precipitation_probability
+                ),
+            });
+        }
+    }
+
+    let coldest = upcoming.iter().min_by(|a, b| a.temperature.total_cmp(&b.temperature));
+    if let Some(hour) = coldest.filter(|h| units.celsius(h.temperature) <= settings.frost_c) {
+        alerts.push(WeatherAlert {
+            kind: AlertKind::Frost,
+            date: hour.time.date(),
+            text: format!("🥶 {}: до {} — возможны заморозки и гололёд", when(hour, today), units.temperature(hour.temperature)),
+        });
+    }
+
+    let hottest = upcoming.iter().max_by(|a, b| a.temperature.total_cmp(&b.temperature));
+    if let Some(hour) = hottest.filter(|h| units.celsius(h.temperature) >= settings.heat_c) {
+        alerts.push(WeatherAlert {
+            kind: AlertKind::Heat,
+            date: hour.time.date(),
+            text: format!("🥵 {}: жара до {}. Пей больше воды", when(hour, today), units.temperature(hour.temperature)),
+        });
+    }
+
+    let windiest = upcoming.iter().max_by(|a, b| a.wind_speed.total_cmp(&b.wind_speed));
+    if let Some(hour) = windiest.filter(|h| units.meters_per_second(h.wind_speed) >= settings.wind_ms) {
+        alerts.push(WeatherAlert {
+            kind: AlertKind::Wind,
+            date: hour.time.date(),
+            text: format!("💨 {}: сильный ветер до {}", when(hour, today), units.wind(hour.wind_speed)),
+        });
+    }
+
+    alerts
+}
+
+/// "Сегодня в 15:00" / "Завтра в 03:00"; `today` — сегодняшняя дата в городе прогноза
+fn when(hour: &HourlyForecast, today: NaiveDate) -> String {
+    let day = if hour.time.date() == today {
+        "Сегодня"
+    } else {
+        "Завтра"
+    };
+    format!("{} в {:02}:00", day, hour.time.hour())
+}
+
+/// Показанные предупреждения: ключ → день события
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ShownAlerts {
+    keys: HashMap<String, NaiveDate>,
+}
+
+/// Отбирает новые предупреждения из прогноза: показанные не повторяются
+/// (список хранится в кэше профиля и переживает перезапуск). Показанным
+/// предупреждение считается, когда облачко с ним появилось на экране, —
+/// до этого оно может ждать в очереди, например, конца тихих часов
+pub struct WeatherAlerts {
+    settings: AlertSettings,
+    cache: DiskCache,
+}
+
+impl WeatherAlerts {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            settings: config.weather_alerts,
+            cache: DiskCache::from_config(config),
+        }
+    }
+
+    /// Предупреждения из прогноза, которые ещё не показывались
+    pub fn check(&self, forecast: &Forecast) -> Vec<WeatherAlert> {
+        if !self.settings.enabled {
+            return Vec::new();
+        }
+
+        let shown = self.shown();
+        let alerts: Vec<WeatherAlert> = detect(forecast, &self.settings)
+            .into_iter()
+            .filter(|alert| !shown.keys.contains_key(&alert.key()))
+            .collect();
+        if !alerts.is_empty() {
+            log::info!("🌩️ Новых предупреждений о погоде: {}", alerts.len());
+        }
+        alerts
+    }
+
+    /// Запоминает, что предупреждение показано
+    pub fn mark_shown(&self, alert: &WeatherAlert) {
+        let mut shown = self.shown();
+        // Предупреждения бывают только на сегодня и завтра: всё, что раньше
+        // вчерашнего дня события, уже прошло
+        let yesterday = alert.date - Duration::days(1);
+        shown.keys.retain(|_, date| *date >= yesterday);
+        shown.keys.insert(alert.key(), alert.date);
+        if let Err(e) = self.cache.write(SHOWN_KEY, &shown) {
+            log::warn!("⚠️ Не удалось сохранить показанные предупреждения: {}", e);
+        }
+    }
+
+    fn shown(&self) -> ShownAlerts {
+        self.cache
+            .read::<ShownAlerts>(SHOWN_KEY)
+            .map(|(shown, _)| shown)
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn day_is_named_by_the_city_date() {
+        let today = NaiveDate::from_ymd_opt(2026, 10, 14).unwrap();
+        let hour = |day: u32, hour: u32| HourlyForecast {
+            time: NaiveDate::from_ymd_opt(2026, 10, day).unwrap().and_hms_opt(hour, 0, 0).unwrap(),
+            temperature: 10.0,
+            precipitation_probability: 0,
+            weather_code: 0,
+            is_day: true,
+            wind_speed: 0.0,
+        };
+        assert_eq!(when(&hour(14, 15), today), "Сегодня в 15:00");
+        assert_eq!(when(&hour(15, 3), today), "Завтра в 03:00");
+    }
+
+    #[test]
+    fn quiet_hours_wrap_around_midnight() {
+        let settings = AlertSettings::default();
+        assert!(settings.is_quiet(23));
+        assert!(settings.is_quiet(7));
+        assert!(!settings.is_quiet(8));
+        assert_eq!(AlertSettings::parse_quiet_hours("off"), Some(None));
+    }
+}
@@ -1,5 +1,7 @@
 /// Main application structure and lifecycle management
 use crate::core::{ClippyAgent, TextToSpeech};
+use crate::services::{BackupManager, StorageHandle};
+use crate::services::storage::RetentionPolicy;
 use crate::config::Config;
 use eframe::egui;
 use std::sync::Arc;
@@ -8,25 +10,104 @@ use std::sync::mpsc as std_mpsc;
 use std::path::PathBuf;
 use std::time::Instant;
 use super::{chat, buttons};
+use super::chat::ChatMessage;
+use super::widgets::WidgetStatus;
+use crate::services::cache::Cached;
+use crate::services::currency::HISTORY_DAYS;
+use crate::services::iso4217;
+use crate::services::{RatePoint, WeatherAlert};
+use std::collections::{HashMap, VecDeque};
 
-/// Data for widget updates sent from background tasks
-#[derive(Clone)]
+type RatesResult = Result<Cached<Vec<crate::services::ExchangeRate>>, String>;
+
+/// Data for widget updates sent from background tasks; each loop fills only its own fields
+#[derive(Clone, Default)]
 pub struct WidgetUpdate {
-    pub weather: Option<crate::services::WeatherInfo>,
-    pub rates: Option<Vec<crate::services::ExchangeRate>>,
+    /// Результат обновления погоды (Err — текст ошибки для виджета)
+    pub weather: Option<Result<Cached<crate::services::WeatherInfo>, String>>,
+    /// Почасовой и недельный прогноз (приходит вместе с успешным обновлением погоды)
+    pub forecast: Option<crate::services::Forecast>,
+    pub air_quality: Option<Result<Cached<crate::services::AirQuality>, String>>,
+    /// Коды валют списка в его порядке и их курсы
+    pub rates: Option<(Vec<String>, RatesResult)>,
+    /// История курсов валют виджетов из БД
+    pub rate_history: Option<Vec<RatePoint>>,
+    pub maintenance: Option<crate::services::storage::MaintenanceReport>,
+    pub crypto: Option<Result<Cached<Vec<crate::services::CryptoQuote>>, String>>,
+    pub stocks: Option<Result<Cached<Vec<crate::services::StockQuote>>, String>>,
+    /// Новые предупреждения о погоде для облачка
+    pub alerts: Vec<WeatherAlert>,
+    /// Сработавшие уведомления о курсах валют и акций для облачка
+    pub rate_alerts: Vec<crate::services::RateAlert>,
+}
+
+/// How long the "history cleared" toast offers an undo
+const UNDO_TIMEOUT_SECS: u64 = 8;
+
+/// How long an alert bubble stays next to Clippy
+const ALERT_DISPLAY_SECS: u64 = 15;
+
+/// Weather or rate alert waiting for the bubble next to Clippy
+pub struct AlertBubble {
+    pub text: String,
+    /// Read the alert aloud when it is shown
+    pub speak: bool,
+    /// Weather alert to mark as shown once the bubble appears; waits out the quiet hours
+    pub weather: Option<WeatherAlert>,
+    /// Rate rule to save as fired once the bubble appears
+    pub rate: Option<crate::services::RateAlert>,
+}
+
+/// Expanded rate chart of a currency widget
+pub struct RateChart {
+    pub currency: String,
+    /// Selected range in days
+    pub days: i64,
+}
+
+/// Converter popover of a currency widget (right click)
+pub struct Converter {
+    pub from: String,
+    pub to: String,
+    /// Amount as typed by the user
+    pub amount: String,
+}
+
+/// Pending undo for a history clear
+pub struct UndoToast {
+    /// Trash batch returned by the agent (arrives asynchronously)
+    pub batch: Option<String>,
+    /// The agent has finished clearing. Undo stays disabled until then, and
+    /// for good if no batch came back (incognito or a storage error)
+    pub cleared: bool,
+    /// Messages that were on screen before clearing
+    pub messages: Vec<ChatMessage>,
+    pub shown_at: Instant,
+}
+
+impl UndoToast {
+    pub fn can_undo(&self) -> bool {
+        self.cleared && self.batch.is_some()
+    }
 }
 
 pub struct ClippyApp {
     pub config: Config,
     pub agent: Arc<Mutex<ClippyAgent>>,
+    pub storage: Option<StorageHandle>,
     pub tts: Arc<TextToSpeech>,
-    pub messages: Vec<(String, String)>,
+    pub messages: Vec<ChatMessage>,
     pub input_text: String,
     pub is_thinking: bool,
-    pub response_receiver: std_mpsc::Receiver<String>,
-    pub response_sender: std_mpsc::Sender<String>,
+    /// Ответ агента и uid, под которым он сохранён в историю
+    pub response_receiver: std_mpsc::Receiver<(String, Option<String>)>,
+    pub response_sender: std_mpsc::Sender<(String, Option<String>)>,
     pub widget_receiver: std_mpsc::Receiver<WidgetUpdate>,
     pub widget_sender: std_mpsc::Sender<WidgetUpdate>,
+    /// Внеочередное обновление виджетов (открытие чата, смена места или валют).
+    /// У каждого цикла обновления свой подписчик, так что запрос, пришедший во
+    /// время загрузки, не теряется, а выполняется сразу после неё
+    pub widget_refresh: Arc<tokio::sync::watch::Sender<()>>,
     pub clippy_texture: Option<egui::TextureHandle>,
     pub style_initialized: bool,
     pub start_time: Instant,
@@ -35,28 +116,78 @@ pub struct ClippyApp {
     pub chat_visible: bool,
     pub animation_progress: f32,
     pub weather: super::widgets::WeatherWidget,
+    pub forecast: Option<crate::services::Forecast>,
+    pub air_quality: super::widgets::AirQualityWidget,
+    /// Лента прогноза раскрыта (клик по виджету погоды)
+    pub forecast_expanded: bool,
+    /// Предупреждения о погоде и уведомления о курсах, ожидающие показа в облачке
+    pub alerts: VecDeque<AlertBubble>,
+    /// Когда показано текущее (первое в очереди) предупреждение
+    pub alert_shown_at: Option<Instant>,
+    /// Список показанных предупреждений о погоде
+    pub weather_alerts: crate::services::WeatherAlerts,
     pub currencies: Vec<super::widgets::CurrencyWidget>,
+    pub crypto: Vec<super::widgets::CryptoWidget>,
+    pub stocks: Vec<super::widgets::StockWidget>,
+    /// Дневные курсы по коду валюты (до года), от старых к новым
+    pub rate_history: HashMap<String, Vec<RatePoint>>,
+    /// Открытый график курса (клик по виджету валюты)
+    pub rate_chart: Option<RateChart>,
+    /// Последние курсы к базовой валюте (для конвертера)
+    pub rates: Vec<crate::services::ExchangeRate>,
+    /// Открытый конвертер (правый клик по виджету валюты)
+    pub converter: Option<Converter>,
     pub widget_updates_started: bool,
     pub widget_data_loaded: bool,
+    pub maintenance: Option<crate::services::storage::MaintenanceReport>,
+    pub cleared_receiver: std_mpsc::Receiver<Option<String>>,
+    pub cleared_sender: std_mpsc::Sender<Option<String>>,
+    pub undo_toast: Option<UndoToast>,
+    /// Инкогнито: ответы не сохраняются, при выходе из режима чат стирается
+    pub incognito: bool,
+    /// Сообщения на экране до входа в инкогнито
+    pub pre_incognito_messages: Vec<ChatMessage>,
 }
 
 impl ClippyApp {
     pub fn new(config: Config) -> Self {
-        let agent = Arc::new(Mutex::new(ClippyAgent::new(config.clone())));
+        let mut agent = ClippyAgent::new(config.clone());
+        let storage = agent.storage_handle();
+        // Смена места командой /place сразу обновляет виджеты
+        let widget_refresh = Arc::new(tokio::sync::watch::Sender::new(()));
+        agent.set_widgets_listener(Arc::clone(&widget_refresh));
+        let agent = Arc::new(Mutex::new(agent));
         let tts = Arc::new(TextToSpeech::new(config.clone()));
         let messages = Vec::new();
+        // Сессия без следов на диске не оставляет и кэш ответов API
+        crate::services::cache::DiskCache::set_memory_only(config.in_memory_storage);
         let (sender, receiver) = std_mpsc::channel();
         let (widget_sender, widget_receiver) = std_mpsc::channel();
+        let (cleared_sender, cleared_receiver) = std_mpsc::channel();
 
         // Инициализируем виджеты валют
-        let currencies = vec![
-            super::widgets::CurrencyWidget::new("USD", "$", "0.00 ₽"),
-            super::widgets::CurrencyWidget::new("EUR", "€", "0.00 ₽"),
-        ];
+        let watchlist = crate::services::CurrencyService::from_config(&config).watchlist();
+        let currencies = watchlist
+            .codes
+            .iter()
+            .map(|code| super::widgets::CurrencyWidget::new(code, iso4217::symbol(code), "—"))
+            .collect();
+        let crypto = config
+            .crypto_coins
+            .iter()
+            .map(|symbol| super::widgets::CryptoWidget::new(symbol))
+            .collect();
+        let stocks = config
+            .stock_tickers
+            .iter()
+            .map(|ticker| super::widgets::StockWidget::new(ticker))
+            .collect();
 
+        let weather_alerts = crate::services::WeatherAlerts::from_config(&config);
         Self {
             config,
             agent,
+            storage,
             tts,
             messages,
             input_text: String::new(),
@@ -65,6 +196,7 @@ impl ClippyApp {
             response_sender: sender,
             widget_receiver,
             widget_sender,
+            widget_refresh,
             clippy_texture: None,
             style_initialized: false,
             start_time: Instant::now(),
@@ -73,157 +205,532 @@ impl ClippyApp {
             chat_visible: false,
             animation_progress: 0.0,
             weather: super::widgets::WeatherWidget::default(),
+            forecast: None,
+            air_quality: super::widgets::AirQualityWidget::default(),
+            forecast_expanded: false,
+            alerts: VecDeque::new(),
+            alert_shown_at: None,
+            weather_alerts,
             currencies,
+            crypto,
+            stocks,
+            rate_history: HashMap::new(),
+            rate_chart: None,
+            rates: Vec::new(),
+            converter: None,
             widget_updates_started: false,
             widget_data_loaded: false,
+            maintenance: None,
+            cleared_receiver,
+            cleared_sender,
+            undo_toast: None,
+            incognito: false,
+            pre_incognito_messages: Vec::new(),
         }
     }
 
-    /// Запускает асинхронное обновление данных виджетов (погода и курсы валют)
-    /// Обновляет виджеты реальными данными из API
-    pub fn start_widget_updates(&mut self, _ctx: &egui::Context) {
+    /// Запускает фоновые обновления виджетов, обслуживание БД и резервное копирование (один раз)
+    pub fn start_widget_updates(&mut self, ctx: &egui::Context) {
         // Избегаем повторного запуска
         if self.widget_updates_started {
             return;
         }
 
         self.widget_updates_started = true;
+        self.start_widget_refresh(ctx);
+        self.start_maintenance(ctx);
+        self.start_backups();
         log::info!("📡 Система обновления виджетов инициализирована");
     }
 
-    /// Загружает данные виджетов (вызывается при открытии чата)
-    pub fn fetch_widget_data(&self) {
+    /// Запускает периодическое обслуживание БД (политики хранения, VACUUM, ANALYZE).
+    /// Результат отображается в виджете статистики
+    fn start_maintenance(&self, ctx: &egui::Context) {
+        let Some(storage) = self.storage.clone() else {
+            return;
+        };
+        let policy = RetentionPolicy::from_config(&self.config);
         let widget_sender = self.widget_sender.clone();
+        let ctx = ctx.clone();
+        let period = std::time::Duration::from_secs(self.config.maintenance_interval_mins * 60);
 
-        log::info!("📡 Загрузка данных для виджетов...");
+        tokio::spawn(async move {
+            // Первый проход через минуту после старта, чтобы не тормозить запуск
+            let start = tokio::time::Instant::now() + std::time::Duration::from_secs(60);
+            let mut interval = tokio::time::interval_at(start, period);
 
-        // Определяем город пользователя по locale системы
-        let user_city = Self::get_user_city();
-        log::info!("🌍 Регион пользователя: {}", user_city);
+            loop {
+                interval.tick().await;
+                let policy = policy.clone();
+                match storage.call(move |s| s.run_maintenance(&policy)).await {
+                    Ok(report) => {
+                        let update = WidgetUpdate {
+                            maintenance: Some(report),
+                            ..Default::default()
+                        };
+                        if widget_sender.send(update).is_err() {
+                            break;
+                        }
+                        ctx.request_repaint();
+                    }
+                    Err(e) => log::warn!("⚠️ Ошибка обслуживания БД: {}", e),
+                }
+            }
+        });
+    }
 
-        // Демонстрационные данные с реалистичными значениями для региона
-        let (temp, description, humidity) = match user_city.as_str() {
-            "Санкт-Петербург" => (12, "Пасмурно".to_string(), 68),
-            "Казань" => (15, "Облачно".to_string(), 65),
-            "Новосибирск" => (8, "Снег".to_string(), 72),
-            "Екатеринбург" => (10, "Облачно".to_string(), 70),
-            "Москва" | _ => (18, "Облачно".to_string(), 62),
-        };
+    /// Запускает автоматическое резервное копирование с ротацией
+    fn start_backups(&self) {
+        if self.storage.is_none() || self.config.backup_interval_hours == 0 || self.config.in_memory_storage {
+            return;
+        }
+        let backups = BackupManager::from_config(&self.config);
+        let period = std::time::Duration::from_secs(self.config.backup_interval_hours * 3600);
 
-        let update = WidgetUpdate {
-            weather: Some(crate::services::WeatherInfo {
-                city: user_city.clone(),
-                temperature: temp,
-                description,
-                humidity,
-            }),
-            rates: Some(vec![
-                crate::services::ExchangeRate {
-                    currency: "USD".to_string(),
-                    rate: 95.50,
-                },
-                crate::services::ExchangeRate {
-                    currency: "EUR".to_string(),
-                    rate: 104.25,
-                },
-            ]),
-        };
+        tokio::spawn(async move {
+            // Первая копия через 5 минут после старта, затем по расписанию
+            let start = tokio::time::Instant::now() + std::time::Duration::from_secs(300);
+            let mut interval = tokio::time::interval_at(start, period);
 
-        let _ = widget_sender.send(update);
-        log::info!("✓ Данные виджетов загружены для города {}", user_city);
+            loop {
+                interval.tick().await;
+                if let Err(e) = backups.create().await {
+                    log::warn!("⚠️ Ошибка резервного копирования: {}", e);
+                }
+            }
+        });
     }
 
-    /// Определяет город пользователя по системному locale
-    fn get_user_city() -> String {
-        // Пытаемся получить timezone или locale
-        match std::env::var("TZ") {
-            Ok(tz) => {
-                // Пытаемся определить город по timezone
-                if tz.contains("Moscow") {
-                    "Москва".to_string()
-                } else if tz.contains("Petersburg") {
-                    "Санкт-Петербург".to_string()
-                } else if tz.contains("Kazan") {
-                    "Казань".to_string()
-                } else if tz.contains("Novosibirsk") {
-                    "Новосибирск".to_string()
-                } else if tz.contains("Ekaterinburg") {
-                    "Екатеринбург".to_string()
-                } else {
-                    "Москва".to_string()
+    /// Запускает фоновое обновление погоды и курсов валют по расписанию.
+    /// Первый запрос выполняется сразу, открытие чата запрашивает внеочередное обновление
+    fn start_widget_refresh(&self, ctx: &egui::Context) {
+        let sender = self.widget_sender.clone();
+        let mut refresh = self.widget_refresh.subscribe();
+        let repaint = ctx.clone();
+        let service = crate::services::WeatherService::from_config(&self.config);
+        let air_service = crate::services::AirQualityService::from_config(&self.config);
+        let location = crate::services::LocationService::from_config(&self.config);
+        let weather_alerts = crate::services::WeatherAlerts::from_config(&self.config);
+        let period = std::time::Duration::from_secs(self.config.weather_refresh_mins * 60);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    Ok(()) = refresh.changed() => {}
+                }
+                // Место перечитывается каждый раз: его могли сменить командой /place
+                let (forecast, air_quality) = match location.current_place().await {
+                    Ok(place) => (
+                        service.get_forecast(&place).await.map_err(|e| e.to_string()),
+                        air_service.get_air_quality(&place).await.map_err(|e| e.to_string()),
+                    ),
+                    Err(e) => (Err(e.to_string()), Err(e.to_string())),
+                };
+                if let Err(e) = &air_quality {
+                    log::warn!("⚠️ Ошибка обновления качества воздуха: {}", e);
                 }
+                let (weather, forecast, alerts) = match forecast {
+                    Ok(forecast) => {
+                        // По устаревшему прогнозу не предупреждаем
+                        let alerts = if forecast.stale {
+                            Vec::new()
+                        } else {
+                            weather_alerts.check(&forecast.value)
+                        };
+                        let current = forecast.clone().map(|f| f.current);
+                        (Ok(current), Some(forecast.value), alerts)
+                    }
+                    Err(e) => {
+                        log::warn!("⚠️ Ошибка обновления погоды: {}", e);
+                        (Err(e), None, Vec::new())
+                    }
+                };
+                let update = WidgetUpdate {
+                    weather: Some(weather),
+                    forecast,
+                    air_quality: Some(air_quality),
+                    alerts,
+                    ..Default::default()
+                };
+                if sender.send(update).is_err() {
+                    break;
+                }
+                repaint.request_repaint();
             }
-            Err(_) => {
-                // Используем системный locale как fallback
-                match std::env::var("LANG") {
-                    Ok(lang) => {
-                        log::debug!("System LANG: {}", lang);
-                        "Москва".to_string()
+        });
+
+        let sender = self.widget_sender.clone();
+        let mut refresh = self.widget_refresh.subscribe();
+        let repaint = ctx.clone();
+        let service = crate::services::CurrencyService::from_config(&self.config);
+        let alert_rules = crate::services::RateAlerts::from_config(&self.config);
+        let storage = self.storage.clone();
+        let period = std::time::Duration::from_secs(self.config.rates_refresh_mins * 60);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    Ok(()) = refresh.changed() => {}
+                }
+                let codes = service.watchlist().codes;
+                let rates = service.get_rates().await.map_err(|e| {
+                    log::warn!("⚠️ Ошибка обновления курсов валют: {}", e);
+                    e.to_string()
+                });
+                // История для графиков: догружаем недостающие дни и читаем из БД
+                let rate_history = match &storage {
+                    Some(storage) => {
+                        let watchlist = service.watchlist();
+                        // Ошибки по отдельным валютам уже в логе, история остальных загружена
+                        service.sync_history(storage, &watchlist).await;
+                        let mut history = Vec::new();
+                        for code in &watchlist.codes {
+                            match service.history(storage, code, &watchlist.base, HISTORY_DAYS).await {
+                                Ok(points) => history.extend(points),
+                                Err(e) => log::warn!("⚠️ Ошибка чтения истории курса {}: {}", code, e),
+                            }
+                        }
+                        Some(history)
+                    }
+                    None => None,
+                };
+                // Правила уведомлений проверяются по каждому свежему обновлению курсов
+                let rate_alerts = match (&storage, &rates) {
+                    (Some(storage), Ok(rates)) if !rates.stale => {
+                        let base = service.watchlist().base;
+                        alert_rules.check(storage, &service, &rates.value, &base).await
                     }
-                    Err(_) => "Москва".to_string(),
+                    _ => Vec::new(),
+                };
+                let update = WidgetUpdate {
+                    rates: Some((codes, rates)),
+                    rate_history,
+                    rate_alerts,
+                    ..Default::default()
+                };
+                if sender.send(update).is_err() {
+                    break;
                 }
+                repaint.request_repaint();
             }
+        });
+
+        // Цены криптовалют и акций меняются быстрее курсов и обновляются своими циклами
+        let service = crate::services::CryptoService::from_config(&self.config);
+        if !service.coins().is_empty() {
+            self.start_crypto_updates(ctx, service);
+        }
+        let service = crate::services::StocksService::from_config(&self.config);
+        if !service.tickers().is_empty() {
+            self.start_stock_updates(ctx, service);
         }
     }
 
+    /// Цикл обновления цен криптовалют
+    fn start_crypto_updates(&self, ctx: &egui::Context, service: crate::services::CryptoService) {
+        let sender = self.widget_sender.clone();
+        let mut refresh = self.widget_refresh.subscribe();
+        let repaint = ctx.clone();
+        let period = std::time::Duration::from_secs(self.config.crypto_refresh_mins * 60);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    Ok(()) = refresh.changed() => {}
+                }
+                let crypto = service.get_quotes().await.map_err(|e| {
+                    log::warn!("⚠️ Ошибка обновления цен криптовалют: {}", e);
+                    e.to_string()
+                });
+                let update = WidgetUpdate {
+                    crypto: Some(crypto),
+                    ..Default::default()
+                };
+                if sender.send(update).is_err() {
+                    break;
+                }
+                repaint.request_repaint();
+            }
+        });
+    }
+
+    /// Цикл обновления котировок акций Мосбиржи
+    fn start_stock_updates(&self, ctx: &egui::Context, service: crate::services::StocksService) {
+        let sender = self.widget_sender.clone();
+        let mut refresh = self.widget_refresh.subscribe();
+        let repaint = ctx.clone();
+        let period = std::time::Duration::from_secs(self.config.stocks_refresh_mins * 60);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    Ok(()) = refresh.changed() => {}
+                }
+                let stocks = service.get_quotes().await.map_err(|e| {
+                    log::warn!("⚠️ Ошибка обновления котировок акций: {}", e);
+                    e.to_string()
+                });
+                let update = WidgetUpdate {
+                    stocks: Some(stocks),
+                    ..Default::default()
+                };
+                if sender.send(update).is_err() {
+                    break;
+                }
+                repaint.request_repaint();
+            }
+        });
+    }
+
     /// Обрабатывает полученные обновления виджетов из канала
     /// Это вызывается из основного UI потока
     pub fn process_widget_updates(&mut self) {
         while let Ok(update) = self.widget_receiver.try_recv() {
             // Применяем обновление погоды если оно пришло
             if let Some(weather) = update.weather {
+                self.apply_weather(weather);
+            }
+
+            if let Some(forecast) = update.forecast {
+                self.forecast = Some(forecast);
+            }
+
+            if let Some(air_quality) = update.air_quality {
+                self.apply_air_quality(air_quality);
+            }
+
+            if let Some(report) = update.maintenance {
+                self.maintenance = Some(report);
+            }
+
+            // Применяем обновление валют если оно пришло
+            if let Some((codes, rates)) = update.rates {
+                self.apply_rates(codes, rates);
+            }
+
+            if let Some(history) = update.rate_history {
+                self.apply_rate_history(history);
+            }
+
+            if let Some(crypto) = update.crypto {
+                self.apply_crypto(crypto);
+            }
+
+            if let Some(stocks) = update.stocks {
+                self.apply_stocks(stocks);
+            }
+
+            // Пока предупреждение не показано, оно приходит с каждым обновлением
+            let speak = self.config.weather_alerts.speak;
+            for alert in update.alerts {
+                let queued = self
+                    .alerts
+                    .iter()
+                    .any(|bubble| bubble.weather.as_ref().is_some_and(|w| w.key() == alert.key()));
+                if !queued {
+                    self.alerts.push_back(AlertBubble {
+                        text: alert.text.clone(),
+                        speak,
+                        weather: Some(alert),
+                        rate: None,
+                    });
+                }
+            }
+            // Сработавшее правило сохраняется только при показе, до этого приходит снова
+            let speak = self.config.rate_alerts_speak;
+            for alert in update.rate_alerts {
+                let queued = self
+                    .alerts
+                    .iter()
+                    .any(|bubble| bubble.rate.as_ref().is_some_and(|r| r.rule_id == alert.rule_id));
+                if !queued {
+                    self.alerts.push_back(AlertBubble {
+                        text: alert.text.clone(),
+                        speak,
+                        weather: None,
+                        rate: Some(alert),
+                    });
+                }
+            }
+        }
+    }
+
+    /// Обновляет виджет погоды; при ошибке остаются прежние значения
+    fn apply_weather(&mut self, result: Result<Cached<crate::services::WeatherInfo>, String>) {
+        match result {
+            Ok(cached) => {
+                let status = Self::cached_status(&cached);
+                let updated_at = Some(cached.fetched_label());
+                let weather = cached.value;
+                let units = weather.units;
                 self.weather = super::widgets::WeatherWidget {
-                    temperature: format!("{} °C", weather.temperature),
+                    temperature: units.temperature(weather.temperature),
                     condition: weather.description.clone(),
+                    icon: weather.icon().to_string(),
                     humidity: format!("{} %", weather.humidity),
+                    wind: units.wind(weather.wind_speed),
+                    status,
+                    updated_at,
                 };
-                log::debug!("🌡️ Виджет погоды обновлен: {} °C", weather.temperature);
+                log::debug!("🌡️ Виджет погоды обновлен: {}", units.temperature(weather.temperature));
             }
+            Err(e) => self.weather.status = WidgetStatus::Error(e),
+        }
+    }
 
-            // Применяем обновление валют если оно пришло
-            if let Some(rates) = update.rates {
-                for (i, rate) in rates.iter().enumerate() {
-                    if i < self.currencies.len() {
-                        self.currencies[i].rate = format!("{:.2} ₽", rate.rate);
-                        log::debug!("💱 Курс {}: {:.2} ₽", rate.currency, rate.rate);
-                    }
-                }
+    /// Обновляет виджет качества воздуха; при ошибке остаются прежние значения
+    fn apply_air_quality(&mut self, result: Result<Cached<crate::services::AirQuality>, String>) {
+        match result {
+            Ok(cached) => {
+                let status = Self::cached_status(&cached);
+                let air = &cached.value;
+                let level = air.level();
+                self.air_quality = super::widgets::AirQualityWidget {
+                    aqi: air.european_aqi.map_or("--".to_string(), |aqi| aqi.to_string()),
+                    level: level.map_or("нет данных", |l| l.label()).to_string(),
+                    icon: level.map_or("🌬️", |l| l.icon()).to_string(),
+                    pollen_warning: air.has_high_pollen(),
+                    status,
+                    updated_at: Some(cached.fetched_label()),
+                };
             }
+            Err(e) => self.air_quality.status = WidgetStatus::Error(e),
         }
     }
 
-    /// Функция-помощник для обновления виджета погоды (вызывается из основного потока)
-    #[allow(dead_code)]
-    pub async fn update_weather_widget(&mut self) {
-        let agent = self.agent.lock().await;
-        if let Ok(weather) = agent.get_weather_data("Москва").await {
-            self.weather = super::widgets::WeatherWidget {
-                temperature: format!("{} °C", weather.temperature),
-                condition: weather.description.clone(),
-                humidity: format!("{} %", weather.humidity),
+    /// Устаревшие данные из кэша помечаются на виджете
+    fn cached_status<T>(cached: &Cached<T>) -> WidgetStatus {
+        if cached.stale {
+            WidgetStatus::Stale
+        } else {
+            WidgetStatus::Ready
+        }
+    }
+
+    /// Обновляет виджеты валют: набор и порядок виджетов повторяют список
+    /// `/currency`, состояние существующих виджетов сохраняется
+    fn apply_rates(&mut self, codes: Vec<String>, result: RatesResult) {
+        let cached = match result {
+            Ok(cached) => cached,
+            Err(e) => {
+                for widget in &mut self.currencies {
+                    widget.status = WidgetStatus::Error(e.clone());
+                }
+                return;
+            }
+        };
+
+        // Widgets follow the watchlist; a currency the source has no rate for keeps its widget with an error
+        let status = Self::cached_status(&cached);
+        let fetched_at = cached.fetched_label();
+        let mut previous = std::mem::take(&mut self.currencies);
+        for code in &codes {
+            let mut widget = match previous.iter().position(|w| w.code == *code) {
+                Some(index) => previous.swap_remove(index),
+                None => super::widgets::CurrencyWidget::new(code, iso4217::symbol(code), "—"),
             };
-            log::debug!("🌡️ Виджет погоды обновлен: {} °C", weather.temperature);
+            match cached.value.iter().find(|rate| rate.currency == *code) {
+                Some(rate) => {
+                    widget.rate = rate.format_value();
+                    widget.source = Some(rate.source_label());
+                    widget.status = status.clone();
+                    widget.updated_at = Some(fetched_at.clone());
+                    log::debug!("💱 Курс {}: {} ({})", rate.currency, widget.rate, rate.source.label());
+                }
+                None => {
+                    widget.status = WidgetStatus::Error(format!("Источник не публикует курс {}", code));
+                    log::warn!("⚠️ Нет курса {} в ответе источника", code);
+                }
+            }
+            self.currencies.push(widget);
+        }
+
+        if self
+            .rate_chart
+            .as_ref()
+            .is_some_and(|chart| !self.currencies.iter().any(|c| c.code == chart.currency))
+        {
+            self.rate_chart = None;
+        }
+        self.rates = cached.value;
+    }
+
+    /// Updates the cryptocurrency widgets in the order of the configured coins
+    fn apply_crypto(&mut self, result: Result<Cached<Vec<crate::services::CryptoQuote>>, String>) {
+        let cached = match result {
+            Ok(cached) => cached,
+            Err(e) => {
+                for widget in &mut self.crypto {
+                    widget.status = WidgetStatus::Error(e.clone());
+                }
+                return;
+            }
+        };
+
+        let status = Self::cached_status(&cached);
+        let fetched_at = cached.fetched_label();
+        for widget in &mut self.crypto {
+            match cached.value.iter().find(|q| q.symbol == widget.symbol) {
+                Some(quote) => {
+                    widget.price = quote.format_short();
+                    widget.change_24h = quote.change_24h.map(|c| c as f32);
+                    widget.sparkline = quote.sparkline.iter().map(|p| *p as f32).collect();
+                    widget.status = status.clone();
+                    widget.updated_at = Some(fetched_at.clone());
+                }
+                None => widget.status = WidgetStatus::Error(format!("Нет цены {}", widget.symbol)),
+            }
         }
     }
 
-    /// Функция-помощник для обновления виджетов валют (вызывается из основного потока)
-    #[allow(dead_code)]
-    pub async fn update_currency_widgets(&mut self) {
-        let agent = self.agent.lock().await;
-        if let Ok(rates) = agent.get_currency_data().await {
-            // Обновляем валюты на основе полученных данных
-            for (i, rate) in rates.iter().enumerate() {
-                if i < self.currencies.len() {
-                    self.currencies[i].rate = format!("{:.2} ₽", rate.rate);
-                    log::debug!("💱 Курс {}: {:.2} ₽", rate.currency, rate.rate);
+    /// Updates the stock widgets in the order of the configured tickers
+    fn apply_stocks(&mut self, result: Result<Cached<Vec<crate::services::StockQuote>>, String>) {
+        let cached = match result {
+            Ok(cached) => cached,
+            Err(e) => {
+                for widget in &mut self.stocks {
+                    widget.status = WidgetStatus::Error(e.clone());
                 }
+                return;
+            }
+        };
+
+        let status = Self::cached_status(&cached);
+        let fetched_at = cached.fetched_label();
+        for widget in &mut self.stocks {
+            match cached.value.iter().find(|q| q.ticker == widget.ticker) {
+                Some(quote) => {
+                    widget.price = quote.format_price();
+                    widget.change_percent = quote.change_percent.map(|c| c as f32);
+                    widget.trading = quote.trading;
+                    widget.status = status.clone();
+                    widget.updated_at = Some(fetched_at.clone());
+                }
+                None => widget.status = WidgetStatus::Error(format!("Нет котировки {}", widget.ticker)),
             }
         }
     }
 
-    /// Загружает данные виджетов при открытии чата
-    pub fn load_widget_data(&self) {
-        self.fetch_widget_data();
+    /// Groups the loaded history by currency and updates the widget sparklines
+    fn apply_rate_history(&mut self, history: Vec<RatePoint>) {
+        self.rate_history.clear();
+        for point in history {
+            self.rate_history.entry(point.currency.clone()).or_default().push(point);
+        }
+
+        for widget in &mut self.currencies {
+            let points = self.rate_history.get(&widget.code).map(Vec::as_slice).unwrap_or_default();
+            let recent = &points[points.len().saturating_sub(super::widgets::SPARKLINE_DAYS)..];
+            widget.sparkline = recent.iter().map(|p| p.rate as f32).collect();
+            widget.change = match recent {
+                [.., previous, last] => Some((last.rate - previous.rate) as f32),
+                _ => None,
+            };
+        }
     }
 
     pub fn load_clippy_image(&mut self, ctx: &egui::Context) {
@@ -346,7 +853,7 @@ impl ClippyApp {
 
         let user_input = self.input_text.clone();
         self.input_text.clear();
-        self.messages.push(("user".to_string(), user_input.clone()));
+        self.messages.push(ChatMessage::new("user", user_input.clone()));
         self.is_thinking = true;
 
         let agent = Arc::clone(&self.agent);
@@ -355,9 +862,12 @@ impl ClippyApp {
 
         tokio::spawn(async move {
             let mut agent = agent.lock().await;
+            let previous_uid = agent.last_reply_uid();
             let response = agent.get_response(&user_input).await;
+            // Ответы на команды чата не сохраняются — uid не меняется
+            let uid = agent.last_reply_uid().filter(|uid| previous_uid.as_ref() != Some(uid));
 
-            if let Err(e) = sender.send(response) {
+            if let Err(e) = sender.send((response, uid)) {
                 eprintln!("Ошибка отправки ответа: {}", e);
             }
 
@@ -365,22 +875,210 @@ impl ClippyApp {
         });
     }
 
+    /// Toggles incognito. Entering it puts the current chat aside; leaving it wipes the
+    /// incognito chat from the screen and the agent's memory and brings the earlier chat back
+    pub fn toggle_incognito(&mut self) {
+        if self.is_thinking {
+            return;
+        }
+
+        self.incognito = !self.incognito;
+        self.tts.set_incognito(self.incognito);
+        crate::services::cache::DiskCache::set_memory_only(self.incognito || self.config.in_memory_storage);
+        self.undo_toast = None;
+        if self.incognito {
+            self.pre_incognito_messages = std::mem::take(&mut self.messages);
+        } else {
+            self.messages = std::mem::take(&mut self.pre_incognito_messages);
+        }
+
+        let enabled = self.incognito;
+        let agent = Arc::clone(&self.agent);
+        tokio::spawn(async move {
+            agent.lock().await.set_incognito(enabled);
+        });
+    }
+
+    /// Applies a feedback control click to a reply and persists it
+    pub fn apply_feedback(&mut self, index: usize, action: crate::services::FeedbackAction) {
+        let Some(message) = self.messages.get_mut(index) else {
+            return;
+        };
+        let Some(uid) = message.uid.clone() else {
+            return;
+        };
+
+        action.apply(&mut message.feedback);
+
+        if let Some(storage) = self.storage.clone() {
+            tokio::spawn(async move {
+                if let Err(e) = storage.call(move |s| s.update_feedback(&uid, &action)).await {
+                    log::error!("Ошибка сохранения оценки: {}", e);
+                }
+            });
+        }
+    }
+
+    /// Clears the chat and moves the session history to the trash, offering an undo
+    pub fn clear_history(&mut self, ctx: &egui::Context) {
+        if self.is_thinking {
+            return;
+        }
+
+        self.undo_toast = Some(UndoToast {
+            batch: None,
+            cleared: false,
+            messages: std::mem::take(&mut self.messages),
+            shown_at: Instant::now(),
+        });
+
+        let agent = Arc::clone(&self.agent);
+        let sender = self.cleared_sender.clone();
+        let ctx_clone = ctx.clone();
+
+        tokio::spawn(async move {
+            let batch = agent.lock().await.clear_history().await;
+            let _ = sender.send(batch);
+            ctx_clone.request_repaint();
+        });
+    }
+
+    /// Restores the messages removed by the last clear. Does nothing until the
+    /// agent has returned the trash batch, so the screen, the agent's memory
+    /// and the database are restored together
+    pub fn undo_clear(&mut self) {
+        if !self.undo_toast.as_ref().is_some_and(UndoToast::can_undo) {
+            return;
+        }
+        let Some(UndoToast { batch: Some(batch), messages, .. }) = self.undo_toast.take() else {
+            return;
+        };
+
+        let mut restored = messages;
+        restored.append(&mut self.messages);
+        self.messages = restored;
+
+        let agent = Arc::clone(&self.agent);
+        tokio::spawn(async move {
+            if let Err(e) = agent.lock().await.restore_history(&batch, None).await {
+                log::error!("{}", e);
+            }
+        });
+    }
+
+    /// Receives trash batches for pending undo toasts and expires old toasts
+    pub fn process_history_events(&mut self, ctx: &egui::Context) {
+        while let Ok(batch) = self.cleared_receiver.try_recv() {
+            if let Some(toast) = &mut self.undo_toast {
+                toast.batch = batch;
+                toast.cleared = true;
+            }
+        }
+
+        if let Some(toast) = &self.undo_toast {
+            if toast.shown_at.elapsed().as_secs() >= UNDO_TIMEOUT_SECS {
+                self.undo_toast = None;
+            } else {
+                ctx.request_repaint_after(std::time::Duration::from_millis(500));
+            }
+        }
+    }
+
     pub fn draw_show_button(&mut self, ctx: &egui::Context, image_rect: egui::Rect) {
         if buttons::draw_show_button(ctx, image_rect) {
             log::debug!("🟢 Show button clicked! Opening chat window");
             self.chat_visible = true;
             self.animation_progress = 0.0;
-            // Загружаем данные для виджетов при открытии чата
-            self.load_widget_data();
+            // Обновляем данные виджетов при открытии чата
+            self.widget_refresh.send_replace(());
+            ctx.request_repaint();
+        }
+    }
+
+    /// Shows the oldest pending alert in a speech bubble next to Clippy.
+    /// Alerts wait while the chat is open, weather alerts also wait out the quiet hours;
+    /// a click on the bubble dismisses it early
+    pub fn draw_alert_bubble(&mut self, ctx: &egui::Context, image_rect: egui::Rect) {
+        if self.chat_visible {
+            return;
+        }
+        if self.alert_shown_at.is_none() {
+            // Тихие часы проверяются при показе: предупреждения о погоде ждут их конца
+            let quiet = self.config.weather_alerts.is_quiet_now();
+            let Some(next) = self.alerts.iter().position(|a| !(quiet && a.weather.is_some())) else {
+                return;
+            };
+            if let Some(alert) = self.alerts.remove(next) {
+                self.alerts.push_front(alert);
+            }
+        }
+        let Some(alert) = self.alerts.front() else {
+            return;
+        };
+
+        let shown_at = match self.alert_shown_at {
+            Some(shown_at) => shown_at,
+            None => {
+                if let Some(weather) = &alert.weather {
+                    self.weather_alerts.mark_shown(weather);
+                }
+                if let (Some(rate), Some(storage)) = (alert.rate.clone(), self.storage.clone()) {
+                    tokio::spawn(async move {
+                        if let Err(e) = rate.mark_shown(&storage).await {
+                            log::warn!("⚠️ Ошибка сохранения уведомления о курсе: {}", e);
+                        }
+                    });
+                }
+                if alert.speak {
+                    let tts = Arc::clone(&self.tts);
+                    let text = alert.text.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = tts.speak(&text).await {
+                            eprintln!("Ошибка озвучивания: {}", e);
+                        }
+                    });
+                }
+                *self.alert_shown_at.insert(Instant::now())
+            }
+        };
+
+        let cloud_rect = super::chat_bubble::show_talk_cloud_side(
+            ctx,
+            &alert.text,
+            image_rect,
+            32,
+            120.0,
+            10.0,
+            true,
+            egui::FontId::proportional(14.0),
+        );
+
+        let clicked = ctx.input(|i| {
+            i.pointer.primary_clicked() && i.pointer.interact_pos().is_some_and(|pos| cloud_rect.contains(pos))
+        });
+        if clicked || shown_at.elapsed().as_secs() >= ALERT_DISPLAY_SECS {
+            self.alerts.pop_front();
+            self.alert_shown_at = None;
             ctx.request_repaint();
+        } else {
+            ctx.request_repaint_after(std::time::Duration::from_millis(500));
         }
     }
 
-    pub fn draw_widgets_panel(&self, ctx: &egui::Context, chat_rect: egui::Rect) {
+    pub fn draw_widgets_panel(&mut self, ctx: &egui::Context, chat_rect: egui::Rect) {
         // Панель виджетов сверху над чатом
         let painter = ctx.layer_painter(egui::LayerId::new(egui::Order::Foreground, egui::Id::new("widgets_panel")));
 
-        let panel_height = 85.0;
+        let widget_width = super::widgets::WIDGET_WIDTH;
+        let widget_height = super::widgets::WIDGET_HEIGHT;
+        let padding = super::widgets::WIDGET_PADDING;
+        let spacing = super::widgets::WIDGET_SPACING;
+
+        // Погода, валюты, криптовалюты и акции идут рядами по столько, сколько влезает в ширину чата,
+        // под ними — статистика и качество воздуха
+        let columns = (((chat_rect.width() - 2.0 * padding + spacing) / (widget_width + spacing)) as usize).max(1);
+        let rows = (1 + self.currencies.len() + self.crypto.len() + self.stocks.len()).div_ceil(columns);
+        let panel_height = 2.0 * padding + rows as f32 * (widget_height + spacing) + widget_height / 1.5;
         let panel_rect = egui::Rect::from_min_size(
             egui::pos2(chat_rect.min.x, chat_rect.min.y - panel_height - 5.0),
             egui::vec2(chat_rect.width(), panel_height),
@@ -400,39 +1098,214 @@ impl ClippyApp {
         );
 
         let alpha = 240u8;
-        let widget_width = super::widgets::WIDGET_WIDTH;
-        let widget_height = super::widgets::WIDGET_HEIGHT;
-        let padding = super::widgets::WIDGET_PADDING;
-        let spacing = super::widgets::WIDGET_SPACING;
+        let slot_rect = |slot: usize| {
+            egui::Rect::from_min_size(
+                egui::pos2(
+                    panel_rect.min.x + padding + (slot % columns) as f32 * (widget_width + spacing),
+                    panel_rect.min.y + padding + (slot / columns) as f32 * (widget_height + spacing),
+                ),
+                egui::vec2(widget_width, widget_height),
+            )
+        };
 
         // Погода виджет
-        let weather_x = panel_rect.min.x + padding;
-        let weather_y = panel_rect.min.y + padding;
-        let weather_rect = egui::Rect::from_min_size(
-            egui::pos2(weather_x, weather_y),
-            egui::vec2(widget_width, widget_height),
-        );
+        let weather_rect = slot_rect(0);
         super::widgets::draw_weather_widget(&painter, weather_rect, alpha, &self.weather);
 
-        // Валюты виджеты
-        for (i, currency) in self.currencies.iter().enumerate() {
-            let currency_x = weather_x + widget_width + spacing + (i as f32) * (widget_width + spacing);
-            let currency_y = weather_y;
-            let currency_rect = egui::Rect::from_min_size(
-                egui::pos2(currency_x, currency_y),
-                egui::vec2(widget_width, widget_height),
+        // Клик по погоде раскрывает ленту прогноза над панелью
+        let weather_hovered = ctx.input(|i| i.pointer.hover_pos()).is_some_and(|pos| weather_rect.contains(pos));
+        if weather_hovered && self.forecast.is_some() {
+            ctx.set_cursor_icon(egui::CursorIcon::PointingHand);
+            if ctx.input(|i| i.pointer.primary_clicked()) {
+                self.forecast_expanded = !self.forecast_expanded;
+                self.rate_chart = None;
+                self.converter = None;
+            }
+        }
+        if let (true, Some(forecast)) = (self.forecast_expanded, self.forecast.as_ref()) {
+            let strip_rect = egui::Rect::from_min_size(
+                egui::pos2(panel_rect.min.x, panel_rect.min.y - super::widgets::FORECAST_STRIP_HEIGHT - 5.0),
+                egui::vec2(panel_rect.width(), super::widgets::FORECAST_STRIP_HEIGHT),
             );
+            super::widgets::draw_forecast_strip(&painter, strip_rect, alpha, forecast);
+        }
+
+        // Валюты виджеты; клик открывает график курса над панелью
+        let pointer = ctx.input(|i| i.pointer.hover_pos());
+        // Clicks on an open combo box list of the converter are not widget clicks
+        let popup_open = egui::Popup::is_any_open(ctx);
+        let clicked = ctx.input(|i| i.pointer.primary_clicked()) && !popup_open;
+        let secondary_clicked = ctx.input(|i| i.pointer.secondary_clicked()) && !popup_open;
+        let mut toggled_chart = None;
+        let mut toggled_converter = None;
+        for (i, currency) in self.currencies.iter().enumerate() {
+            let currency_rect = slot_rect(i + 1);
             super::widgets::draw_currency_widget(&painter, currency_rect, alpha, currency);
+
+            if pointer.is_some_and(|pos| currency_rect.contains(pos)) {
+                ctx.set_cursor_icon(egui::CursorIcon::PointingHand);
+                if clicked {
+                    toggled_chart = Some(currency.code.clone());
+                }
+                if secondary_clicked {
+                    toggled_converter = Some(currency.code.clone());
+                }
+            }
+        }
+        if let Some(code) = toggled_chart {
+            let same = self.rate_chart.as_ref().is_some_and(|chart| chart.currency == code);
+            self.rate_chart = (!same).then_some(RateChart { currency: code, days: 30 });
+            self.forecast_expanded = false;
+            self.converter = None;
+        }
+        if let Some(code) = toggled_converter {
+            let same = self.converter.as_ref().is_some_and(|c| c.from == code);
+            self.converter = (!same).then(|| Converter {
+                to: self.rates.first().map_or_else(|| "RUB".to_string(), |r| r.base.clone()),
+                from: code,
+                amount: "100".to_string(),
+            });
+            self.rate_chart = None;
+            self.forecast_expanded = false;
+        }
+        self.draw_converter(ctx, egui::pos2(panel_rect.min.x, panel_rect.min.y - 5.0));
+
+        // Криптовалюты и акции после валют
+        for (i, crypto) in self.crypto.iter().enumerate() {
+            let crypto_rect = slot_rect(1 + self.currencies.len() + i);
+            super::widgets::draw_crypto_widget(&painter, crypto_rect, alpha, crypto);
+        }
+        for (i, stock) in self.stocks.iter().enumerate() {
+            let stock_rect = slot_rect(1 + self.currencies.len() + self.crypto.len() + i);
+            super::widgets::draw_stock_widget(&painter, stock_rect, alpha, stock);
+        }
+
+        let chart_rect = egui::Rect::from_min_size(
+            egui::pos2(panel_rect.min.x, panel_rect.min.y - super::widgets::RATE_CHART_HEIGHT - 5.0),
+            egui::vec2(panel_rect.width(), super::widgets::RATE_CHART_HEIGHT),
+        );
+        if let Some(chart) = &mut self.rate_chart {
+            for (days, button) in super::widgets::rate_chart_range_rects(chart_rect) {
+                if pointer.is_some_and(|pos| button.contains(pos)) {
+                    ctx.set_cursor_icon(egui::CursorIcon::PointingHand);
+                    if clicked {
+                        chart.days = days;
+                    }
+                }
+            }
+        }
+        if let Some(chart) = &self.rate_chart {
+            if let Some(currency) = self.currencies.iter().find(|c| c.code == chart.currency) {
+                let points = self.rate_history.get(&chart.currency).map(Vec::as_slice).unwrap_or_default();
+                let since = chrono::Local::now().date_naive() - chrono::Duration::days(chart.days);
+                let start = points.partition_point(|p| p.date < since);
+                super::widgets::draw_rate_chart(&painter, chart_rect, alpha, currency, &points[start..], chart.days);
+            }
         }
 
         // Статистика виджет
-        let stats_x = weather_x;
-        let stats_y = weather_y + widget_height + spacing;
+        let stats_x = panel_rect.min.x + padding;
+        let stats_y = panel_rect.min.y + padding + rows as f32 * (widget_height + spacing);
         let stats_rect = egui::Rect::from_min_size(
             egui::pos2(stats_x, stats_y),
             egui::vec2(widget_width, widget_height / 1.5),
         );
-        super::widgets::draw_stats_widget(&painter, stats_rect, alpha, self.messages.len());
+        super::widgets::draw_stats_widget(&painter, stats_rect, alpha, self.messages.len(), self.maintenance.as_ref());
+
+        // Качество воздуха рядом со статистикой
+        let air_rect = egui::Rect::from_min_size(
+            egui::pos2(stats_x + widget_width + spacing, stats_y),
+            egui::vec2(widget_width, widget_height / 1.5),
+        );
+        super::widgets::draw_air_quality_widget(&painter, air_rect, alpha, &self.air_quality);
+    }
+
+    /// Draws the converter popover with its bottom-left corner at `anchor`
+    fn draw_converter(&mut self, ctx: &egui::Context, anchor: egui::Pos2) {
+        let Some(converter) = &mut self.converter else {
+            return;
+        };
+        let base = self.rates.first().map(|r| r.base.clone());
+        let mut close = ctx.input(|i| i.key_pressed(egui::Key::Escape));
+
+        egui::Area::new(egui::Id::new("currency_converter"))
+            .order(egui::Order::Foreground)
+            .pivot(egui::Align2::LEFT_BOTTOM)
+            .fixed_pos(anchor)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.set_width(190.0);
+                    ui.horizontal(|ui| {
+                        ui.label(egui::RichText::new("💱 Конвертер").strong());
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                            close |= ui.small_button("✕").clicked();
+                        });
+                    });
+
+                    let Some(base) = base else {
+                        ui.label("Курсы ещё не загружены");
+                        return;
+                    };
+
+                    ui.horizontal(|ui| {
+                        ui.add(egui::TextEdit::singleline(&mut converter.amount).desired_width(90.0));
+                        ui.label(format!("{} {}", iso4217::symbol(&converter.from), converter.from));
+                    });
+
+                    // Target: the base currency and the other watched currencies
+                    let targets: Vec<String> = std::iter::once(base.clone())
+                        .chain(self.rates.iter().map(|r| r.currency.clone()))
+                        .filter(|code| *code != converter.from)
+                        .collect();
+                    if !targets.contains(&converter.to) {
+                        if let Some(first) = targets.first() {
+                            converter.to = first.clone();
+                        }
+                    }
+                    ui.horizontal(|ui| {
+                        if ui.small_button("⇄").on_hover_text("Поменять местами").clicked() {
+                            std::mem::swap(&mut converter.from, &mut converter.to);
+                        }
+                        egui::ComboBox::from_id_salt("converter_target")
+                            .selected_text(&converter.to)
+                            .show_ui(ui, |ui| {
+                                for code in &targets {
+                                    ui.selectable_value(&mut converter.to, code.clone(), code);
+                                }
+                            });
+                    });
+
+                    let amount = converter.amount.replace([' ', '\u{a0}'], "").replace(',', ".").parse::<f64>();
+                    match amount {
+                        Ok(amount) => match crate::services::currency::convert_with(
+                            &self.rates,
+                            &base,
+                            amount,
+                            &converter.from,
+                            &converter.to,
+                        ) {
+                            Ok(conversion) => {
+                                ui.label(
+                                    egui::RichText::new(format!("= {}", iso4217::format_amount(conversion.result, &conversion.to)))
+                                        .size(16.0)
+                                        .strong(),
+                                );
+                                ui.small(format!("1 {} = {:.4} {}", conversion.from, conversion.rate, conversion.to));
+                            }
+                            Err(e) => {
+                                ui.colored_label(egui::Color32::from_rgb(180, 60, 60), e.to_string());
+                            }
+                        },
+                        Err(_) => {
+                            ui.colored_label(egui::Color32::from_rgb(180, 60, 60), "Введите сумму");
+                        }
+                    }
+                });
+            });
+
+        if close {
+            self.converter = None;
+        }
     }
 
     pub fn draw_chat_window(&mut self, ctx: &egui::Context, image_rect: egui::Rect) {
@@ -460,26 +1333,65 @@ impl ClippyApp {
         let scaled_size = egui::vec2(chat::CHAT_WINDOW_WIDTH * scale, chat::CHAT_WINDOW_HEIGHT * scale);
         let animated_rect = egui::Rect::from_center_size(center, scaled_size);
 
-        // Фон окна чата
-        let bg_color = egui::Color32::from_rgba_unmultiplied(245, 246, 247, alpha);
+        // Фон окна чата (в инкогнито — фиолетовый оттенок и яркая обводка)
+        let (bg_color, stroke) = if self.incognito {
+            (
+                egui::Color32::from_rgba_unmultiplied(236, 232, 246, alpha),
+                egui::Stroke::new(2.5, egui::Color32::from_rgba_unmultiplied(120, 90, 170, alpha)),
+            )
+        } else {
+            (
+                egui::Color32::from_rgba_unmultiplied(245, 246, 247, alpha),
+                egui::Stroke::new(1.5, egui::Color32::from_rgba_unmultiplied(180, 180, 180, alpha)),
+            )
+        };
         painter.rect_filled(animated_rect, 12.0, bg_color);
 
         // Обводка
-        let stroke_color = egui::Color32::from_rgba_unmultiplied(180, 180, 180, alpha);
-        painter.rect_stroke(animated_rect, 12.0, egui::Stroke::new(1.5, stroke_color), egui::epaint::StrokeKind::Outside);
+        painter.rect_stroke(animated_rect, 12.0, stroke, egui::epaint::StrokeKind::Outside);
 
         // Заголовок
         let title_y = animated_rect.min.y + 15.0;
+        let mut title = if self.config.profile.is_default() {
+            "💬 Скрепыш".to_string()
+        } else {
+            format!("💬 Скрепыш · {}", self.config.profile.name)
+        };
+        if self.incognito {
+            title.push_str(" · 🕶 инкогнито");
+        }
         painter.text(
             egui::pos2(animated_rect.min.x + 15.0, title_y),
             egui::Align2::LEFT_CENTER,
-            "💬 Скрепыш",
+            title,
             egui::FontId::proportional(14.0),
             egui::Color32::from_rgba_unmultiplied(40, 40, 40, alpha),
         );
 
+        if chat::draw_clear_button(&painter, ctx, animated_rect, alpha) {
+            self.clear_history(ctx);
+        }
+
+        if chat::draw_incognito_button(&painter, ctx, animated_rect, alpha, self.incognito) {
+            self.toggle_incognito();
+        }
+
         // Draw messages using the chat module
-        chat::draw_messages(&painter, animated_rect, alpha, &self.messages, self.is_thinking);
+        if let Some((index, action)) =
+            chat::draw_messages(&painter, ctx, animated_rect, alpha, &self.messages, self.is_thinking)
+        {
+            self.apply_feedback(index, action);
+        }
+
+        // Undo toast right after clearing
+        let undo_clicked = self.undo_toast.as_ref().is_some_and(|toast| {
+            let remaining = UNDO_TIMEOUT_SECS.saturating_sub(toast.shown_at.elapsed().as_secs());
+            let text = format!("🗑 История очищена ({} с)", remaining);
+            chat::draw_undo_toast(&painter, ctx, animated_rect, alpha, &text, toast.can_undo())
+        });
+        if undo_clicked {
+            self.undo_clear();
+        }
 
         // Draw send button
         if chat::draw_send_button(&painter, ctx, animated_rect, alpha, self.is_thinking) {
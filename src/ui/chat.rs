@@ -1,5 +1,6 @@
 /// Chat window rendering and interaction module
 use eframe::egui;
+use crate::services::{Feedback, FeedbackAction};
 
 /// Constants for chat window styling
 pub const CHAT_WINDOW_WIDTH: f32 = 350.0;
@@ -8,21 +9,45 @@ pub const CHAT_PADDING: f32 = 15.0;
 pub const SEND_BUTTON_SIZE: f32 = 35.0;
 pub const LINE_HEIGHT: f32 = 14.0;
 
+/// A message shown in the chat window
+#[derive(Debug, Clone)]
+pub struct ChatMessage {
+    pub role: String,
+    pub content: String,
+    /// uid in the conversation store (only for saved assistant replies)
+    pub uid: Option<String>,
+    pub feedback: Feedback,
+}
+
+impl ChatMessage {
+    pub fn new(role: &str, content: impl Into<String>) -> Self {
+        Self {
+            role: role.to_string(),
+            content: content.into(),
+            uid: None,
+            feedback: Feedback::default(),
+        }
+    }
+}
+
 /// Renders the animated chat window with messages
 ///
 /// # Arguments
 /// * `painter` - The painter for drawing
 /// * `animated_rect` - The rectangle with animation transformations applied
 /// * `alpha` - Transparency value (0-255)
-/// * `messages` - List of messages (role, content)
+/// * `messages` - List of messages
 /// * `is_thinking` - Whether the agent is currently processing
+///
+/// Returns the index of a message and the feedback control clicked on it
 pub fn draw_messages(
     painter: &egui::Painter,
+    ctx: &egui::Context,
     animated_rect: egui::Rect,
     alpha: u8,
-    messages: &[(String, String)],
+    messages: &[ChatMessage],
     is_thinking: bool,
-) {
+) -> Option<(usize, FeedbackAction)> {
     // Разделитель под заголовком
     let title_y = animated_rect.min.y + 15.0 + 20.0;
     painter.line_segment(
@@ -45,8 +70,10 @@ pub fn draw_messages(
     let mut y = messages_area.min.y + 5.0;
     let message_max_width = messages_area.width() - 16.0;
 
-    for (role, msg) in messages {
-        let is_user = role == "user";
+    let mut clicked = None;
+
+    for (index, message) in messages.iter().enumerate() {
+        let is_user = message.role == "user";
 
         let (bubble_color, text_color, alignment) = if is_user {
             (
@@ -74,7 +101,7 @@ pub fn draw_messages(
         y += 16.0;
 
         // Сообщение в пузыре
-        let msg_lines: Vec<&str> = msg.lines().collect();
+        let msg_lines: Vec<&str> = message.content.lines().collect();
         let mut max_msg_height = LINE_HEIGHT;
 
         for _ in &msg_lines {
@@ -101,6 +128,13 @@ pub fn draw_messages(
             text_y += LINE_HEIGHT;
         }
 
+        // Оценка ответа: 👍 👎 🔖 в правом нижнем углу пузыря
+        if !is_user && message.uid.is_some() {
+            if let Some(action) = draw_feedback_controls(painter, ctx, bubble_rect, alpha, &message.feedback) {
+                clicked = Some((index, action));
+            }
+        }
+
         y += max_msg_height + 10.0;
 
         if y > messages_area.max.y {
@@ -119,6 +153,59 @@ pub fn draw_messages(
             egui::Color32::from_rgba_unmultiplied(150, 150, 150, alpha),
         );
     }
+
+    clicked
+}
+
+/// Renders the 👍 👎 🔖 controls of a reply bubble and returns the clicked action
+fn draw_feedback_controls(
+    painter: &egui::Painter,
+    ctx: &egui::Context,
+    bubble_rect: egui::Rect,
+    alpha: u8,
+    feedback: &Feedback,
+) -> Option<FeedbackAction> {
+    let controls = [
+        ("👍", feedback.rating > 0, FeedbackAction::Rate(1)),
+        ("👎", feedback.rating < 0, FeedbackAction::Rate(-1)),
+        ("🔖", feedback.bookmarked, FeedbackAction::ToggleBookmark),
+    ];
+
+    let pointer = ctx.input(|i| i.pointer.latest_pos());
+    let mut clicked = None;
+    let mut x = bubble_rect.max.x - 10.0;
+
+    for (icon, active, action) in controls.into_iter().rev() {
+        let rect = egui::Rect::from_center_size(
+            egui::pos2(x, bubble_rect.max.y - 7.0),
+            egui::vec2(14.0, 12.0),
+        );
+        x -= 16.0;
+
+        let is_hovered = pointer.map(|p| rect.contains(p)).unwrap_or(false);
+        if active || is_hovered {
+            painter.rect_filled(
+                rect,
+                3.0,
+                egui::Color32::from_rgba_unmultiplied(190, 210, 240, alpha),
+            );
+        }
+
+        let icon_alpha = if active || is_hovered { alpha } else { alpha / 2 };
+        painter.text(
+            rect.center(),
+            egui::Align2::CENTER_CENTER,
+            icon,
+            egui::FontId::proportional(9.0),
+            egui::Color32::from_rgba_unmultiplied(60, 60, 60, icon_alpha),
+        );
+
+        if is_hovered && ctx.input(|i| i.pointer.primary_clicked()) {
+            clicked = Some(action);
+        }
+    }
+
+    clicked
 }
 
 /// Renders the send button and returns true if clicked
@@ -197,3 +284,134 @@ pub fn draw_input_field(
 
     enter_pressed
 }
+
+/// Renders the clear-history button in the title bar and returns true if clicked
+pub fn draw_clear_button(
+    painter: &egui::Painter,
+    ctx: &egui::Context,
+    animated_rect: egui::Rect,
+    alpha: u8,
+) -> bool {
+    let clear_btn_rect = egui::Rect::from_center_size(
+        egui::pos2(animated_rect.max.x - 20.0, animated_rect.min.y + 15.0),
+        egui::vec2(22.0, 22.0),
+    );
+
+    let is_hovered = ctx.input(|i| i.pointer.latest_pos())
+        .map(|p| clear_btn_rect.contains(p))
+        .unwrap_or(false);
+
+    if is_hovered {
+        painter.rect_filled(
+            clear_btn_rect,
+            4.0,
+            egui::Color32::from_rgba_unmultiplied(230, 200, 200, alpha),
+        );
+    }
+
+    painter.text(
+        clear_btn_rect.center(),
+        egui::Align2::CENTER_CENTER,
+        "🗑",
+        egui::FontId::proportional(12.0),
+        egui::Color32::from_rgba_unmultiplied(120, 120, 120, alpha),
+    );
+
+    is_hovered && ctx.input(|i| i.pointer.primary_clicked())
+}
+
+/// Renders the incognito toggle left of the clear button and returns true if clicked
+pub fn draw_incognito_button(
+    painter: &egui::Painter,
+    ctx: &egui::Context,
+    animated_rect: egui::Rect,
+    alpha: u8,
+    active: bool,
+) -> bool {
+    let btn_rect = egui::Rect::from_center_size(
+        egui::pos2(animated_rect.max.x - 46.0, animated_rect.min.y + 15.0),
+        egui::vec2(22.0, 22.0),
+    );
+
+    let is_hovered = ctx.input(|i| i.pointer.latest_pos())
+        .map(|p| btn_rect.contains(p))
+        .unwrap_or(false);
+
+    if active || is_hovered {
+        let fill = if active {
+            egui::Color32::from_rgba_unmultiplied(120, 90, 170, alpha)
+        } else {
+            egui::Color32::from_rgba_unmultiplied(215, 205, 235, alpha)
+        };
+        painter.rect_filled(btn_rect, 4.0, fill);
+    }
+
+    let icon_color = if active {
+        egui::Color32::from_rgba_unmultiplied(255, 255, 255, alpha)
+    } else {
+        egui::Color32::from_rgba_unmultiplied(120, 120, 120, alpha)
+    };
+    painter.text(
+        btn_rect.center(),
+        egui::Align2::CENTER_CENTER,
+        "🕶",
+        egui::FontId::proportional(12.0),
+        icon_color,
+    );
+
+    is_hovered && ctx.input(|i| i.pointer.primary_clicked())
+}
+
+/// Renders the undo toast above the input field and returns true if "Отменить" was clicked.
+/// A disabled button is drawn greyed out and ignores clicks
+pub fn draw_undo_toast(
+    painter: &egui::Painter,
+    ctx: &egui::Context,
+    animated_rect: egui::Rect,
+    alpha: u8,
+    text: &str,
+    enabled: bool,
+) -> bool {
+    let toast_rect = egui::Rect::from_min_size(
+        egui::pos2(animated_rect.min.x + 10.0, animated_rect.max.y - 75.0),
+        egui::vec2(animated_rect.width() - 20.0, 28.0),
+    );
+
+    painter.rect_filled(
+        toast_rect,
+        6.0,
+        egui::Color32::from_rgba_unmultiplied(60, 60, 60, alpha),
+    );
+    painter.text(
+        egui::pos2(toast_rect.min.x + 10.0, toast_rect.center().y),
+        egui::Align2::LEFT_CENTER,
+        text,
+        egui::FontId::proportional(11.0),
+        egui::Color32::WHITE,
+    );
+
+    let undo_rect = egui::Rect::from_min_max(
+        egui::pos2(toast_rect.max.x - 80.0, toast_rect.min.y),
+        toast_rect.max,
+    );
+    let is_hovered = enabled
+        && ctx.input(|i| i.pointer.latest_pos())
+            .map(|p| undo_rect.contains(p))
+            .unwrap_or(false);
+
+    painter.text(
+        undo_rect.center(),
+        egui::Align2::CENTER_CENTER,
+        "Отменить",
+        egui::FontId::proportional(11.0),
+        if !enabled {
+            egui::Color32::from_rgb(130, 130, 130)
+        } else if is_hovered {
+            egui::Color32::from_rgb(140, 220, 170)
+        } else {
+            egui::Color32::from_rgb(100, 200, 140)
+        },
+    );
+
+    is_hovered && ctx.input(|i| i.pointer.primary_clicked())
+}
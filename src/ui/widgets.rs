@@ -1,26 +1,63 @@
 /// Widget system for displaying weather, currency rates, and stats
 use eframe::egui;
+use crate::core::intents::weekday_short;
+use crate::services::storage::MaintenanceReport;
+use crate::services::{wmo, Forecast, RatePoint};
+use chrono::{Datelike, Timelike};
 
 /// Constants for widget styling
 pub const WIDGET_WIDTH: f32 = 100.0;
 pub const WIDGET_HEIGHT: f32 = 70.0;
 pub const WIDGET_PADDING: f32 = 8.0;
 pub const WIDGET_SPACING: f32 = 8.0;
+pub const FORECAST_STRIP_HEIGHT: f32 = 112.0;
+pub const RATE_CHART_HEIGHT: f32 = 112.0;
+
+/// Days shown in a currency widget's sparkline
+pub const SPARKLINE_DAYS: usize = 30;
+/// Selectable ranges of the rate chart: days and button label
+pub const RATE_CHART_RANGES: [(i64, &str); 4] = [(7, "7 д"), (30, "30 д"), (90, "90 д"), (365, "год")];
+const RANGE_BUTTON_WIDTH: f32 = 30.0;
+
+/// Hours shown in the forecast strip (every second hour of the next day)
+const STRIP_HOURS: usize = 12;
+
+/// State of the data shown by a widget
+#[derive(Clone, Debug, PartialEq)]
+pub enum WidgetStatus {
+    /// First request is in flight
+    Loading,
+    Ready,
+    /// Network is unavailable; showing cached data from `updated_at`
+    Stale,
+    /// Last refresh failed; the previous values (if any) are still shown
+    Error(String),
+}
 
 /// Weather widget data
 #[derive(Clone, Debug)]
 pub struct WeatherWidget {
     pub temperature: String,
     pub condition: String,
+    /// WMO icon for the current code and time of day
+    pub icon: String,
     pub humidity: String,
+    pub wind: String,
+    pub status: WidgetStatus,
+    /// Time of the last successful refresh (HH:MM)
+    pub updated_at: Option<String>,
 }
 
 impl Default for WeatherWidget {
     fn default() -> Self {
         Self {
-            temperature: "-- °C".to_string(),
+            temperature: "--°".to_string(),
             condition: "...".to_string(),
+            icon: "🌡️".to_string(),
             humidity: "-- %".to_string(),
+            wind: "--".to_string(),
+            status: WidgetStatus::Loading,
+            updated_at: None,
         }
     }
 }
@@ -31,6 +68,15 @@ pub struct CurrencyWidget {
     pub code: String,
     pub symbol: String,
     pub rate: String,
+    /// Where the rate came from and its date ("ЦБ РФ · 18.10")
+    pub source: Option<String>,
+    /// Daily rates for the sparkline, oldest first
+    pub sparkline: Vec<f32>,
+    /// Day-over-day change of the latest rate
+    pub change: Option<f32>,
+    pub status: WidgetStatus,
+    /// Time of the last successful refresh (HH:MM)
+    pub updated_at: Option<String>,
 }
 
 impl CurrencyWidget {
@@ -39,6 +85,92 @@ impl CurrencyWidget {
             code: code.to_string(),
             symbol: symbol.to_string(),
             rate: rate.to_string(),
+            source: None,
+            sparkline: Vec::new(),
+            change: None,
+            status: WidgetStatus::Loading,
+            updated_at: None,
+        }
+    }
+}
+
+/// Cryptocurrency price widget data
+#[derive(Clone, Debug)]
+pub struct CryptoWidget {
+    pub symbol: String,
+    pub price: String,
+    /// 24h change, percent
+    pub change_24h: Option<f32>,
+    /// Hourly prices over the last week, oldest first
+    pub sparkline: Vec<f32>,
+    pub status: WidgetStatus,
+    /// Time of the last successful refresh (HH:MM)
+    pub updated_at: Option<String>,
+}
+
+impl CryptoWidget {
+    pub fn new(symbol: &str) -> Self {
+        Self {
+            symbol: symbol.to_string(),
+            price: "—".to_string(),
+            change_24h: None,
+            sparkline: Vec::new(),
+            status: WidgetStatus::Loading,
+            updated_at: None,
+        }
+    }
+}
+
+/// Moscow Exchange stock quote widget data
+#[derive(Clone, Debug)]
+pub struct StockWidget {
+    pub ticker: String,
+    pub price: String,
+    /// Change to the previous close, percent
+    pub change_percent: Option<f32>,
+    /// Whether the exchange is trading the stock right now
+    pub trading: bool,
+    pub status: WidgetStatus,
+    /// Time of the last successful refresh (HH:MM)
+    pub updated_at: Option<String>,
+}
+
+impl StockWidget {
+    pub fn new(ticker: &str) -> Self {
+        Self {
+            ticker: ticker.to_string(),
+            price: "—".to_string(),
+            change_percent: None,
+            trading: false,
+            status: WidgetStatus::Loading,
+            updated_at: None,
+        }
+    }
+}
+
+/// Air quality widget data
+#[derive(Clone, Debug)]
+pub struct AirQualityWidget {
+    /// European AQI
+    pub aqi: String,
+    pub level: String,
+    pub icon: String,
+    /// High pollen concentration right now
+    pub pollen_warning: bool,
+    pub status: WidgetStatus,
+    /// Time of the last successful refresh (HH:MM)
+    pub updated_at: Option<String>,
+}
+
+impl Default for AirQualityWidget {
+    fn default() -> Self {
+        Self {
+            aqi: "--".to_string(),
+            level: "...".to_string(),
+            icon: "🌬️".to_string(),
+            pollen_warning: false,
+            status: WidgetStatus::Loading,
+            updated_at: None,
         }
     }
 }
@@ -85,24 +217,64 @@ pub fn draw_widget(
     );
 }
 
-/// Renders the weather widget
-pub fn draw_weather_widget(
+/// Renders the loading/error marker and the last update time in the widget's top-right corner,
+/// and an error hint on the bottom line when there is no data to show yet
+fn draw_widget_status(
     painter: &egui::Painter,
     rect: egui::Rect,
     alpha: u8,
-    weather: &WeatherWidget,
+    status: &WidgetStatus,
+    updated_at: Option<&str>,
 ) {
-    draw_widget(painter, rect, "🌡️ Погода", &weather.temperature, alpha);
+    let time = updated_at.unwrap_or("");
+    let (label, color) = match status {
+        WidgetStatus::Loading => (format!("⏳ {}", time), egui::Color32::from_rgba_unmultiplied(130, 130, 130, alpha)),
+        WidgetStatus::Ready => (time.to_string(), egui::Color32::from_rgba_unmultiplied(130, 130, 130, alpha)),
+        WidgetStatus::Stale => (format!("⌛ {}", time), egui::Color32::from_rgba_unmultiplied(200, 140, 40, alpha)),
+        WidgetStatus::Error(_) => (format!("⚠ {}", time), egui::Color32::from_rgba_unmultiplied(200, 80, 60, alpha)),
+    };
 
-    // Secondary info (humidity)
-    let info_y = rect.min.y + 50.0;
     painter.text(
-        egui::pos2(rect.min.x + WIDGET_PADDING, info_y),
-        egui::Align2::LEFT_TOP,
-        &format!("💧 {}", weather.humidity),
+        egui::pos2(rect.max.x - WIDGET_PADDING, rect.min.y + WIDGET_PADDING),
+        egui::Align2::RIGHT_TOP,
+        label.trim(),
         egui::FontId::proportional(8.0),
-        egui::Color32::from_rgba_unmultiplied(120, 120, 120, alpha),
+        color,
     );
+
+    if let (WidgetStatus::Error(_), None) = (status, updated_at) {
+        painter.text(
+            egui::pos2(rect.max.x - WIDGET_PADDING, rect.max.y - WIDGET_PADDING),
+            egui::Align2::RIGHT_BOTTOM,
+            "нет данных",
+            egui::FontId::proportional(8.0),
+            color,
+        );
+    }
+}
+
+/// Renders the weather widget
+pub fn draw_weather_widget(
+    painter: &egui::Painter,
+    rect: egui::Rect,
+    alpha: u8,
+    weather: &WeatherWidget,
+) {
+    draw_widget(painter, rect, &format!("{} Погода", weather.icon), &weather.temperature, alpha);
+
+    // Secondary info (humidity, wind)
+    if weather.updated_at.is_some() {
+        let info_y = rect.min.y + 50.0;
+        painter.text(
+            egui::pos2(rect.min.x + WIDGET_PADDING, info_y),
+            egui::Align2::LEFT_TOP,
+            format!("💧 {} 💨 {}", weather.humidity, weather.wind),
+            egui::FontId::proportional(8.0),
+            egui::Color32::from_rgba_unmultiplied(120, 120, 120, alpha),
+        );
+    }
+
+    draw_widget_status(painter, rect, alpha, &weather.status, weather.updated_at.as_deref());
 }
 
 /// Renders a currency widget
@@ -119,6 +291,274 @@ pub fn draw_currency_widget(
         &currency.rate,
         alpha,
     );
+
+    if let Some(change) = currency.change {
+        painter.text(
+            egui::pos2(rect.max.x - WIDGET_PADDING, rect.min.y + WIDGET_PADDING + 18.0),
+            egui::Align2::RIGHT_TOP,
+            format_change(change),
+            egui::FontId::proportional(8.0),
+            change_color(change, alpha),
+        );
+    }
+
+    if currency.sparkline.len() >= 2 {
+        let spark_rect = egui::Rect::from_min_max(
+            egui::pos2(rect.min.x + WIDGET_PADDING, rect.min.y + 34.0),
+            egui::pos2(rect.max.x - WIDGET_PADDING, rect.min.y + 47.0),
+        );
+        let trend = currency.sparkline[currency.sparkline.len() - 1] - currency.sparkline[0];
+        draw_sparkline(painter, spark_rect, &currency.sparkline, change_color(trend, alpha), 1.0);
+    }
+
+    if let Some(source) = &currency.source {
+        painter.text(
+            egui::pos2(rect.min.x + WIDGET_PADDING, rect.min.y + 50.0),
+            egui::Align2::LEFT_TOP,
+            source,
+            egui::FontId::proportional(8.0),
+            egui::Color32::from_rgba_unmultiplied(120, 120, 120, alpha),
+        );
+    }
+
+    draw_widget_status(painter, rect, alpha, &currency.status, currency.updated_at.as_deref());
+}
+
+/// Draws a cryptocurrency widget: price, 24h change and a week sparkline
+pub fn draw_crypto_widget(
+    painter: &egui::Painter,
+    rect: egui::Rect,
+    alpha: u8,
+    crypto: &CryptoWidget,
+) {
+    draw_widget(painter, rect, &format!("🪙 {}", crypto.symbol), &crypto.price, alpha);
+
+    if crypto.sparkline.len() >= 2 {
+        let spark_rect = egui::Rect::from_min_max(
+            egui::pos2(rect.min.x + WIDGET_PADDING, rect.min.y + 34.0),
+            egui::pos2(rect.max.x - WIDGET_PADDING, rect.min.y + 47.0),
+        );
+        let trend = crypto.sparkline[crypto.sparkline.len() - 1] - crypto.sparkline[0];
+        draw_sparkline(painter, spark_rect, &crypto.sparkline, change_color(trend, alpha), 1.0);
+    }
+
+    // The price is too wide to fit the change next to it
+    if let Some(change) = crypto.change_24h {
+        painter.text(
+            egui::pos2(rect.min.x + WIDGET_PADDING, rect.min.y + 50.0),
+            egui::Align2::LEFT_TOP,
+            format!("{}% за 24 ч", format_change(change)),
+            egui::FontId::proportional(8.0),
+            change_color(change, alpha),
+        );
+    }
+
+    draw_widget_status(painter, rect, alpha, &crypto.status, crypto.updated_at.as_deref());
+}
+
+/// Draws a stock widget: last price, change to the previous close and trading status
+pub fn draw_stock_widget(
+    painter: &egui::Painter,
+    rect: egui::Rect,
+    alpha: u8,
+    stock: &StockWidget,
+) {
+    draw_widget(painter, rect, &format!("📊 {}", stock.ticker), &stock.price, alpha);
+
+    if let Some(change) = stock.change_percent {
+        painter.text(
+            egui::pos2(rect.min.x + WIDGET_PADDING, rect.min.y + 38.0),
+            egui::Align2::LEFT_TOP,
+            format!("{}%", format_change(change)),
+            egui::FontId::proportional(9.0),
+            change_color(change, alpha),
+        );
+    }
+
+    let (label, color) = if stock.trading {
+        ("● торги идут", egui::Color32::from_rgba_unmultiplied(40, 150, 70, alpha))
+    } else {
+        ("○ торги закрыты", egui::Color32::from_rgba_unmultiplied(120, 120, 120, alpha))
+    };
+    painter.text(
+        egui::pos2(rect.min.x + WIDGET_PADDING, rect.min.y + 50.0),
+        egui::Align2::LEFT_TOP,
+        label,
+        egui::FontId::proportional(8.0),
+        color,
+    );
+
+    draw_widget_status(painter, rect, alpha, &stock.status, stock.updated_at.as_deref());
+}
+
+/// "▲ 0.35" / "▼ 1.20"
+fn format_change(change: f32) -> String {
+    if change >= 0.0 {
+        format!("▲ {:.2}", change)
+    } else {
+        format!("▼ {:.2}", -change)
+    }
+}
+
+/// Green for a rising rate, red for a falling one
+fn change_color(change: f32, alpha: u8) -> egui::Color32 {
+    if change >= 0.0 {
+        egui::Color32::from_rgba_unmultiplied(40, 150, 70, alpha)
+    } else {
+        egui::Color32::from_rgba_unmultiplied(200, 60, 50, alpha)
+    }
+}
+
+/// Draws values as a polyline scaled to fill `rect`
+fn draw_sparkline(painter: &egui::Painter, rect: egui::Rect, values: &[f32], color: egui::Color32, width: f32) {
+    let (min, max) = values
+        .iter()
+        .fold((f32::MAX, f32::MIN), |(min, max), v| (min.min(*v), max.max(*v)));
+    let span = (max - min).max(f32::EPSILON);
+    let step = rect.width() / (values.len() - 1) as f32;
+    let points: Vec<egui::Pos2> = values
+        .iter()
+        .enumerate()
+        .map(|(i, v)| egui::pos2(rect.min.x + step * i as f32, rect.max.y - (v - min) / span * rect.height()))
+        .collect();
+    painter.add(egui::Shape::line(points, egui::Stroke::new(width, color)));
+}
+
+/// Range buttons in the top-right corner of the rate chart
+pub fn rate_chart_range_rects(rect: egui::Rect) -> Vec<(i64, egui::Rect)> {
+    let inner = rect.shrink(WIDGET_PADDING);
+    RATE_CHART_RANGES
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, (days, _))| {
+            let max_x = inner.max.x - i as f32 * RANGE_BUTTON_WIDTH;
+            let button = egui::Rect::from_min_max(
+                egui::pos2(max_x - RANGE_BUTTON_WIDTH + 2.0, inner.min.y - 2.0),
+                egui::pos2(max_x, inner.min.y + 11.0),
+            );
+            (*days, button)
+        })
+        .collect()
+}
+
+/// Renders the rate chart for one currency over the selected range
+pub fn draw_rate_chart(
+    painter: &egui::Painter,
+    rect: egui::Rect,
+    alpha: u8,
+    currency: &CurrencyWidget,
+    points: &[RatePoint],
+    days: i64,
+) {
+    painter.rect_filled(rect, 8.0, egui::Color32::from_rgba_unmultiplied(250, 250, 250, alpha));
+    painter.rect_stroke(
+        rect,
+        8.0,
+        egui::Stroke::new(1.0, egui::Color32::from_rgba_unmultiplied(200, 200, 200, alpha)),
+        egui::epaint::StrokeKind::Outside,
+    );
+
+    let dim = egui::Color32::from_rgba_unmultiplied(120, 120, 120, alpha);
+    let dark = egui::Color32::from_rgba_unmultiplied(40, 40, 40, alpha);
+    let inner = rect.shrink(WIDGET_PADDING);
+
+    // Title with the change over the whole range
+    let mut title = format!("{} {} · {}", currency.symbol, currency.code, currency.rate);
+    if let (Some(first), Some(last)) = (points.first(), points.last()) {
+        let percent = (last.rate - first.rate) / first.rate * 100.0;
+        title.push_str(&format!("  {}%", format_change(percent as f32)));
+    }
+    painter.text(inner.min, egui::Align2::LEFT_TOP, title, egui::FontId::proportional(9.0), dark);
+
+    for ((range_days, label), (_, button)) in RATE_CHART_RANGES.iter().zip(rate_chart_range_rects(rect).iter().rev()) {
+        let selected = *range_days == days;
+        if selected {
+            painter.rect_filled(*button, 4.0, egui::Color32::from_rgba_unmultiplied(225, 230, 240, alpha));
+        }
+        painter.text(
+            button.center(),
+            egui::Align2::CENTER_CENTER,
+            *label,
+            egui::FontId::proportional(8.0),
+            if selected { dark } else { dim },
+        );
+    }
+
+    if points.len() < 2 {
+        painter.text(
+            inner.center(),
+            egui::Align2::CENTER_CENTER,
+            "Нет истории курса",
+            egui::FontId::proportional(9.0),
+            dim,
+        );
+        return;
+    }
+
+    let plot = egui::Rect::from_min_max(
+        egui::pos2(inner.min.x + 34.0, inner.min.y + 18.0),
+        egui::pos2(inner.max.x, inner.max.y - 12.0),
+    );
+    painter.rect_stroke(
+        plot,
+        0.0,
+        egui::Stroke::new(1.0, egui::Color32::from_rgba_unmultiplied(230, 230, 230, alpha)),
+        egui::epaint::StrokeKind::Inside,
+    );
+
+    let values: Vec<f32> = points.iter().map(|p| p.rate as f32).collect();
+    let trend = values[values.len() - 1] - values[0];
+    draw_sparkline(painter, plot.shrink(2.0), &values, change_color(trend, alpha), 1.5);
+
+    // Min/max on the left, first/last date below
+    let (min, max) = values
+        .iter()
+        .fold((f32::MAX, f32::MIN), |(min, max), v| (min.min(*v), max.max(*v)));
+    let small = egui::FontId::proportional(7.0);
+    painter.text(egui::pos2(inner.min.x, plot.min.y), egui::Align2::LEFT_TOP, format!("{:.2}", max), small.clone(), dim);
+    painter.text(egui::pos2(inner.min.x, plot.max.y), egui::Align2::LEFT_BOTTOM, format!("{:.2}", min), small.clone(), dim);
+    painter.text(
+        egui::pos2(plot.min.x, inner.max.y),
+        egui::Align2::LEFT_BOTTOM,
+        points[0].date.format("%d.%m.%y").to_string(),
+        small.clone(),
+        dim,
+    );
+    painter.text(
+        egui::pos2(plot.max.x, inner.max.y),
+        egui::Align2::RIGHT_BOTTOM,
+        points[points.len() - 1].date.format("%d.%m.%y").to_string(),
+        small,
+        dim,
+    );
+}
+
+/// Renders the compact air quality widget: AQI with its level and a pollen marker
+pub fn draw_air_quality_widget(
+    painter: &egui::Painter,
+    rect: egui::Rect,
+    alpha: u8,
+    air: &AirQualityWidget,
+) {
+    draw_widget(painter, rect, &format!("{} Воздух", air.icon), &air.aqi, alpha);
+
+    if air.updated_at.is_some() {
+        let label = if air.pollen_warning {
+            format!("{} 🌼", air.level)
+        } else {
+            air.level.clone()
+        };
+        painter.text(
+            egui::pos2(rect.max.x - WIDGET_PADDING, rect.min.y + WIDGET_PADDING + 16.0),
+            egui::Align2::RIGHT_TOP,
+            label,
+            egui::FontId::proportional(8.0),
+            egui::Color32::from_rgba_unmultiplied(120, 120, 120, alpha),
+        );
+    }
+
+    draw_widget_status(painter, rect, alpha, &air.status, air.updated_at.as_deref());
 }
 
 /// Renders the stats widget
@@ -127,7 +567,129 @@ pub fn draw_stats_widget(
     rect: egui::Rect,
     alpha: u8,
     messages_count: usize,
+    maintenance: Option<&MaintenanceReport>,
 ) {
     let content = format!("{}", messages_count);
     draw_widget(painter, rect, "📊 Сообщений", &content, alpha);
+
+    // Итог последнего обслуживания БД: время и число удалённых сообщений
+    if let Some(report) = maintenance {
+        painter.text(
+            egui::pos2(rect.max.x - WIDGET_PADDING, rect.min.y + WIDGET_PADDING),
+            egui::Align2::RIGHT_TOP,
+            format!("🧹 {} −{}", report.finished_at, report.deleted),
+            egui::FontId::proportional(8.0),
+            egui::Color32::from_rgba_unmultiplied(120, 120, 120, alpha),
+        );
+        painter.text(
+            egui::pos2(rect.max.x - WIDGET_PADDING, rect.min.y + WIDGET_PADDING + 12.0),
+            egui::Align2::RIGHT_TOP,
+            format!("{} КБ", report.size_after / 1024),
+            egui::FontId::proportional(8.0),
+            egui::Color32::from_rgba_unmultiplied(120, 120, 120, alpha),
+        );
+    }
+}
+
+/// Renders the forecast strip: hourly temperatures on top, 7 days below
+pub fn draw_forecast_strip(
+    painter: &egui::Painter,
+    rect: egui::Rect,
+    alpha: u8,
+    forecast: &Forecast,
+) {
+    painter.rect_filled(rect, 8.0, egui::Color32::from_rgba_unmultiplied(250, 250, 250, alpha));
+    painter.rect_stroke(
+        rect,
+        8.0,
+        egui::Stroke::new(1.0, egui::Color32::from_rgba_unmultiplied(200, 200, 200, alpha)),
+        egui::epaint::StrokeKind::Outside,
+    );
+
+    let dim = egui::Color32::from_rgba_unmultiplied(120, 120, 120, alpha);
+    let dark = egui::Color32::from_rgba_unmultiplied(40, 40, 40, alpha);
+    let rain = egui::Color32::from_rgba_unmultiplied(60, 110, 200, alpha);
+    let inner = rect.shrink(WIDGET_PADDING);
+
+    // Hourly row
+    let hours: Vec<_> = forecast.hourly.iter().step_by(2).take(STRIP_HOURS).collect();
+    let hour_width = inner.width() / STRIP_HOURS as f32;
+    for (i, hour) in hours.iter().enumerate() {
+        let x = inner.min.x + hour_width * (i as f32 + 0.5);
+        painter.text(
+            egui::pos2(x, inner.min.y),
+            egui::Align2::CENTER_TOP,
+            format!("{:02}", hour.time.hour()),
+            egui::FontId::proportional(8.0),
+            dim,
+        );
+        painter.text(
+            egui::pos2(x, inner.min.y + 11.0),
+            egui::Align2::CENTER_TOP,
+            format!("{}{:+.0}°", wmo::icon(hour.weather_code, hour.is_day), hour.temperature),
+            egui::FontId::proportional(9.0),
+            dark,
+        );
+        if hour.precipitation_probability > 0 {
+            painter.text(
+                egui::pos2(x, inner.min.y + 24.0),
+                egui::Align2::CENTER_TOP,
+                format!("{}%", hour.precipitation_probability),
+                egui::FontId::proportional(7.0),
+                rain,
+            );
+        }
+    }
+
+    let separator_y = inner.min.y + 38.0;
+    painter.line_segment(
+        [egui::pos2(inner.min.x, separator_y), egui::pos2(inner.max.x, separator_y)],
+        egui::Stroke::new(1.0, egui::Color32::from_rgba_unmultiplied(220, 220, 220, alpha)),
+    );
+
+    // Daily row
+    let day_width = inner.width() / forecast.daily.len().max(1) as f32;
+    let top = separator_y + 5.0;
+    for (i, day) in forecast.daily.iter().enumerate() {
+        let x = inner.min.x + day_width * (i as f32 + 0.5);
+        painter.text(
+            egui::pos2(x, top),
+            egui::Align2::CENTER_TOP,
+            weekday_short(day.date.weekday()),
+            egui::FontId::proportional(8.0),
+            dim,
+        );
+        painter.text(
+            egui::pos2(x, top + 11.0),
+            egui::Align2::CENTER_TOP,
+            wmo::icon(day.weather_code, true),
+            egui::FontId::proportional(11.0),
+            dark,
+        );
+        painter.text(
+            egui::pos2(x, top + 26.0),
+            egui::Align2::CENTER_TOP,
+            format!("{:+.0}°/{:+.0}°", day.temperature_max, day.temperature_min),
+            egui::FontId::proportional(8.0),
+            dark,
+        );
+        painter.text(
+            egui::pos2(x, top + 38.0),
+            egui::Align2::CENTER_TOP,
+            format!("💧{}% 💨{:.0}", day.precipitation_probability, day.wind_speed_max),
+            egui::FontId::proportional(7.0),
+            if day.is_wet() { rain } else { dim },
+        );
+    }
+
+    // Sunrise/sunset for today in the corner of the daily row
+    if let Some((sunrise, sunset)) = forecast.daily.first().and_then(|d| d.sunrise.zip(d.sunset)) {
+        painter.text(
+            egui::pos2(inner.max.x, inner.max.y),
+            egui::Align2::RIGHT_BOTTOM,
+            format!("🌅 {} – {}", sunrise.format("%H:%M"), sunset.format("%H:%M")),
+            egui::FontId::proportional(7.0),
+            dim,
+        );
+    }
 }